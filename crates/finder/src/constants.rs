@@ -0,0 +1,531 @@
+use crate::finder_types::{Branch, FinderType};
+use crate::functions::MAX_CALL_DEPTH;
+use crate::symbols::ModuleConstants;
+use rustpython_parser::ast;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// SQL string reported for a name, by the offset it starts at and the name
+pub(crate) type Reported = (usize, String);
+
+pub(crate) type Bindings = HashMap<String, Binding>;
+
+/// What is known about a name at some point of the code
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Binding {
+    /// String, variants of strings or number the name holds, `None` if it isn't known
+    pub value: Option<FinderType>,
+    /// SQL strings reported for the value, with the blocks they were reported in
+    pub reported: Vec<(Reported, usize)>,
+    /// File of the module the name was imported as
+    pub module: Option<PathBuf>,
+}
+
+impl Binding {
+    pub const fn is_known(&self) -> bool {
+        self.value.is_some() || self.module.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ScopeKind {
+    Module,
+    Class,
+    Function,
+}
+
+#[derive(Debug)]
+struct Scope {
+    kind: ScopeKind,
+    bindings: Bindings,
+    // Names a function assigns to, which are local to all of its body
+    locals: HashSet<String>,
+}
+
+/// String values known for the names of a module while its statements are walked in order.
+/// Functions only rely on module names that are assigned once, as they may run at any time.
+#[derive(Debug, Default)]
+pub(crate) struct Constants {
+    // Innermost scope last
+    scopes: Vec<Scope>,
+    module: Bindings,
+    superseded: HashSet<Reported>,
+    block: usize,
+    blocks: usize,
+    // Module functions that calls are followed into
+    functions: HashMap<String, Arc<ast::StmtFunctionDef>>,
+    // Parameters all calls of a function pass the same string to, by function
+    parameters: HashMap<String, Bindings>,
+    // Functions being evaluated for a call, innermost last, with what they returned so far
+    calls: Vec<(String, Vec<Option<Binding>>)>,
+}
+
+impl Constants {
+    pub fn push(&mut self, kind: ScopeKind, locals: HashSet<String>) {
+        self.scopes.push(Scope {
+            kind,
+            bindings: Bindings::new(),
+            locals,
+        });
+    }
+
+    pub fn pop(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Enters a module where only the names assigned once are known
+    pub fn push_module_constants(&mut self) {
+        self.scopes.push(Scope {
+            kind: ScopeKind::Module,
+            bindings: self.module.clone(),
+            locals: HashSet::new(),
+        });
+    }
+
+    /// Sets a module name that is assigned once to a known string, number or module
+    pub fn set_module_constant(&mut self, name: &str, binding: Binding) {
+        self.module.insert(name.to_string(), binding);
+    }
+
+    /// Module names assigned once to a string without runtime values, which other modules
+    /// can import
+    pub fn module_strings(&self) -> ModuleConstants {
+        self.module
+            .iter()
+            .filter_map(|(name, binding)| {
+                let Some(FinderType::Str(value)) = &binding.value else {
+                    return None;
+                };
+                value
+                    .splices()
+                    .is_empty()
+                    .then(|| (name.clone(), value.to_string()))
+            })
+            .collect()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Binding> {
+        for scope in self.scopes.iter().rev() {
+            match scope.kind {
+                ScopeKind::Module => return scope.bindings.get(name).cloned(),
+                ScopeKind::Class => {
+                    if let Some(binding) = scope.bindings.get(name) {
+                        return Some(binding.clone());
+                    }
+                }
+                ScopeKind::Function if scope.locals.contains(name) => {
+                    return scope.bindings.get(name).cloned();
+                }
+                // Module constants that were already reached keep the SQL reported for them
+                ScopeKind::Function => {
+                    let constant = self.module.get(name)?;
+                    let module = self.scopes.first()?.bindings.get(name);
+                    return Some(module.filter(|b| b.is_known()).unwrap_or(constant).clone());
+                }
+            }
+        }
+        None
+    }
+
+    pub fn bind(&mut self, name: &str, binding: Binding) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+        // Names declared `global` in a function aren't tracked
+        if scope.kind != ScopeKind::Function || scope.locals.contains(name) {
+            scope.bindings.insert(name.to_string(), binding);
+        }
+    }
+
+    /// Forgets the values of `names`, which were assigned something unknown
+    pub fn invalidate<'a>(&mut self, names: impl IntoIterator<Item = &'a String>) {
+        for name in names {
+            self.bind(name, Binding::default());
+        }
+    }
+
+    /// Bindings of the innermost scope, to restore or merge after a branch
+    pub fn snapshot(&self) -> Bindings {
+        self.scopes
+            .last()
+            .map(|scope| scope.bindings.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn restore(&mut self, bindings: Bindings) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.bindings = bindings;
+        }
+    }
+
+    /// Bindings after either of two branches ran, names they disagree on aren't known
+    pub fn merge(a: &Bindings, b: &Bindings) -> Bindings {
+        a.keys()
+            .chain(b.keys())
+            .map(|name| {
+                let binding = match (a.get(name), b.get(name)) {
+                    (Some(a), Some(b)) if a == b => a.clone(),
+                    _ => Binding::default(),
+                };
+                (name.clone(), binding)
+            })
+            .collect()
+    }
+
+    /// Bindings after the body or the else branch of an `if` ran. Strings a name holds in
+    /// either become its variants, and the SQL reported in both branches is reported for the
+    /// value of this block.
+    pub fn merge_branches(
+        &self,
+        taken: &Bindings,
+        not_taken: &Bindings,
+        branch: &Branch,
+        max_variants: usize,
+    ) -> Bindings {
+        taken
+            .keys()
+            .chain(not_taken.keys())
+            .map(|name| {
+                let binding = match (taken.get(name), not_taken.get(name)) {
+                    (Some(a), Some(b)) if a == b => a.clone(),
+                    (Some(a), Some(b)) => self.merge_branch_values(a, b, branch, max_variants),
+                    _ => Binding::default(),
+                };
+                (name.clone(), binding)
+            })
+            .collect()
+    }
+
+    fn merge_branch_values(
+        &self,
+        taken: &Binding,
+        not_taken: &Binding,
+        branch: &Branch,
+        max_variants: usize,
+    ) -> Binding {
+        let (Some(a), Some(b), None, None) = (
+            &taken.value,
+            &not_taken.value,
+            &taken.module,
+            &not_taken.module,
+        ) else {
+            return Binding::default();
+        };
+        let value = FinderType::either(a.clone(), b.clone(), branch, max_variants);
+        let mut reported: Vec<(Reported, usize)> = Vec::new();
+        if !taken.reported.is_empty() && !not_taken.reported.is_empty() {
+            for (sql, _) in taken.reported.iter().chain(&not_taken.reported) {
+                if !reported.iter().any(|(r, _)| r == sql) {
+                    reported.push((sql.clone(), self.block));
+                }
+            }
+        }
+        Binding {
+            value: (!value.is_placeholder()).then_some(value),
+            reported,
+            module: None,
+        }
+    }
+
+    /// Starts a block of statements that run one after another, returning the enclosing block
+    pub fn enter_block(&mut self) -> usize {
+        self.blocks += 1;
+        std::mem::replace(&mut self.block, self.blocks)
+    }
+
+    pub fn exit_block(&mut self, enclosing: usize) {
+        self.block = enclosing;
+    }
+
+    pub const fn block(&self) -> usize {
+        self.block
+    }
+
+    pub fn set_function(&mut self, name: &str, function: Arc<ast::StmtFunctionDef>) {
+        self.functions.insert(name.to_string(), function);
+    }
+
+    /// Module function a name refers to, unless a function or class around shadows it
+    pub fn function(&self, name: &str) -> Option<Arc<ast::StmtFunctionDef>> {
+        let shadowed = self.scopes.iter().any(|scope| match scope.kind {
+            ScopeKind::Module => false,
+            ScopeKind::Class => scope.bindings.contains_key(name),
+            ScopeKind::Function => scope.locals.contains(name),
+        });
+        if shadowed {
+            return None;
+        }
+        self.functions.get(name).cloned()
+    }
+
+    pub fn set_parameters(&mut self, function: &str, parameters: Bindings) {
+        self.parameters.insert(function.to_string(), parameters);
+    }
+
+    pub fn parameters(&self, function: &str) -> Bindings {
+        self.parameters.get(function).cloned().unwrap_or_default()
+    }
+
+    /// Starts evaluating the body of a function for a call, `false` if it's recursive or the
+    /// calls are nested too deep
+    pub fn enter_call(&mut self, function: &str) -> bool {
+        if self.calls.len() >= MAX_CALL_DEPTH || self.calls.iter().any(|(f, _)| f == function) {
+            return false;
+        }
+        self.calls.push((function.to_string(), Vec::new()));
+        true
+    }
+
+    /// Ends evaluating a function, returning what its `return` statements returned
+    pub fn exit_call(&mut self) -> Vec<Option<Binding>> {
+        self.calls
+            .pop()
+            .map(|(_, returns)| returns)
+            .unwrap_or_default()
+    }
+
+    /// Records what a `return` statement returns, `None` if it returns nothing
+    pub fn add_return(&mut self, binding: Option<Binding>) {
+        if let Some((_, returns)) = self.calls.last_mut() {
+            returns.push(binding);
+        }
+    }
+
+    /// Drops the SQL reported for a value that a later statement of the same block extended,
+    /// only the final value is checked. Evaluating a call doesn't change what is reported.
+    pub fn supersede(&mut self, previous: &Binding) {
+        if !self.calls.is_empty() {
+            return;
+        }
+        for (reported, block) in &previous.reported {
+            if *block == self.block {
+                self.superseded.insert(reported.clone());
+            }
+        }
+    }
+
+    pub fn is_superseded(&self, reported: &Reported) -> bool {
+        self.superseded.contains(reported)
+    }
+}
+
+/// Names a block of statements assigns to, not counting those in nested functions and classes
+pub(crate) fn assigned_names(body: &[ast::Stmt]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    visit_assignments(body, &mut |name| {
+        names.insert(name.to_string());
+    });
+    names
+}
+
+/// How many times a block of statements assigns to each name
+pub(crate) fn assignment_counts(body: &[ast::Stmt]) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    visit_assignments(body, &mut |name| {
+        *counts.entry(name.to_string()).or_default() += 1;
+    });
+    counts
+}
+
+/// Names a function can see and assign to: its parameters and the names it assigns to,
+/// without the ones declared `global` or `nonlocal`
+pub(crate) fn function_locals(args: &ast::Arguments, body: &[ast::Stmt]) -> HashSet<String> {
+    let globals = declared_globals(body);
+    args.posonlyargs
+        .iter()
+        .chain(&args.args)
+        .chain(&args.kwonlyargs)
+        .map(|arg| &arg.def)
+        .chain(args.vararg.as_deref())
+        .chain(args.kwarg.as_deref())
+        .map(|arg| arg.arg.to_string())
+        .chain(assigned_names(body))
+        .filter(|name| !globals.contains(name))
+        .collect()
+}
+
+/// Names declared `global` or `nonlocal` anywhere in a block, nested functions included
+pub(crate) fn declared_globals(body: &[ast::Stmt]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for stmt in body {
+        match stmt {
+            ast::Stmt::Global(ast::StmtGlobal {
+                names: declared, ..
+            })
+            | ast::Stmt::Nonlocal(ast::StmtNonlocal {
+                names: declared, ..
+            }) => {
+                names.extend(declared.iter().map(ToString::to_string));
+            }
+            _ => {
+                for block in blocks(stmt) {
+                    names.extend(declared_globals(block));
+                }
+                if let ast::Stmt::FunctionDef(ast::StmtFunctionDef { body, .. })
+                | ast::Stmt::AsyncFunctionDef(ast::StmtAsyncFunctionDef { body, .. })
+                | ast::Stmt::ClassDef(ast::StmtClassDef { body, .. }) = stmt
+                {
+                    names.extend(declared_globals(body));
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Names bound by an assignment target like `a`, `(a, *b)` or `[a, b]`
+pub(crate) fn target_names(target: &ast::Expr, f: &mut impl FnMut(&str)) {
+    match target {
+        ast::Expr::Name(name) => f(&name.id),
+        ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+        | ast::Expr::List(ast::ExprList { elts, .. }) => {
+            for elt in elts {
+                target_names(elt, f);
+            }
+        }
+        ast::Expr::Starred(starred) => target_names(&starred.value, f),
+        _ => {}
+    }
+}
+
+/// Whether an expression building a string reads `name`
+pub(crate) fn mentions(expr: &ast::Expr, name: &str) -> bool {
+    match expr {
+        ast::Expr::Name(n) => n.id.as_str() == name,
+        ast::Expr::BinOp(b) => mentions(&b.left, name) || mentions(&b.right, name),
+        ast::Expr::JoinedStr(j) => j.values.iter().any(|v| mentions(v, name)),
+        ast::Expr::FormattedValue(f) => mentions(&f.value, name),
+        ast::Expr::Attribute(a) => mentions(&a.value, name),
+        ast::Expr::IfExp(i) => {
+            mentions(&i.test, name) || mentions(&i.body, name) || mentions(&i.orelse, name)
+        }
+        ast::Expr::Call(c) => {
+            mentions(&c.func, name)
+                || c.args.iter().any(|a| mentions(a, name))
+                || c.keywords.iter().any(|k| mentions(&k.value, name))
+        }
+        ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+        | ast::Expr::List(ast::ExprList { elts, .. }) => elts.iter().any(|e| mentions(e, name)),
+        _ => false,
+    }
+}
+
+fn visit_assignments(body: &[ast::Stmt], f: &mut impl FnMut(&str)) {
+    for stmt in body {
+        match stmt {
+            ast::Stmt::Assign(ast::StmtAssign { targets, .. })
+            | ast::Stmt::Delete(ast::StmtDelete { targets, .. }) => {
+                for target in targets {
+                    target_names(target, f);
+                }
+            }
+            ast::Stmt::AnnAssign(ast::StmtAnnAssign { target, .. })
+            | ast::Stmt::AugAssign(ast::StmtAugAssign { target, .. })
+            | ast::Stmt::For(ast::StmtFor { target, .. })
+            | ast::Stmt::AsyncFor(ast::StmtAsyncFor { target, .. }) => target_names(target, f),
+            ast::Stmt::With(ast::StmtWith { items, .. })
+            | ast::Stmt::AsyncWith(ast::StmtAsyncWith { items, .. }) => {
+                for item in items {
+                    if let Some(vars) = &item.optional_vars {
+                        target_names(vars, f);
+                    }
+                }
+            }
+            ast::Stmt::FunctionDef(ast::StmtFunctionDef { name, .. })
+            | ast::Stmt::AsyncFunctionDef(ast::StmtAsyncFunctionDef { name, .. })
+            | ast::Stmt::ClassDef(ast::StmtClassDef { name, .. }) => f(name),
+            ast::Stmt::Import(ast::StmtImport { names, .. })
+            | ast::Stmt::ImportFrom(ast::StmtImportFrom { names, .. }) => {
+                for alias in names {
+                    let name = alias.asname.as_ref().unwrap_or(&alias.name);
+                    f(name.split('.').next().unwrap_or_default());
+                }
+            }
+            ast::Stmt::Try(ast::StmtTry { handlers, .. })
+            | ast::Stmt::TryStar(ast::StmtTryStar { handlers, .. }) => {
+                for handler in handlers {
+                    if let Some(name) = &handler.as_except_handler().and_then(|h| h.name.as_ref()) {
+                        f(name);
+                    }
+                }
+            }
+            _ => {}
+        }
+        for block in blocks(stmt) {
+            visit_assignments(block, f);
+        }
+    }
+}
+
+/// Absolute modules a block imports anywhere, nested functions and classes included, in order.
+/// `from a import b` imports `a` and, in case it's a submodule, `a.b`.
+pub(crate) fn imported_modules(body: &[ast::Stmt]) -> Vec<String> {
+    let mut modules = Vec::new();
+    for stmt in body {
+        match stmt {
+            ast::Stmt::Import(ast::StmtImport { names, .. }) => {
+                modules.extend(names.iter().map(|alias| alias.name.to_string()));
+            }
+            ast::Stmt::ImportFrom(ast::StmtImportFrom {
+                module: Some(module),
+                names,
+                level,
+                ..
+            }) if level.as_ref().map_or(0, ast::Int::to_usize) == 0 => {
+                modules.push(module.to_string());
+                modules.extend(names.iter().map(|alias| format!("{module}.{}", alias.name)));
+            }
+            ast::Stmt::FunctionDef(ast::StmtFunctionDef { body, .. })
+            | ast::Stmt::AsyncFunctionDef(ast::StmtAsyncFunctionDef { body, .. })
+            | ast::Stmt::ClassDef(ast::StmtClassDef { body, .. }) => {
+                modules.extend(imported_modules(body));
+            }
+            _ => {
+                for block in blocks(stmt) {
+                    modules.extend(imported_modules(block));
+                }
+            }
+        }
+    }
+    modules
+}
+
+/// Blocks of a compound statement that run in the same scope
+fn blocks(stmt: &ast::Stmt) -> Vec<&[ast::Stmt]> {
+    match stmt {
+        ast::Stmt::For(ast::StmtFor { body, orelse, .. })
+        | ast::Stmt::AsyncFor(ast::StmtAsyncFor { body, orelse, .. })
+        | ast::Stmt::While(ast::StmtWhile { body, orelse, .. })
+        | ast::Stmt::If(ast::StmtIf { body, orelse, .. }) => vec![body, orelse],
+        ast::Stmt::With(ast::StmtWith { body, .. })
+        | ast::Stmt::AsyncWith(ast::StmtAsyncWith { body, .. }) => vec![body],
+        ast::Stmt::Try(ast::StmtTry {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+            ..
+        })
+        | ast::Stmt::TryStar(ast::StmtTryStar {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+            ..
+        }) => {
+            let mut blocks: Vec<&[ast::Stmt]> = vec![body, orelse, finalbody];
+            blocks.extend(
+                handlers
+                    .iter()
+                    .filter_map(|h| h.as_except_handler())
+                    .map(|h| h.body.as_slice()),
+            );
+            blocks
+        }
+        ast::Stmt::Match(ast::StmtMatch { cases, .. }) => {
+            cases.iter().map(|case| case.body.as_slice()).collect()
+        }
+        _ => vec![],
+    }
+}
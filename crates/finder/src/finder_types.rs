@@ -1,12 +1,17 @@
-use std::ops::{Add, Div, Mul, Sub};
+use std::collections::HashMap;
+use std::ops::{Add, Deref, Div, Mul, Sub};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::{env, fmt};
 
-use globset::{Glob, GlobSet, GlobSetBuilder};
+use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
 use logging::{always_log, error};
 use regex::Regex;
+use rustpython_parser::text_size::TextRange;
+use sqlparser::dialect::{Dialect, GenericDialect};
 
-use crate::preanalysis::ByteRange;
+use crate::preanalysis::{ByteRange, LineCol, SourceMap};
+use crate::presets::{ApiFunction, PRESETS, ParamStyle, is_within};
 
 // Internal result type for processing
 #[derive(Debug, Clone)]
@@ -14,6 +19,29 @@ pub struct SqlResult {
     pub byte_range: ByteRange,
     pub variable_name: String,
     pub content: FinderType,
+    pub params: Option<CallParams>,
+    pub context: Context,
+    pub param_styles: &'static [ParamStyle],
+    /// Dialect named by the `function_contexts` entry of the called function
+    pub dialect: Option<String>,
+}
+
+/// Config pattern a SQL string was found by
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Context {
+    /// `variable_contexts` pattern matching the name assigned to
+    Variable(String),
+    /// `function_contexts` pattern matching the called function
+    Function(String),
+}
+
+/// Parameters passed as a literal along with the SQL, e.g. `cursor.execute(sql, (a, b))`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallParams {
+    /// Tuple or list of this many values
+    Positional(usize),
+    /// Dict with these keys
+    Named(Vec<String>),
 }
 
 #[derive(Debug, Clone)]
@@ -21,6 +49,40 @@ pub struct SqlExtract {
     pub file_path: String,
     pub strings: Vec<SqlString>,
     pub rel_path: String,
+    /// For notebooks, the cells the lines of the extracted positions belong to
+    pub cells: Option<CellMap>,
+    /// Database drivers the file imports, telling the dialect of its SQL
+    pub drivers: Vec<DriverImport>,
+}
+
+/// Imported module of a database driver and the dialect of the database it connects to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriverImport {
+    pub module: String,
+    pub dialect: String,
+}
+
+/// Maps lines of a notebook's joined code cells back to the cells
+#[derive(Debug, Clone, Default)]
+pub struct CellMap {
+    // First joined line and number of each cell
+    starts: Vec<(usize, usize)>,
+}
+
+impl CellMap {
+    pub(crate) fn push(&mut self, first_line: usize, cell: usize) {
+        self.starts.push((first_line, cell));
+    }
+
+    /// Returns the cell number, counting code cells from 1, and the line within that cell.
+    #[must_use]
+    pub fn locate(&self, line: usize) -> (usize, usize) {
+        let index = self.starts.partition_point(|(first, _)| *first <= line);
+        index.checked_sub(1).map_or((0, line), |index| {
+            let (first, cell) = self.starts[index];
+            (cell, line - first + 1)
+        })
+    }
 }
 
 impl SqlExtract {
@@ -30,20 +92,36 @@ impl SqlExtract {
             .file_name()
             .expect("Current directory should have a name");
 
-        let relative_part = Path::new(&file_path)
-            .strip_prefix(&cwd)
-            .expect("Should always be able to strip prefix cwd from curr_file.");
-
-        let mut full_rel_path = PathBuf::new();
-        full_rel_path.push(cwd_name);
-        full_rel_path.push(relative_part);
-
-        let rel_path = full_rel_path.to_string_lossy().to_string();
+        // Files outside of the cwd keep their full path
+        let rel_path = Path::new(&file_path).strip_prefix(&cwd).map_or_else(
+            |_| file_path.clone(),
+            |relative_part| {
+                let mut full_rel_path = PathBuf::new();
+                full_rel_path.push(cwd_name);
+                full_rel_path.push(relative_part);
+                full_rel_path.to_string_lossy().to_string()
+            },
+        );
 
         Self {
             file_path,
             strings,
             rel_path,
+            cells: None,
+            drivers: Vec::new(),
+        }
+    }
+
+    /// Cell and line within it of a line of the file. Lines of notebooks are counted within
+    /// their cell, other files have no cells.
+    #[must_use]
+    pub fn locate(&self, line: usize) -> (Option<usize>, usize) {
+        match &self.cells {
+            Some(cells) => {
+                let (cell, line) = cells.locate(line);
+                (Some(cell), line)
+            }
+            None => (None, line),
         }
     }
 }
@@ -54,9 +132,83 @@ pub struct SqlString {
     pub variable_name: String,
     pub sql_content: String,
     pub range: crate::preanalysis::Range,
+    /// Source text of `range`
+    pub source: String,
+    pub source_map: SourceMap,
+    /// Runtime values spliced into `sql_content`, in order of appearance
+    pub interpolations: Vec<Interpolation>,
+    /// Parameters of the call the SQL was passed to, if given as a literal
+    pub params: Option<CallParams>,
+    /// Parameter styles of the preset function the SQL was passed to
+    pub param_styles: Vec<ParamStyle>,
+    /// Pattern the SQL was found by, `None` for raw SQL
+    pub context: Option<Context>,
+    /// Dialect named by a `# sqint: dialect=...` pragma or the `function_contexts` entry of the
+    /// called function, `None` for the configured one
+    pub dialect: Option<String>,
+    /// Branches of `if` statements and conditional expressions this SQL is built in
+    pub branches: Vec<Branch>,
+    /// SQL built in other branches, each with its own `branches`
+    pub alternatives: Vec<SqlString>,
+}
+
+/// Way the condition of an `if` statement or conditional expression went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+    /// Source of the condition
+    pub condition: String,
+    pub line: usize,
+    pub taken: bool,
+}
+
+impl Branch {
+    /// Whether the branches can't both be taken, being opposite ways of the same condition
+    fn contradicts(&self, other: &Self) -> bool {
+        self.condition == other.condition && self.line == other.line && self.taken != other.taken
+    }
+}
+
+impl fmt::Display for Branch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` is {} on line {}",
+            self.condition, self.taken, self.line
+        )
+    }
+}
+
+/// Python value only known at runtime that was spliced into the SQL text
+#[derive(Debug, Clone)]
+pub struct Interpolation {
+    pub kind: InterpolationKind,
+    /// Source of the Python expression the value comes from
+    pub expr: String,
+    /// Source position of the expression
+    pub position: LineCol,
+    /// Byte range of the value's stand-in within `sql_content`
+    pub range: std::ops::Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterpolationKind {
+    FString,
+    PercentFormat,
+    FormatCall,
+}
+
+impl fmt::Display for InterpolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FString => write!(f, "an f-string"),
+            Self::PercentFormat => write!(f, "%-formatting"),
+            Self::FormatCall => write!(f, "str.format()"),
+        }
+    }
 }
 
 impl SqlString {
+    /// Creates a SQL string whose content is copied verbatim from the source, starting at `range`.
     pub fn new(
         variable_name: String,
         sql_content: String,
@@ -64,10 +216,46 @@ impl SqlString {
     ) -> Self {
         Self {
             variable_name,
+            source: sql_content.clone(),
             sql_content,
+            source_map: SourceMap::verbatim(range.start.clone()),
             range,
+            interpolations: Vec::new(),
+            params: None,
+            param_styles: Vec::new(),
+            context: None,
+            dialect: None,
+            branches: Vec::new(),
+            alternatives: Vec::new(),
         }
     }
+
+    /// This SQL followed by its alternatives
+    pub fn variants(&self) -> impl Iterator<Item = &Self> {
+        std::iter::once(self).chain(&self.alternatives)
+    }
+
+    /// Returns the source position of the byte at `offset` in `sql_content`.
+    #[must_use]
+    pub fn position_at(&self, offset: usize) -> LineCol {
+        self.source_map
+            .locate(&self.sql_content, offset)
+            .unwrap_or_else(|| self.range.start.clone())
+    }
+
+    /// SQL with the runtime values shown as the Python expressions they come from,
+    /// like `SELECT * FROM users WHERE id = {user_id}`
+    #[must_use]
+    pub fn resolved_sql(&self) -> String {
+        let mut sql = self.sql_content.clone();
+        for interpolation in self.interpolations.iter().rev() {
+            sql.replace_range(
+                interpolation.range.clone(),
+                &format!("{{{}}}", interpolation.expr),
+            );
+        }
+        sql
+    }
     fn truncate_content(&self, len: usize) -> &str {
         &self.sql_content[..self.sql_content.len().min(len)]
     }
@@ -81,34 +269,180 @@ impl SqlString {
 pub struct FinderConfig {
     variable_ctx: GlobSet,
     func_ctx: GlobSet,
+    // Patterns of the globs, in the order of the sets
+    variable_patterns: Vec<String>,
+    func_patterns: Vec<String>,
+    // Dialects named by `pattern:dialect` entries of the function contexts, by pattern
+    func_dialects: HashMap<String, String>,
     sql_regex: Regex,
+    sql_dialect: Arc<dyn Dialect + Send + Sync>,
+    max_variants: usize,
+    // Functions of the enabled presets with the modules of their preset, in the order of `PRESETS`
+    api_functions: Vec<(GlobMatcher, &'static [&'static str], &'static ApiFunction)>,
 }
 
+/// Most combinations of branches a string is followed through by default
+pub const DEFAULT_MAX_VARIANTS: usize = 16;
+
 impl FinderConfig {
     #[must_use]
     #[allow(clippy::missing_panics_doc)]
     pub fn new(variable_ctx: &[String], func_ctx: &[String]) -> Self {
+        let (variable_ctx, variable_patterns) = slice_to_glob(variable_ctx, "variable_contexts");
+        let mut func_dialects = HashMap::new();
+        let func_ctx: Vec<String> = func_ctx
+            .iter()
+            .map(|entry| match entry.rsplit_once(':') {
+                Some((pattern, dialect)) => {
+                    func_dialects.insert(pattern.to_string(), dialect.to_string());
+                    pattern.to_string()
+                }
+                None => entry.clone(),
+            })
+            .collect();
+        let (func_ctx, func_patterns) = slice_to_glob(&func_ctx, "function_contexts");
         Self {
-            variable_ctx: slice_to_glob(variable_ctx, "variable_contexts"),
-            func_ctx: slice_to_glob(func_ctx, "function_contexts"),
+            variable_ctx,
+            func_ctx,
+            variable_patterns,
+            func_patterns,
+            func_dialects,
             sql_regex: Regex::new(r"(?i)^\s*(select|insert|update|delete|create|drop|alter|truncate|with|explain|show|describe)\b").unwrap(),
+            sql_dialect: Arc::new(GenericDialect {}),
+            max_variants: DEFAULT_MAX_VARIANTS,
+            api_functions: Vec::new(),
         }
     }
+
+    /// Enables the presets of database API functions with these names, none by default.
+    #[must_use]
+    pub fn with_presets(mut self, names: &[String]) -> Self {
+        for name in names {
+            if !PRESETS.iter().any(|preset| preset.name == name) {
+                always_log!("Unknown preset '{name}'");
+            }
+        }
+        self.api_functions = PRESETS
+            .iter()
+            .filter(|preset| names.iter().any(|name| name == preset.name))
+            .flat_map(|preset| {
+                preset
+                    .functions
+                    .iter()
+                    .map(|function| (preset.modules, function))
+            })
+            .filter_map(|(modules, function)| {
+                Glob::new(function.pattern)
+                    .ok()
+                    .map(|glob| (glob.compile_matcher(), modules, function))
+            })
+            .collect();
+        self
+    }
+
+    /// Function of an enabled preset `name` calls in a file importing `modules`. The presets of
+    /// imported modules are matched first, then the others in order.
+    pub(crate) fn api_function(
+        &self,
+        name: &str,
+        modules: &[String],
+    ) -> Option<&'static ApiFunction> {
+        let mut matching = self
+            .api_functions
+            .iter()
+            .filter(|(matcher, ..)| matcher.is_match(name));
+        matching
+            .clone()
+            .find(|(_, packages, _)| {
+                packages
+                    .iter()
+                    .any(|package| modules.iter().any(|module| is_within(module, package)))
+            })
+            .or_else(|| matching.next())
+            .map(|&(.., function)| function)
+    }
+
+    /// Sets the most combinations of branches a string is built in that are followed, strings
+    /// built in more aren't known.
+    #[must_use]
+    pub const fn with_max_variants(mut self, max_variants: usize) -> Self {
+        self.max_variants = max_variants;
+        self
+    }
+
+    pub(crate) const fn max_variants(&self) -> usize {
+        self.max_variants
+    }
+
+    /// Sets the dialect raw SQL files are split into statements with, generic by default.
+    #[must_use]
+    pub fn with_dialect(mut self, dialect: Box<dyn Dialect + Send + Sync>) -> Self {
+        self.sql_dialect = Arc::from(dialect);
+        self
+    }
+
+    pub(crate) fn sql_dialect(&self) -> &dyn Dialect {
+        &*self.sql_dialect
+    }
     pub(crate) fn is_sql_variable_name(&self, name: &str) -> bool {
         self.variable_ctx.is_match(name)
     }
 
     pub(crate) fn is_sql_function_name(&self, name: &str) -> bool {
         self.func_ctx.is_match(name)
+            || self
+                .api_functions
+                .iter()
+                .any(|(matcher, ..)| matcher.is_match(name))
     }
 
     pub(crate) fn is_sql_str(&self, input: &str) -> bool {
         self.sql_regex.is_match(input)
     }
+
+    /// Context of SQL assigned to `name`, a variable name that matched
+    pub(crate) fn variable_context(&self, name: &str) -> Context {
+        Context::Variable(first_match(
+            &self.variable_ctx,
+            &self.variable_patterns,
+            name,
+        ))
+    }
+
+    /// Dialect of SQL passed to `name`, named by the first matching entry that names one
+    pub(crate) fn function_dialect(&self, name: &str) -> Option<String> {
+        self.func_ctx
+            .matches(name)
+            .into_iter()
+            .filter_map(|index| self.func_patterns.get(index))
+            .find_map(|pattern| self.func_dialects.get(pattern).cloned())
+    }
+
+    /// Context of SQL passed to `name`, a function name that matched, or `api_function`
+    pub(crate) fn function_context(
+        &self,
+        name: &str,
+        api_function: Option<&ApiFunction>,
+    ) -> Context {
+        let pattern = first_match(&self.func_ctx, &self.func_patterns, name);
+        match api_function {
+            Some(function) if pattern.is_empty() => Context::Function(function.pattern.to_string()),
+            _ => Context::Function(pattern),
+        }
+    }
+}
+
+fn first_match(set: &GlobSet, patterns: &[String], name: &str) -> String {
+    set.matches(name)
+        .first()
+        .and_then(|&index| patterns.get(index))
+        .cloned()
+        .unwrap_or_default()
 }
 
-fn slice_to_glob(patterns: &[String], log_ctx: &str) -> GlobSet {
-    let valid_globs = patterns
+/// Builds the set of the valid patterns, returned along with it
+fn slice_to_glob(patterns: &[String], log_ctx: &str) -> (GlobSet, Vec<String>) {
+    let valid_globs: Vec<Glob> = patterns
         .iter()
         .filter_map(|pattern| match Glob::new(pattern) {
             Ok(glob) => Some(glob),
@@ -116,39 +450,364 @@ fn slice_to_glob(patterns: &[String], log_ctx: &str) -> GlobSet {
                 always_log!("Failed to parse {log_ctx} glob pattern '{pattern}': {e}");
                 None
             }
+        })
+        .collect();
+
+    let builder = valid_globs
+        .iter()
+        .fold(GlobSetBuilder::new(), |mut builder, glob| {
+            builder.add(glob.clone());
+            builder
         });
 
-    let builder = valid_globs.fold(GlobSetBuilder::new(), |mut builder, glob| {
-        builder.add(glob);
-        builder
-    });
+    match builder.build() {
+        Ok(set) => (
+            set,
+            valid_globs
+                .iter()
+                .map(|glob| glob.glob().to_string())
+                .collect(),
+        ),
+        Err(e) => {
+            error!("Failed to build GlobSet for {log_ctx}: {e}");
+            (GlobSetBuilder::new().build().unwrap(), Vec::new())
+        }
+    }
+}
 
-    builder.build().unwrap_or_else(|e| {
-        error!("Failed to build GlobSet for {log_ctx}: {e}");
-        GlobSetBuilder::new().build().unwrap()
-    })
+/// String content where every byte remembers the source byte offset it was produced from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MappedStr {
+    text: String,
+    origins: Vec<usize>,
+    splices: Vec<Splice>,
 }
 
-#[derive(Debug, Clone)]
+/// Stand-in for a runtime value within a [`MappedStr`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Splice {
+    pub range: std::ops::Range<usize>,
+    pub kind: InterpolationKind,
+    /// Source range of the Python expression
+    pub source: TextRange,
+}
+
+impl Splice {
+    // Moves the splice from starting at `from` to starting at `to`, keeping its length
+    fn moved(&self, from: usize, to: usize) -> Self {
+        Self {
+            range: self.range.start - from + to..self.range.end - from + to,
+            ..self.clone()
+        }
+    }
+}
+
+impl MappedStr {
+    /// Text that doesn't appear literally in the source, attributed as a whole to `origin`.
+    pub fn opaque(text: String, origin: usize) -> Self {
+        let origins = vec![origin; text.len()];
+        Self {
+            text,
+            origins,
+            splices: Vec::new(),
+        }
+    }
+
+    /// Text that appears literally in the source from byte offset `start`.
+    pub fn verbatim(text: &str, start: usize) -> Self {
+        Self {
+            text: text.to_string(),
+            origins: (start..start + text.len()).collect(),
+            splices: Vec::new(),
+        }
+    }
+
+    /// Stand-in for the runtime value of the expression at `source`, remembered as a splice.
+    pub fn spliced(text: String, source: TextRange, kind: InterpolationKind) -> Self {
+        let mut mapped = Self::opaque(text, source.start().to_usize());
+        mapped.splices.push(Splice {
+            range: 0..mapped.text.len(),
+            kind,
+            source,
+        });
+        mapped
+    }
+
+    pub fn origins(&self) -> &[usize] {
+        &self.origins
+    }
+
+    pub fn splices(&self) -> &[Splice] {
+        &self.splices
+    }
+
+    /// Origin of the byte at `offset`, or of the end of the string when out of bounds.
+    pub fn origin_at(&self, offset: usize) -> Option<usize> {
+        self.origins
+            .get(offset)
+            .or_else(|| self.origins.last())
+            .copied()
+    }
+
+    pub fn push_char(&mut self, ch: char, origin: usize) {
+        self.text.push(ch);
+        self.origins
+            .extend(std::iter::repeat_n(origin, ch.len_utf8()));
+    }
+
+    pub fn push_mapped(&mut self, other: &Self) {
+        let len = self.text.len();
+        self.splices
+            .extend(other.splices.iter().map(|s| s.moved(0, len)));
+        self.text.push_str(&other.text);
+        self.origins.extend_from_slice(&other.origins);
+    }
+
+    /// Replaces `range`, dropping the splices it overlaps.
+    pub fn replace_range(&mut self, range: std::ops::Range<usize>, with: &Self) {
+        let (before, rest): (Vec<Splice>, Vec<Splice>) = std::mem::take(&mut self.splices)
+            .into_iter()
+            .partition(|s| s.range.end <= range.start);
+        let after = rest
+            .iter()
+            .filter(|s| s.range.start >= range.end)
+            .map(|s| s.moved(range.end, range.start + with.text.len()));
+        self.splices = before
+            .into_iter()
+            .chain(with.splices.iter().map(|s| s.moved(0, range.start)))
+            .chain(after)
+            .collect();
+
+        self.text.replace_range(range.clone(), &with.text);
+        self.origins.splice(range, with.origins.iter().copied());
+    }
+
+    /// Replaces the first `count` occurrences of `pat`.
+    pub fn replacen(&self, pat: &str, with: &Self, count: usize) -> Self {
+        let mut result = Self::default();
+        let mut last = 0;
+        for (idx, _) in self.text.match_indices(pat).take(count) {
+            result.push_slice(self, last..idx);
+            result.push_mapped(with);
+            last = idx + pat.len();
+        }
+        result.push_slice(self, last..self.text.len());
+        result
+    }
+
+    pub fn replace_all(&self, pat: &str, with: &Self) -> Self {
+        self.replacen(pat, with, usize::MAX)
+    }
+
+    #[must_use]
+    pub fn repeat(&self, count: usize) -> Self {
+        let len = self.text.len();
+        Self {
+            text: self.text.repeat(count),
+            origins: self.origins.repeat(count),
+            splices: (0..count)
+                .flat_map(|i| self.splices.iter().map(move |s| s.moved(0, i * len)))
+                .collect(),
+        }
+    }
+
+    fn push_slice(&mut self, other: &Self, range: std::ops::Range<usize>) {
+        let len = self.text.len();
+        self.splices.extend(
+            other
+                .splices
+                .iter()
+                .filter(|s| s.range.start >= range.start && s.range.end <= range.end)
+                .map(|s| s.moved(range.start, len)),
+        );
+        self.text.push_str(&other.text[range.clone()]);
+        self.origins.extend_from_slice(&other.origins[range]);
+    }
+}
+
+impl Deref for MappedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for MappedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// String a value is when the conditions of `branches` went their way
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub value: MappedStr,
+    pub branches: Vec<Branch>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum FinderType {
-    Str(String),
+    Str(MappedStr),
     Int(String),
     Float(f64),
     Bool(bool),
     Tuple(Vec<FinderType>),
+    /// Strings built in different branches, at least two
+    Variants(Vec<Variant>),
     Placeholder,
 }
 
 impl FinderType {
-    pub fn get_str(&self) -> Option<&str> {
+    /// Strings the value can be
+    pub fn texts(&self) -> Vec<&str> {
         match self {
-            Self::Str(s) => Some(s),
+            Self::Str(s) => vec![s],
+            Self::Variants(variants) => variants.iter().map(|v| &*v.value).collect(),
+            _ => vec![],
+        }
+    }
+    /// Whether both values are the same strings, wherever in the source they come from
+    pub fn same_text(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Str(a), Self::Str(b)) => **a == **b,
+            (Self::Variants(a), Self::Variants(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b)
+                        .all(|(a, b)| *a.value == *b.value && a.branches == b.branches)
+            }
+            _ => false,
+        }
+    }
+    /// Strings the value can be with the branches each is built in, `None` if it's no string
+    pub fn into_variants(self) -> Option<Vec<Variant>> {
+        match self {
+            Self::Str(value) => Some(vec![Variant {
+                value,
+                branches: Vec::new(),
+            }]),
+            Self::Variants(variants) => Some(variants),
             _ => None,
         }
     }
+    /// Value that is `taken` when the condition of `branch` holds and `not_taken` otherwise,
+    /// a placeholder unless both are strings.
+    pub fn either(taken: Self, not_taken: Self, branch: &Branch, max_variants: usize) -> Self {
+        let (Some(taken), Some(not_taken)) = (taken.into_variants(), not_taken.into_variants())
+        else {
+            return Self::Placeholder;
+        };
+        let way = |variants: Vec<Variant>, taken: bool| {
+            variants.into_iter().map(move |mut variant| {
+                let branch = Branch {
+                    taken,
+                    ..branch.clone()
+                };
+                variant.branches.push(branch);
+                variant.branches.sort_by_key(|b| b.line);
+                variant
+            })
+        };
+        Self::from_variants(
+            way(taken, true).chain(way(not_taken, false)).collect(),
+            max_variants,
+        )
+    }
+    /// Applies `f` to every combination of the variants of `values`, leaving out combinations
+    /// that take both ways of a condition. A placeholder if any result is one or there are more
+    /// than `max_variants` combinations.
+    pub fn combine(
+        values: Vec<Self>,
+        max_variants: usize,
+        f: impl Fn(Vec<Self>) -> Option<Self>,
+    ) -> Option<Self> {
+        if !values
+            .iter()
+            .any(|value| matches!(value, Self::Variants(_)))
+        {
+            return f(values);
+        }
+        let mut combinations: Vec<(Vec<Self>, Vec<Branch>)> = vec![(Vec::new(), Vec::new())];
+        for value in values {
+            let Self::Variants(variants) = value else {
+                for (values, _) in &mut combinations {
+                    values.push(value.clone());
+                }
+                continue;
+            };
+            let mut extended = Vec::new();
+            for (values, branches) in combinations {
+                for variant in &variants {
+                    let contradicts = variant
+                        .branches
+                        .iter()
+                        .any(|b| branches.iter().any(|other| b.contradicts(other)));
+                    if contradicts {
+                        continue;
+                    }
+                    let mut values = values.clone();
+                    values.push(Self::Str(variant.value.clone()));
+                    let mut branches = branches.clone();
+                    for branch in &variant.branches {
+                        if !branches.contains(branch) {
+                            branches.push(branch.clone());
+                        }
+                    }
+                    branches.sort_by_key(|b| b.line);
+                    extended.push((values, branches));
+                }
+            }
+            combinations = extended;
+            if combinations.len() > max_variants {
+                return Some(Self::Placeholder);
+            }
+        }
+
+        let mut variants = Vec::new();
+        for (values, branches) in combinations {
+            match f(values)? {
+                Self::Str(value) => variants.push(Variant { value, branches }),
+                _ => return Some(Self::Placeholder),
+            }
+        }
+        Some(Self::from_variants(variants, max_variants))
+    }
+    /// Value of the variants, keeping the first of those with the same string
+    fn from_variants(variants: Vec<Variant>, max_variants: usize) -> Self {
+        let mut unique: Vec<Variant> = Vec::new();
+        for variant in variants {
+            if !unique.iter().any(|u| *u.value == *variant.value) {
+                unique.push(variant);
+            }
+        }
+        match unique.len() {
+            0 => Self::Placeholder,
+            1 => Self::Str(unique.remove(0).value),
+            count if count > max_variants => Self::Placeholder,
+            _ => Self::Variants(unique),
+        }
+    }
     pub fn is_placeholder(&self) -> bool {
         matches!(self, Self::Placeholder)
     }
+    /// Renders the value as it would be spliced into a string at `origin`.
+    pub fn to_mapped(&self, origin: usize) -> MappedStr {
+        match self {
+            Self::Str(s) => s.clone(),
+            other => MappedStr::opaque(other.to_string(), origin),
+        }
+    }
+    /// Renders the value of the expression at `source` as interpolated into a string,
+    /// remembering where runtime values were spliced in.
+    pub fn to_spliced(&self, source: TextRange, kind: InterpolationKind) -> MappedStr {
+        match self {
+            Self::Placeholder | Self::Variants(_) => {
+                MappedStr::spliced(self.to_string(), source, kind)
+            }
+            other => other.to_mapped(source.start().to_usize()),
+        }
+    }
 }
 
 impl std::fmt::Display for FinderType {
@@ -169,7 +828,8 @@ impl std::fmt::Display for FinderType {
                 }
                 write!(f, ")")
             }
-            Self::Placeholder => write!(f, "PLACEHOLDER"),
+            // Which of the variants it is is only known at runtime
+            Self::Placeholder | Self::Variants(_) => write!(f, "PLACEHOLDER"),
         }
     }
 }
@@ -179,7 +839,10 @@ impl Add for FinderType {
     fn add(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
             (Self::Placeholder, _) | (_, Self::Placeholder) => Some(Self::Placeholder),
-            (Self::Str(s1), Self::Str(s2)) => Some(Self::Str(s1 + &s2)),
+            (Self::Str(mut s1), Self::Str(s2)) => {
+                s1.push_mapped(&s2);
+                Some(Self::Str(s1))
+            }
             (Self::Int(s1), Self::Int(s2)) => Some(Self::Int(s1 + &s2)),
             (Self::Float(f1), Self::Float(f2)) => Some(Self::Float(f1 + f2)),
             (Self::Bool(b1), Self::Bool(b2)) => Some(Self::Bool(b1 || b2)),
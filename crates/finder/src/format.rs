@@ -6,18 +6,20 @@
 
 use logging::bail_with;
 use regex::Regex;
+use rustpython_parser::text_size::TextRange;
 
-use crate::finder_types::FinderType;
+use crate::finder_types::{FinderType, InterpolationKind, MappedStr};
 
+/// Applies `%` formatting. Args carry the source range of the expression they came from.
 pub fn format_python_string(
-    format_str: &str,
-    args: &[FinderType],
-    kwargs: &[(String, FinderType)],
-) -> Option<String> {
+    format_str: &MappedStr,
+    args: &[(FinderType, TextRange)],
+    kwargs: &[(String, FinderType, TextRange)],
+) -> Option<MappedStr> {
     let re = Regex::new(
         r"%\(([^)]+)\)[-+0 #]*(?:\*|\d+)?(?:\.(?:\*|\d+))?[hlL]?[sdifgGeEoxXcubp%]|%[-+0 #]*(?:\*|\d+)?(?:\.(?:\*|\d+))?[hlL]?[sdifgGeEoxXcubp%]",
     ).ok()?;
-    let mut result = format_str.to_string();
+    let mut result = format_str.clone();
     let mut value_index = 0;
     let matches: Vec<_> = re.find_iter(format_str).collect();
 
@@ -25,32 +27,41 @@ pub fn format_python_string(
         let specifier = m.as_str();
 
         if specifier == "%%" {
-            result.replace_range(m.range(), "%");
+            let origin = format_str.origin_at(m.start())?;
+            result.replace_range(m.range(), &MappedStr::opaque("%".to_string(), origin));
             continue;
         }
 
-        let (value, conv) = if specifier.starts_with("%(") {
+        let ((value, source), conv) = if specifier.starts_with("%(") {
             // Named format specifier like %(name)s
             let key_end = specifier.find(')')?;
             let key = &specifier[2..key_end]; // Extract key between %( and )
             let conv = specifier.chars().last()?;
 
             // Find the value in kwargs slice
-            let value = kwargs.iter().find(|(k, _)| k == key).map(|(_, v)| v)?;
+            let value = kwargs
+                .iter()
+                .find(|(k, _, _)| k == key)
+                .map(|(_, v, source)| (v, *source))?;
             (value, conv)
         } else {
             // Positional format specifier like %s, %d, etc.
             if value_index >= args.len() {
                 return None;
             }
-            let value = &args[args.len() - 1 - value_index];
+            let (value, source) = &args[args.len() - 1 - value_index];
             value_index += 1;
+            let value = (value, *source);
             let conv = specifier.chars().last()?;
             (value, conv)
         };
 
+        if conv == 's' {
+            let spliced = value.to_spliced(source, InterpolationKind::PercentFormat);
+            result.replace_range(m.range(), &spliced);
+            continue;
+        }
         let replacement = match conv {
-            's' => Some(value.to_string()),
             'd' | 'i' => format_value_as_int(value),
             'u' => format_value_as_unsigned(value),
             'b' => format_value_as_binary(value),
@@ -65,7 +76,8 @@ pub fn format_python_string(
             _ => bail_with!(None, "Unhandled format conversion specifier: {}", conv),
         };
 
-        result.replace_range(m.range(), &replacement?);
+        let origin = source.start().to_usize();
+        result.replace_range(m.range(), &MappedStr::opaque(replacement?, origin));
     }
 
     Some(result)
@@ -254,16 +266,16 @@ fn format_value_as_scientific(value: &FinderType, specifier: &str) -> Option<Str
 fn format_value_as_char(value: &FinderType) -> Option<String> {
     match value {
         FinderType::Int(i) => {
-            if let Ok(code) = i.parse::<u32>() {
-                if let Some(ch) = char::from_u32(code) {
-                    return Some(ch.to_string());
-                }
+            if let Ok(code) = i.parse::<u32>()
+                && let Some(ch) = char::from_u32(code)
+            {
+                return Some(ch.to_string());
             }
             None
         }
         FinderType::Str(s) => {
             if s.len() == 1 {
-                Some(s.clone())
+                Some(s.to_string())
             } else {
                 None
             }
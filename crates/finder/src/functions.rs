@@ -0,0 +1,100 @@
+use rustpython_parser::ast::{self, fold::Fold};
+use rustpython_parser::text_size::TextRange;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+
+/// Deepest chain of calls followed into the functions of a module
+pub(crate) const MAX_CALL_DEPTH: usize = 8;
+
+/// Calls of the named functions anywhere in a module, by function name
+pub(crate) fn call_sites(
+    suite: &ast::Suite,
+    names: &HashSet<String>,
+) -> HashMap<String, Vec<ast::ExprCall>> {
+    let mut sites = CallSites {
+        names,
+        calls: HashMap::new(),
+    };
+    for stmt in suite.clone() {
+        let Ok(_) = sites.fold_stmt(stmt);
+    }
+    sites.calls
+}
+
+struct CallSites<'a> {
+    names: &'a HashSet<String>,
+    calls: HashMap<String, Vec<ast::ExprCall>>,
+}
+
+impl Fold<TextRange> for CallSites<'_> {
+    type TargetU = TextRange;
+    type Error = Infallible;
+    type UserContext = ();
+
+    fn will_map_user(&mut self, _user: &TextRange) {}
+
+    fn map_user(&mut self, user: TextRange, (): ()) -> Result<TextRange, Infallible> {
+        Ok(user)
+    }
+
+    fn fold_expr_call(&mut self, call: ast::ExprCall) -> Result<ast::ExprCall, Infallible> {
+        if let ast::Expr::Name(name) = &*call.func
+            && self.names.contains(name.id.as_str())
+        {
+            self.calls
+                .entry(name.id.to_string())
+                .or_default()
+                .push(call.clone());
+        }
+        ast::fold::fold_expr_call(self, call)
+    }
+}
+
+/// Parameters of a function paired with the expressions a call passes them, or their defaults.
+/// `None` if that depends on the runtime, like with `*args` in the call.
+pub(crate) fn arguments<'a>(
+    params: &'a ast::Arguments,
+    call: &'a ast::ExprCall,
+) -> Option<Vec<(&'a str, Option<&'a ast::Expr>)>> {
+    if call
+        .args
+        .iter()
+        .any(|arg| matches!(arg, ast::Expr::Starred(_)))
+        || call.keywords.iter().any(|kw| kw.arg.is_none())
+    {
+        return None;
+    }
+    let positional: Vec<&ast::ArgWithDefault> =
+        params.posonlyargs.iter().chain(&params.args).collect();
+    if call.args.len() > positional.len() && params.vararg.is_none() {
+        return None;
+    }
+
+    let arguments = positional
+        .iter()
+        .copied()
+        .enumerate()
+        .map(|(index, param)| (param, call.args.get(index)))
+        .chain(params.kwonlyargs.iter().map(|param| (param, None)))
+        .map(|(param, arg)| {
+            let name = param.def.arg.as_str();
+            let arg = arg
+                .or_else(|| {
+                    call.keywords
+                        .iter()
+                        .find(|kw| kw.arg.as_ref().is_some_and(|arg| arg.as_str() == name))
+                        .map(|kw| &kw.value)
+                })
+                .or(param.default.as_deref());
+            (name, arg)
+        })
+        .chain(
+            params
+                .vararg
+                .iter()
+                .chain(&params.kwarg)
+                .map(|param| (param.arg.as_str(), None)),
+        )
+        .collect();
+    Some(arguments)
+}
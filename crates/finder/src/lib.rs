@@ -1,157 +1,570 @@
+mod constants;
 mod finder_types;
 mod format;
+mod functions;
+mod literal;
+mod notebook;
 pub mod preanalysis;
+mod presets;
+mod statements;
+mod symbols;
+#[cfg(test)]
 mod tests;
 mod traversal;
-pub use crate::finder_types::{FinderConfig, SqlExtract, SqlString};
-use logging::{bail_with, error};
+pub use crate::finder_types::{
+    Branch, CallParams, CellMap, Context, DEFAULT_MAX_VARIANTS, DriverImport, FinderConfig,
+    Interpolation, InterpolationKind, MappedStr, SqlExtract, SqlString,
+};
+pub use crate::preanalysis::LineCol;
+pub use crate::presets::{ParamStyle, preset_names};
+pub use crate::symbols::SymbolTable;
+use constants::{Binding, Constants, ScopeKind};
+use finder_types::FinderType;
+use logging::{bail_with, error, info};
 use rustpython_parser::{
     Parse,
     ast::{self, Ranged},
 };
-use std::{fs, sync::Arc};
+use std::{
+    cell::{Cell, RefCell},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use symbols::ModuleConstants;
 
 pub struct SqlFinder {
     config: Arc<FinderConfig>,
+    constants: RefCell<Constants>,
+    symbols: Option<Arc<SymbolTable>>,
+    // File being analyzed, which relative imports start from
+    path: RefCell<PathBuf>,
+    // Modules whose imports led to this one
+    importers: Vec<PathBuf>,
+    // Whether an import cycle was cut while reading imported modules
+    incomplete: Cell<bool>,
+    // Modules the file being analyzed imports, which tell the presets its calls belong to
+    modules: RefCell<Vec<String>>,
 }
 
 impl SqlFinder {
     #[must_use]
-    pub const fn new(config: Arc<FinderConfig>) -> Self {
-        Self { config }
+    pub fn new(config: Arc<FinderConfig>) -> Self {
+        Self {
+            config,
+            constants: RefCell::new(Constants::default()),
+            symbols: None,
+            path: RefCell::default(),
+            importers: Vec::new(),
+            incomplete: Cell::new(false),
+            modules: RefCell::default(),
+        }
     }
 
+    /// Resolves imports to the string constants of the modules of the project, shared through
+    /// `symbols` by the finders of all files.
     #[must_use]
-    pub fn analyze_file(&mut self, file_path: &str, is_raw_sql: bool) -> Option<SqlExtract> {
-        if is_raw_sql {
-            self.analyze_sql_file(file_path)
-        } else {
-            self.analyze_python_file(file_path)
-        }
+    pub fn with_symbols(mut self, symbols: Arc<SymbolTable>) -> Self {
+        self.symbols = Some(symbols);
+        self
     }
 
-    fn analyze_python_file(&mut self, file_path: &str) -> Option<SqlExtract> {
+    #[must_use]
+    pub fn analyze_file(&mut self, file_path: &str, is_raw_sql: bool) -> Option<SqlExtract> {
         let source_code = fs::read_to_string(file_path)
             .inspect_err(|e| error!("Failed to read file '{file_path}': {e}"))
             .ok()?;
+        self.analyze_source(file_path, &source_code, is_raw_sql)
+    }
 
-        let parsed = ast::Suite::parse(&source_code, file_path)
+    /// Finds the SQL of a file whose content is already in memory, like an editor buffer
+    #[must_use]
+    pub fn analyze_source(
+        &mut self,
+        file_path: &str,
+        source_code: &str,
+        is_raw_sql: bool,
+    ) -> Option<SqlExtract> {
+        *self.path.borrow_mut() = PathBuf::from(file_path);
+        if is_raw_sql {
+            return Some(SqlExtract::new(
+                file_path.to_string(),
+                self.split_sql(source_code),
+            ));
+        }
+        if Path::new(file_path)
+            .extension()
+            .is_some_and(|ext| ext == "ipynb")
+        {
+            return self.analyze_notebook(file_path, source_code);
+        }
+
+        let parsed = ast::Suite::parse(source_code, file_path)
             .inspect_err(|e| {
                 error!("Failed to parse Python file '{file_path}': {e}");
             })
             .ok()?;
 
-        let range_file = preanalysis::PreanalyzedFile::from_src(&source_code);
+        let range_file = preanalysis::PreanalyzedFile::from_src(source_code);
+        *self.modules.borrow_mut() = constants::imported_modules(&parsed);
         let strings = self.analyze_stmts(&parsed, &range_file);
 
-        Some(SqlExtract::new(file_path.to_string(), strings))
+        let mut extract = SqlExtract::new(file_path.to_string(), strings);
+        extract.drivers = presets::imported_drivers(&self.modules.borrow());
+        Some(extract)
     }
-    fn analyze_sql_file(&mut self, file_path: &str) -> Option<SqlExtract> {
-        let source_code = fs::read_to_string(file_path)
-            .inspect_err(|e| error!("Failed to read file '{file_path}': {e}"))
+
+    /// Checks the code cells of a notebook in order, and `%%sql` cells as raw SQL
+    pub(crate) fn analyze_notebook(&self, file_path: &str, json: &str) -> Option<SqlExtract> {
+        *self.path.borrow_mut() = PathBuf::from(file_path);
+        let notebook = notebook::Notebook::parse(json)
+            .inspect_err(|e| error!("Failed to read notebook '{file_path}': {e}"))
             .ok()?;
-        let mut strings = Vec::new();
-        let mut current_pos = 0;
-        let mut line_num = 1;
-        let mut col_num = 1;
-
-        for (index, sql_segment) in source_code.split(';').enumerate() {
-            let cleaned = Self::trim_sql_comments(sql_segment);
-
-            if !cleaned.is_empty() {
-                let start_line = line_num;
-                let start_col = col_num;
-                let range = crate::preanalysis::Range {
-                    start: crate::preanalysis::LineCol::new(start_line, start_col, 0),
-                };
-                strings.push(SqlString::new(
+        if let Some(language) = notebook
+            .language
+            .as_deref()
+            .filter(|language| !language.eq_ignore_ascii_case("python"))
+        {
+            info!("Skipping notebook '{file_path}' written in {language}");
+            return None;
+        }
+        for (cell, e) in &notebook.invalid_cells {
+            error!("Failed to parse cell {cell} of notebook '{file_path}': {e}");
+        }
+
+        let parsed = ast::Suite::parse(&notebook.source, file_path)
+            .inspect_err(|e| error!("Failed to parse notebook '{file_path}': {e}"))
+            .ok()?;
+        let range_file = preanalysis::PreanalyzedFile::from_src(&notebook.source);
+        *self.modules.borrow_mut() = constants::imported_modules(&parsed);
+        let mut strings = self.analyze_stmts(&parsed, &range_file);
+        strings.extend(
+            notebook
+                .sql_cells
+                .into_iter()
+                .map(|(sql, range)| SqlString::new("%%sql".to_string(), sql, range)),
+        );
+        strings.sort_by_key(|s| (s.range.start.line(), s.range.start.col()));
+
+        let mut extract = SqlExtract::new(file_path.to_string(), strings);
+        extract.cells = Some(notebook.cells);
+        extract.drivers = presets::imported_drivers(&self.modules.borrow());
+        Some(extract)
+    }
+
+    /// Function of an enabled preset `name` calls, as the modules the file imports tell
+    pub(crate) fn api_function(&self, name: &str) -> Option<&'static presets::ApiFunction> {
+        self.config.api_function(name, &self.modules.borrow())
+    }
+
+    /// Splits raw SQL into its statements, each starting where its SQL does
+    pub(crate) fn split_sql(&self, source_code: &str) -> Vec<SqlString> {
+        statements::split(source_code, self.config.sql_dialect())
+            .into_iter()
+            .enumerate()
+            .map(|(index, statement)| {
+                let sql = &source_code[statement.range];
+                SqlString::new(
                     format!("sql_statement_{}", index + 1),
-                    cleaned,
-                    range,
-                ));
-            }
+                    sql.to_string(),
+                    preanalysis::Range::verbatim(statement.start, sql),
+                )
+            })
+            .collect()
+    }
+
+    /// Finds the SQL of a module, following the string values of its names from statement to
+    /// statement so SQL assembled over several statements is checked as a whole.
+    pub(crate) fn analyze_stmts(
+        &self,
+        suite: &ast::Suite,
+        rf: &preanalysis::PreanalyzedFile,
+    ) -> Vec<SqlString> {
+        *self.constants.borrow_mut() = Constants::default();
+        self.find_module_constants(suite, rf);
+        self.find_functions(suite, rf);
+        self.constants
+            .borrow_mut()
+            .push(ScopeKind::Module, std::collections::HashSet::new());
+        let results = self.analyze_block(suite, rf);
+
+        let constants = self.constants.borrow();
+        results
+            .into_iter()
+            .filter(|s| {
+                !constants.is_superseded(&(s.range.start.byte_offset(), s.variable_name.clone()))
+            })
+            .collect()
+    }
 
-            // Update position for next segment
-            for ch in sql_segment.chars() {
-                if ch == '\n' {
-                    line_num += 1;
-                    col_num = 1;
-                } else {
-                    col_num += 1;
+    /// Module names assigned once to a string or an imported module, before any statement is
+    /// walked, so functions defined above them can use them
+    fn find_module_constants(&self, suite: &ast::Suite, rf: &preanalysis::PreanalyzedFile) {
+        let counts = constants::assignment_counts(suite);
+        let globals = constants::declared_globals(suite);
+        // Names a `*` import binds aren't counted
+        let is_constant = |name: &str| {
+            counts.get(name).is_none_or(|&count| count == 1) && !globals.contains(name)
+        };
+        self.constants
+            .borrow_mut()
+            .push(ScopeKind::Module, std::collections::HashSet::new());
+        for stmt in suite {
+            let bindings = match stmt {
+                ast::Stmt::Import(_) | ast::Stmt::ImportFrom(_) => self.import_bindings(stmt),
+                ast::Stmt::Assign(ast::StmtAssign { targets, value, .. }) if targets.len() == 1 => {
+                    self.constant_binding(&targets[0], value, rf)
+                }
+                ast::Stmt::AnnAssign(ast::StmtAnnAssign {
+                    target,
+                    value: Some(value),
+                    ..
+                }) => self.constant_binding(target, value, rf),
+                _ => continue,
+            };
+            let mut constants = self.constants.borrow_mut();
+            for (name, binding) in bindings {
+                if binding.is_known() && is_constant(&name) {
+                    constants.set_module_constant(&name, binding.clone());
+                    constants.bind(&name, binding);
                 }
             }
+        }
+        self.constants.borrow_mut().pop();
+    }
 
-            // Account for the semicolon delimiter (except for the last segment)
-            if current_pos + sql_segment.len() < source_code.len() {
-                col_num += 1;
+    /// Functions of the module that calls are followed into, and the strings all calls in the
+    /// module pass to their parameters
+    fn find_functions(&self, suite: &ast::Suite, rf: &preanalysis::PreanalyzedFile) {
+        let counts = constants::assignment_counts(suite);
+        let globals = constants::declared_globals(suite);
+        let mut names = std::collections::HashSet::new();
+        for stmt in suite {
+            if let ast::Stmt::FunctionDef(function) = stmt
+                && counts.get(function.name.as_str()) == Some(&1)
+                && !globals.contains(function.name.as_str())
+            {
+                names.insert(function.name.to_string());
+                self.constants
+                    .borrow_mut()
+                    .set_function(&function.name, Arc::new(function.clone()));
             }
-            current_pos += sql_segment.len() + 1;
+        }
+        if names.is_empty() {
+            return;
         }
 
-        Some(SqlExtract::new(file_path.to_string(), strings))
+        self.constants.borrow_mut().push_module_constants();
+        for (name, calls) in functions::call_sites(suite, &names) {
+            let Some(function) = self.constants.borrow().function(&name) else {
+                continue;
+            };
+            // SQL passed to a function matching the patterns was checked at the call
+            let reported = self.config.is_sql_function_name(&name);
+            let mut agreed: Option<constants::Bindings> = None;
+            for call in &calls {
+                let Some(arguments) = functions::arguments(&function.args, call) else {
+                    agreed = Some(constants::Bindings::new());
+                    break;
+                };
+                let bindings: constants::Bindings = arguments
+                    .into_iter()
+                    .filter_map(|(param, arg)| {
+                        let value = self.string_value(arg?, rf)?;
+                        let reported = if reported {
+                            let origin = (call.range.start().to_usize(), name.clone());
+                            vec![(origin, usize::MAX)]
+                        } else {
+                            Vec::new()
+                        };
+                        let binding = Binding {
+                            value: Some(value),
+                            reported,
+                            module: None,
+                        };
+                        Some((param.to_string(), binding))
+                    })
+                    .collect();
+                agreed = Some(match agreed {
+                    None => bindings,
+                    Some(agreed) => agreed
+                        .into_iter()
+                        .filter(|(param, binding)| {
+                            bindings.get(param).is_some_and(|b| {
+                                b.value
+                                    .as_ref()
+                                    .zip(binding.value.as_ref())
+                                    .is_some_and(|(a, b)| a.same_text(b))
+                            })
+                        })
+                        .collect(),
+                });
+            }
+            if let Some(parameters) = agreed.filter(|p| !p.is_empty()) {
+                self.constants
+                    .borrow_mut()
+                    .set_parameters(&name, parameters);
+            }
+        }
+        self.constants.borrow_mut().pop();
     }
 
-    fn trim_sql_comments(sql: &str) -> String {
-        let lines: Vec<&str> = sql.lines().collect();
-        let mut start_idx = 0;
-        let mut end_idx = lines.len();
+    fn constant_binding(
+        &self,
+        target: &ast::Expr,
+        value: &ast::Expr,
+        rf: &preanalysis::PreanalyzedFile,
+    ) -> Vec<(String, Binding)> {
+        let ast::Expr::Name(name) = target else {
+            return vec![];
+        };
+        let binding = Binding {
+            value: self
+                .string_value(value, rf)
+                .or_else(|| Self::number_value(value)),
+            ..Binding::default()
+        };
+        vec![(name.id.to_string(), binding)]
+    }
 
-        // Find first non-comment, non-empty line
-        for (i, line) in lines.iter().enumerate() {
-            let trimmed = line.trim();
-            if !trimmed.is_empty() && !trimmed.starts_with("--") && !trimmed.starts_with("#") {
-                start_idx = i;
-                break;
-            }
+    /// Names an import binds, to the imported string constants and modules that are known
+    fn import_bindings(&self, stmt: &ast::Stmt) -> Vec<(String, Binding)> {
+        if self.symbols.is_none() {
+            return constants::assigned_names(std::slice::from_ref(stmt))
+                .into_iter()
+                .map(|name| (name, Binding::default()))
+                .collect();
         }
-
-        // Find last non-comment, non-empty line
-        for (i, line) in lines.iter().enumerate().rev() {
-            let trimmed = line.trim();
-            if !trimmed.is_empty() && !trimmed.starts_with("--") && !trimmed.starts_with("#") {
-                end_idx = i + 1;
-                break;
+        let path = self.path.borrow().clone();
+        match stmt {
+            ast::Stmt::Import(ast::StmtImport { names, .. }) => names
+                .iter()
+                .map(|alias| {
+                    // `import a.b` binds `a`, while `import a.b as c` binds `c` to `a.b`
+                    let (name, module) = match &alias.asname {
+                        Some(asname) => (asname.as_str(), alias.name.as_str()),
+                        None => {
+                            let top = alias.name.split('.').next().unwrap_or_default();
+                            (top, top)
+                        }
+                    };
+                    let binding = Binding {
+                        module: symbols::resolve_module(&path, Some(module), 0),
+                        ..Binding::default()
+                    };
+                    (name.to_string(), binding)
+                })
+                .collect(),
+            ast::Stmt::ImportFrom(ast::StmtImportFrom {
+                module,
+                names,
+                level,
+                ..
+            }) => {
+                let module = module.as_ref().map(ast::Identifier::as_str);
+                let level = level.as_ref().map_or(0, ast::Int::to_usize);
+                let file = symbols::resolve_module(&path, module, level);
+                let constants = file.as_deref().and_then(|file| self.imported_module(file));
+                let mut bindings = Vec::new();
+                for alias in names {
+                    let origin = alias.range.start().to_usize();
+                    if alias.name.as_str() == "*" {
+                        bindings.extend(constants.iter().flat_map(|c| c.iter()).map(
+                            |(name, text)| {
+                                let binding = Binding {
+                                    value: Some(FinderType::Str(MappedStr::opaque(
+                                        text.clone(),
+                                        origin,
+                                    ))),
+                                    ..Binding::default()
+                                };
+                                (name.clone(), binding)
+                            },
+                        ));
+                        continue;
+                    }
+                    let value = constants
+                        .as_ref()
+                        .and_then(|c| c.get(alias.name.as_str()))
+                        .map(|text| FinderType::Str(MappedStr::opaque(text.clone(), origin)));
+                    // `from package import module`
+                    let submodule = value.is_none().then(|| {
+                        let submodule = module.map_or_else(
+                            || alias.name.to_string(),
+                            |module| format!("{module}.{}", alias.name),
+                        );
+                        symbols::resolve_module(&path, Some(&submodule), level)
+                    });
+                    let binding = Binding {
+                        value,
+                        module: submodule.flatten(),
+                        ..Binding::default()
+                    };
+                    let name = alias.asname.as_ref().unwrap_or(&alias.name);
+                    bindings.push((name.to_string(), binding));
+                }
+                bindings
             }
+            _ => vec![],
         }
+    }
 
-        if start_idx >= end_idx {
-            return String::new();
+    /// String constants of an imported module, which is read the first time it's imported.
+    /// Modules that import each other don't see each other's constants.
+    pub(crate) fn imported_module(&self, file: &Path) -> Option<Arc<ModuleConstants>> {
+        let symbols = self.symbols.as_ref()?;
+        if let Some(constants) = symbols.get(file) {
+            return Some(constants);
+        }
+        let path = self.path.borrow().clone();
+        if path == file || self.importers.iter().any(|importer| importer == file) {
+            self.incomplete.set(true);
+            return None;
         }
 
-        lines[start_idx..end_idx].join("\n").trim().to_string()
+        let source = fs::read_to_string(file)
+            .inspect_err(|e| info!("Failed to read imported module '{}': {e}", file.display()))
+            .ok()?;
+        let file_name = file.to_string_lossy();
+        let parsed = ast::Suite::parse(&source, &file_name)
+            .inspect_err(|e| info!("Failed to parse imported module '{file_name}': {e}"))
+            .ok()?;
+        let mut importers = self.importers.clone();
+        importers.push(path);
+        let finder = Self {
+            importers,
+            ..Self::new(self.config.clone()).with_symbols(symbols.clone())
+        };
+        *finder.path.borrow_mut() = file.to_path_buf();
+        finder.find_module_constants(&parsed, &preanalysis::PreanalyzedFile::from_src(&source));
+
+        let constants = Arc::new(finder.constants.borrow().module_strings());
+        // What was found past a cycle depends on where the cycle was entered, so it isn't kept
+        if finder.incomplete.get() {
+            self.incomplete.set(true);
+        } else {
+            symbols.insert(file.to_path_buf(), constants.clone());
+        }
+        Some(constants)
     }
 
     #[allow(clippy::too_many_lines)]
-    pub(crate) fn analyze_stmts(
+    fn analyze_block(
         &self,
-        suite: &ast::Suite,
+        suite: &[ast::Stmt],
         rf: &preanalysis::PreanalyzedFile,
     ) -> Vec<SqlString> {
+        let enclosing = self.constants.borrow_mut().enter_block();
         let mut results = Vec::new();
         for stmt in suite {
             let start_offset = stmt.range().start().to_usize();
             let end_offset = stmt.range().end().to_usize();
             if rf.should_ignore_stmt_at(start_offset) || rf.should_ignore_stmt_at(end_offset) {
+                self.constants
+                    .borrow_mut()
+                    .invalidate(&constants::assigned_names(std::slice::from_ref(stmt)));
                 continue;
             }
 
             let stmt_results = match stmt {
-                ast::Stmt::Assign(a) => self.analyze_assignment(a, rf),
-                ast::Stmt::AnnAssign(a) => self.analyze_annotated_assignment(a, rf),
+                ast::Stmt::Assign(a) => {
+                    let mut found = self.analyze_assignment(a, rf);
+                    // SQL passed to the call whose result is assigned, like `rows = cur.fetch(...)`
+                    if found.is_empty()
+                        && matches!(*a.value, ast::Expr::Call(_) | ast::Expr::Await(_))
+                    {
+                        found = self.analyze_expr(&a.value, rf);
+                    }
+                    for target in &a.targets {
+                        self.bind_target(target, &a.value, &found, rf);
+                    }
+                    found
+                }
+                ast::Stmt::AnnAssign(a) => {
+                    let found = self.analyze_annotated_assignment(a, rf);
+                    if let Some(value) = &a.value {
+                        self.bind_target(&a.target, value, &found, rf);
+                    }
+                    found
+                }
+                ast::Stmt::AugAssign(a) => self.analyze_aug_assignment(a, rf),
 
                 ast::Stmt::For(ast::StmtFor { body, orelse, .. })
                 | ast::Stmt::AsyncFor(ast::StmtAsyncFor { body, orelse, .. })
-                | ast::Stmt::While(ast::StmtWhile { body, orelse, .. })
-                | ast::Stmt::If(ast::StmtIf { body, orelse, .. }) => {
-                    self.analyze_body_and_orelse(body, orelse, rf)
+                | ast::Stmt::While(ast::StmtWhile { body, orelse, .. }) => {
+                    // Values assigned in a loop differ from one iteration to the next
+                    let assigned = constants::assigned_names(std::slice::from_ref(stmt));
+                    self.constants.borrow_mut().invalidate(&assigned);
+                    let found = self.analyze_body_and_orelse(body, orelse, rf);
+                    self.constants.borrow_mut().invalidate(&assigned);
+                    found
+                }
+                ast::Stmt::If(ast::StmtIf {
+                    test, body, orelse, ..
+                }) => {
+                    let before = self.constants.borrow().snapshot();
+                    let mut found = self.analyze_block(body, rf);
+                    let after_body = self.constants.borrow().snapshot();
+                    self.constants.borrow_mut().restore(before);
+                    found.extend(self.analyze_block(orelse, rf));
+                    let mut constants = self.constants.borrow_mut();
+                    let merged = constants.merge_branches(
+                        &after_body,
+                        &constants.snapshot(),
+                        &Self::branch(test, rf),
+                        self.config.max_variants(),
+                    );
+                    constants.restore(merged);
+                    found
                 }
 
-                ast::Stmt::FunctionDef(ast::StmtFunctionDef { body, .. })
-                | ast::Stmt::AsyncFunctionDef(ast::StmtAsyncFunctionDef { body, .. })
-                | ast::Stmt::ClassDef(ast::StmtClassDef { body, .. })
-                | ast::Stmt::With(ast::StmtWith { body, .. })
+                ast::Stmt::FunctionDef(ast::StmtFunctionDef {
+                    name,
+                    args,
+                    body,
+                    range,
+                    ..
+                })
+                | ast::Stmt::AsyncFunctionDef(ast::StmtAsyncFunctionDef {
+                    name,
+                    args,
+                    body,
+                    range,
+                    ..
+                }) => {
+                    let mut constants = self.constants.borrow_mut();
+                    // Parameters are known for the module function all calls pass them to
+                    let parameters = constants
+                        .function(name)
+                        .filter(|function| function.range == *range)
+                        .map(|_| constants.parameters(name))
+                        .unwrap_or_default();
+                    constants.push(ScopeKind::Function, constants::function_locals(args, body));
+                    for (param, binding) in parameters {
+                        constants.bind(&param, binding);
+                    }
+                    drop(constants);
+                    let found = self.analyze_block(body, rf);
+                    let mut constants = self.constants.borrow_mut();
+                    constants.pop();
+                    constants.bind(name, Binding::default());
+                    found
+                }
+                ast::Stmt::ClassDef(ast::StmtClassDef { name, body, .. }) => {
+                    self.constants
+                        .borrow_mut()
+                        .push(ScopeKind::Class, std::collections::HashSet::new());
+                    let found = self.analyze_block(body, rf);
+                    let mut constants = self.constants.borrow_mut();
+                    constants.pop();
+                    constants.bind(name, Binding::default());
+                    found
+                }
+                ast::Stmt::With(ast::StmtWith { body, .. })
                 | ast::Stmt::AsyncWith(ast::StmtAsyncWith { body, .. }) => {
-                    self.analyze_stmts(body, rf)
+                    let targets = constants::assigned_names(std::slice::from_ref(stmt));
+                    self.constants.borrow_mut().invalidate(&targets);
+                    self.analyze_block(body, rf)
                 }
 
                 ast::Stmt::Try(t) => {
@@ -160,20 +573,47 @@ impl SqlFinder {
                 ast::Stmt::TryStar(t) => {
                     self.analyze_try(&t.body, &t.orelse, &t.finalbody, &t.handlers, rf)
                 }
-                ast::Stmt::Match(f) => f
-                    .cases
-                    .iter()
-                    .flat_map(|c| self.analyze_stmts(&c.body, rf))
-                    .collect(),
-
-                ast::Stmt::Expr(e) => self.analyze_stmt_expr(e, rf),
-                ast::Stmt::Return(_)
-                | ast::Stmt::Import(_)
-                | ast::Stmt::ImportFrom(_)
-                | ast::Stmt::Continue(_)
+                ast::Stmt::Match(f) => {
+                    let before = self.constants.borrow().snapshot();
+                    let mut merged = before.clone();
+                    let mut found = Vec::new();
+                    for case in &f.cases {
+                        self.constants.borrow_mut().restore(before.clone());
+                        found.extend(self.analyze_block(&case.body, rf));
+                        merged = Constants::merge(&merged, &self.constants.borrow().snapshot());
+                    }
+                    self.constants.borrow_mut().restore(merged);
+                    found
+                }
+
+                ast::Stmt::Expr(e) => self.analyze_expr(&e.value, rf),
+                ast::Stmt::Import(_) | ast::Stmt::ImportFrom(_) => {
+                    let bindings = self.import_bindings(stmt);
+                    let mut constants = self.constants.borrow_mut();
+                    for (name, binding) in bindings {
+                        constants.bind(&name, binding);
+                    }
+                    vec![]
+                }
+                ast::Stmt::Delete(_) => {
+                    self.constants
+                        .borrow_mut()
+                        .invalidate(&constants::assigned_names(std::slice::from_ref(stmt)));
+                    vec![]
+                }
+                ast::Stmt::Return(ast::StmtReturn { value, .. }) => {
+                    let returned = value.as_ref().map(|value| self.returned_binding(value, rf));
+                    self.constants.borrow_mut().add_return(returned);
+                    match value.as_deref() {
+                        Some(value @ ast::Expr::Call(_)) => self.analyze_expr(value, rf),
+                        _ => vec![],
+                    }
+                }
+                ast::Stmt::Continue(_)
                 | ast::Stmt::Assert(_)
-                | ast::Stmt::Delete(_)
-                | ast::Stmt::Raise(_) => {
+                | ast::Stmt::Raise(_)
+                | ast::Stmt::Global(_)
+                | ast::Stmt::Nonlocal(_) => {
                     vec![]
                 }
                 _ => {
@@ -184,41 +624,47 @@ impl SqlFinder {
             results.extend(stmt_results);
         }
 
+        self.constants.borrow_mut().exit_block(enclosing);
         results
     }
 
     fn analyze_body_and_orelse(
         &self,
-        body: &Vec<ast::Stmt>,
-        orelse: &Vec<ast::Stmt>,
+        body: &[ast::Stmt],
+        orelse: &[ast::Stmt],
         range_file: &preanalysis::PreanalyzedFile,
     ) -> Vec<SqlString> {
-        let body_results = self.analyze_stmts(body, range_file);
-        let orelse_results = self.analyze_stmts(orelse, range_file);
+        let body_results = self.analyze_block(body, range_file);
+        let orelse_results = self.analyze_block(orelse, range_file);
         body_results.into_iter().chain(orelse_results).collect()
     }
 
+    /// Handlers may start anywhere in the body, so the names it assigns aren't known in them
     fn analyze_try(
         &self,
-        body: &Vec<ast::Stmt>,
-        orelse: &Vec<ast::Stmt>,
-        finalbody: &Vec<ast::Stmt>,
+        body: &[ast::Stmt],
+        orelse: &[ast::Stmt],
+        finalbody: &[ast::Stmt],
         handlers: &[ast::ExceptHandler],
         range_file: &preanalysis::PreanalyzedFile,
     ) -> Vec<SqlString> {
-        let body_results = self.analyze_stmts(body, range_file);
+        let before = self.constants.borrow().snapshot();
+        let body_results = self.analyze_block(body, range_file);
+        let orelse_results = self.analyze_block(orelse, range_file);
+        let mut merged = self.constants.borrow().snapshot();
 
-        let handler_results: Vec<SqlString> = handlers
-            .iter()
-            .filter_map(|h| {
-                h.as_except_handler()
-                    .map_or_else(|| None, |eh| Some(self.analyze_stmts(&eh.body, range_file)))
-            })
-            .flatten()
-            .collect();
+        let mut handler_results = Vec::new();
+        for handler in handlers.iter().filter_map(|h| h.as_except_handler()) {
+            let mut constants = self.constants.borrow_mut();
+            constants.restore(before.clone());
+            constants.invalidate(&constants::assigned_names(body));
+            drop(constants);
+            handler_results.extend(self.analyze_block(&handler.body, range_file));
+            merged = Constants::merge(&merged, &self.constants.borrow().snapshot());
+        }
+        self.constants.borrow_mut().restore(merged);
 
-        let orelse_results = self.analyze_stmts(orelse, range_file);
-        let finally_results = self.analyze_stmts(finalbody, range_file);
+        let finally_results = self.analyze_block(finalbody, range_file);
 
         body_results
             .into_iter()
@@ -0,0 +1,182 @@
+// Decoding of Python string literal source text, keeping track of where every decoded byte
+// came from. The AST only carries decoded values and, for f-strings, reports the range of the
+// whole literal on every part, so positions inside literals have to be recovered from source.
+use crate::finder_types::MappedStr;
+
+#[derive(Debug)]
+pub enum LiteralPart {
+    Text(MappedStr),
+    // Replacement field of an f-string, its value comes from the matching `FormattedValue`
+    Field,
+}
+
+/// Decodes the source of a (possibly implicitly concatenated) string literal starting at `base`.
+/// Returns `None` for anything it can't decode exactly, callers then fall back to coarse mapping.
+pub fn decode_literal(src: &str, base: usize) -> Option<Vec<LiteralPart>> {
+    let mut parts = Vec::new();
+    let mut text = MappedStr::default();
+    let mut idx = 0;
+
+    while idx < src.len() {
+        idx = skip_between_literals(src, idx);
+        if idx >= src.len() {
+            break;
+        }
+
+        let prefix_len = src[idx..]
+            .find(|c: char| !matches!(c, 'r' | 'R' | 'b' | 'B' | 'u' | 'U' | 'f' | 'F'))?;
+        let prefix = src[idx..idx + prefix_len].to_ascii_lowercase();
+        if prefix.contains('b') {
+            return None;
+        }
+        let raw = prefix.contains('r');
+        let fstr = prefix.contains('f');
+        idx += prefix_len;
+
+        let quote = src[idx..]
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')?;
+        let delim = if src[idx..].starts_with(&quote.to_string().repeat(3)) {
+            quote.to_string().repeat(3)
+        } else {
+            quote.to_string()
+        };
+        idx += delim.len();
+
+        loop {
+            let rest = src.get(idx..)?;
+            if rest.starts_with(&delim) {
+                idx += delim.len();
+                break;
+            }
+            let ch = rest.chars().next()?;
+            match ch {
+                '\\' if raw => {
+                    text.push_char('\\', base + idx);
+                    idx += 1;
+                    if let Some(next) = src[idx..].chars().next() {
+                        text.push_char(next, base + idx);
+                        idx += next.len_utf8();
+                    }
+                }
+                '\\' => idx = decode_escape(src, idx, base, &mut text)?,
+                '{' | '}' if fstr && rest[1..].starts_with(ch) => {
+                    text.push_char(ch, base + idx);
+                    idx += 2;
+                }
+                '{' if fstr => {
+                    parts.push(LiteralPart::Text(std::mem::take(&mut text)));
+                    parts.push(LiteralPart::Field);
+                    idx = skip_field(src, idx)?;
+                }
+                '}' if fstr => return None,
+                '\r' => {
+                    // Python normalizes every newline flavour inside literals to `\n`
+                    text.push_char('\n', base + idx);
+                    idx += if rest.starts_with("\r\n") { 2 } else { 1 };
+                }
+                _ => {
+                    text.push_char(ch, base + idx);
+                    idx += ch.len_utf8();
+                }
+            }
+        }
+    }
+
+    parts.push(LiteralPart::Text(text));
+    Some(parts)
+}
+
+// Skips whitespace, comments and line continuations between implicitly concatenated literals.
+fn skip_between_literals(src: &str, mut idx: usize) -> usize {
+    while let Some(ch) = src[idx..].chars().next() {
+        match ch {
+            ' ' | '\t' | '\n' | '\r' | '\x0c' | '\\' => idx += 1,
+            '#' => idx = src[idx..].find('\n').map_or(src.len(), |nl| idx + nl),
+            _ => break,
+        }
+    }
+    idx
+}
+
+// Returns the offset right after the escape sequence starting at `idx`.
+fn decode_escape(src: &str, idx: usize, base: usize, text: &mut MappedStr) -> Option<usize> {
+    let origin = base + idx;
+    let next = src[idx + 1..].chars().next()?;
+    let after = idx + 1 + next.len_utf8();
+
+    let simple = match next {
+        '\n' => return Some(after),
+        '\r' => {
+            return Some(if src[after..].starts_with('\n') {
+                after + 1
+            } else {
+                after
+            });
+        }
+        '\\' | '\'' | '"' => Some(next),
+        'a' => Some('\x07'),
+        'b' => Some('\x08'),
+        'f' => Some('\x0c'),
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        't' => Some('\t'),
+        'v' => Some('\x0b'),
+        _ => None,
+    };
+    if let Some(ch) = simple {
+        text.push_char(ch, origin);
+        return Some(after);
+    }
+
+    let (radix, max_digits, digits_start) = match next {
+        '0'..='7' => (8, 3, idx + 1),
+        'x' => (16, 2, after),
+        'u' => (16, 4, after),
+        'U' => (16, 8, after),
+        // Named unicode escapes would need the unicode name database
+        'N' => return None,
+        _ => {
+            text.push_char('\\', origin);
+            text.push_char(next, origin + 1);
+            return Some(after);
+        }
+    };
+
+    let digits: String = src[digits_start..]
+        .chars()
+        .take(max_digits)
+        .take_while(|c| c.is_digit(radix))
+        .collect();
+    if radix == 16 && digits.len() != max_digits {
+        return None;
+    }
+    let ch = char::from_u32(u32::from_str_radix(&digits, radix).ok()?)?;
+    text.push_char(ch, origin);
+    Some(digits_start + digits.len())
+}
+
+// Returns the offset right after the replacement field whose `{` is at `idx`.
+fn skip_field(src: &str, idx: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut chars = src[idx..].char_indices();
+
+    while let Some((rel, ch)) = chars.next() {
+        match ch {
+            '{' | '[' | '(' => depth += 1,
+            '}' | ']' | ')' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(idx + rel + 1);
+                }
+            }
+            '\'' | '"' => {
+                // Nested literals can't contain the outer quote, so the next one closes them
+                chars.by_ref().find(|(_, c)| *c == ch)?;
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,184 @@
+use crate::finder_types::CellMap;
+use crate::preanalysis::{LineCol, Range};
+use rustpython_parser::{Parse, ast};
+use serde::Deserialize;
+
+/// Cell magics that run their body as Python, the body of others like `%%bash` isn't checked
+const PYTHON_CELL_MAGICS: [&str; 5] = ["time", "timeit", "capture", "prun", "debug"];
+
+/// Code cells of a Jupyter notebook joined into a single module, in order. Lines that aren't
+/// Python, like IPython magics and cells that don't parse, are blanked so every line keeps its place.
+pub struct Notebook {
+    pub source: String,
+    pub cells: CellMap,
+    /// SQL of `%%sql` cells and where it starts in `source`
+    pub sql_cells: Vec<(String, Range)>,
+    /// Code cells that aren't Python even without their magics, with the parse error
+    pub invalid_cells: Vec<(usize, String)>,
+    /// Language of the notebook's kernel, if it says
+    pub language: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawNotebook {
+    cells: Vec<RawCell>,
+    #[serde(default)]
+    metadata: RawMetadata,
+}
+
+#[derive(Deserialize, Default)]
+struct RawMetadata {
+    language_info: Option<RawLanguageInfo>,
+}
+
+#[derive(Deserialize)]
+struct RawLanguageInfo {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RawCell {
+    cell_type: String,
+    #[serde(default)]
+    source: RawSource,
+}
+
+/// Cell source is stored either as a single string or as a list of lines
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawSource {
+    Lines(Vec<String>),
+    Text(String),
+}
+
+impl Default for RawSource {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl RawSource {
+    fn text(&self) -> String {
+        match self {
+            Self::Lines(lines) => lines.concat(),
+            Self::Text(text) => text.clone(),
+        }
+    }
+}
+
+impl Notebook {
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let raw: RawNotebook = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let mut notebook = Self {
+            source: String::new(),
+            cells: CellMap::default(),
+            sql_cells: Vec::new(),
+            invalid_cells: Vec::new(),
+            language: raw.metadata.language_info.map(|info| info.name),
+        };
+
+        let mut first_line = 1;
+        let code_cells = raw.cells.iter().filter(|cell| cell.cell_type == "code");
+        for (index, cell) in code_cells.enumerate() {
+            notebook.cells.push(first_line, index + 1);
+            let python = notebook.python_of(index + 1, &cell.source.text(), first_line);
+            first_line += python.matches('\n').count();
+            notebook.source.push_str(&python);
+        }
+        Ok(notebook)
+    }
+
+    /// Python code of a cell, one line for each of the cell's lines
+    fn python_of(&mut self, cell: usize, code: &str, first_line: usize) -> String {
+        let lines: Vec<&str> = code.lines().collect();
+        let blank = "\n".repeat(lines.len());
+
+        if let Some(magic) = lines
+            .first()
+            .and_then(|line| line.trim_start().strip_prefix("%%"))
+        {
+            let name = magic.split_whitespace().next().unwrap_or_default();
+            if name == "sql" {
+                self.push_sql_cell(code, first_line);
+                return blank;
+            }
+            if !PYTHON_CELL_MAGICS.contains(&name) {
+                return blank;
+            }
+            let body = joined(std::iter::once("").chain(lines[1..].iter().copied()));
+            return if parses(&body) { body } else { blank };
+        }
+
+        let code = joined(lines.iter().copied());
+        if parses(&code) {
+            return code;
+        }
+        let stripped = joined(lines.iter().map(|line| {
+            if is_magic(line) {
+                let indent = line.len() - line.trim_start().len();
+                format!("{}pass", &line[..indent])
+            } else {
+                (*line).to_string()
+            }
+        }));
+        match ast::Suite::parse(&stripped, "<cell>") {
+            Ok(_) => stripped,
+            Err(e) => {
+                self.invalid_cells.push((cell, e.to_string()));
+                blank
+            }
+        }
+    }
+
+    /// Records the SQL after the `%%sql` line, leading whitespace skipped
+    fn push_sql_cell(&mut self, code: &str, first_line: usize) {
+        let Some(newline) = code.find('\n') else {
+            return;
+        };
+        let body = &code[newline + 1..];
+        let sql = body.trim();
+        if sql.is_empty() {
+            return;
+        }
+        let skipped = &body[..body.len() - body.trim_start().len()];
+        let line = first_line + 1 + skipped.matches('\n').count();
+        let col = skipped
+            .rfind('\n')
+            .map_or(skipped, |nl| &skipped[nl + 1..])
+            .chars()
+            .count()
+            + 1;
+        // The cell starts where the source joined so far ends
+        let offset = self.source.len() + newline + 1 + skipped.len();
+        let start = LineCol::new(line, col, offset);
+        self.sql_cells
+            .push((sql.to_string(), Range::verbatim(start, sql)));
+    }
+}
+
+fn joined<S: AsRef<str>>(lines: impl Iterator<Item = S>) -> String {
+    lines.fold(String::new(), |mut code, line| {
+        code.push_str(line.as_ref());
+        code.push('\n');
+        code
+    })
+}
+
+fn parses(code: &str) -> bool {
+    ast::Suite::parse(code, "<cell>").is_ok()
+}
+
+/// Line magics like `%sql ...` and shell commands like `!pip install`, also when assigned
+fn is_magic(line: &str) -> bool {
+    let line = line.trim_start();
+    if line.starts_with(['%', '!']) {
+        return true;
+    }
+    line.split_once('=').is_some_and(|(target, value)| {
+        !target.trim().is_empty()
+            && target
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | ',' | ' '))
+            && value.trim_start().starts_with(['%', '!'])
+    })
+}
@@ -2,7 +2,7 @@ use std::fmt::Display;
 
 use rangemap::RangeMap;
 use rustpython_parser::text_size::TextRange;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
 pub struct LineCol {
@@ -19,6 +19,34 @@ impl LineCol {
             byte_offset,
         }
     }
+
+    pub const fn line(&self) -> usize {
+        self.line
+    }
+
+    pub const fn col(&self) -> usize {
+        self.col
+    }
+
+    pub const fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+}
+
+impl LineCol {
+    /// Position reached after walking over `text`, which appears verbatim in the source.
+    fn advanced_by(&self, text: &str) -> Self {
+        text.chars().fold(self.clone(), |mut pos, ch| {
+            if ch == '\n' {
+                pos.line += 1;
+                pos.col = 1;
+            } else {
+                pos.col += 1;
+            }
+            pos.byte_offset += ch.len_utf8();
+            pos
+        })
+    }
 }
 
 impl Display for LineCol {
@@ -27,20 +55,76 @@ impl Display for LineCol {
     }
 }
 
+/// Source span of the Python expression or raw SQL a SQL string was found in
 #[derive(Debug, Clone)]
 pub struct Range {
     pub start: LineCol,
+    /// Position right after the span
+    pub end: LineCol,
+}
+
+impl Range {
+    /// Span of `text`, which appears verbatim in the source from `start`.
+    pub fn verbatim(start: LineCol, text: &str) -> Self {
+        Self {
+            end: start.advanced_by(text),
+            start,
+        }
+    }
+}
+
+/// Maps byte offsets of extracted SQL text back to positions in the source file.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    spans: Vec<Span>,
+}
+
+#[derive(Debug, Clone)]
+struct Span {
+    // Offset into the extracted text where this span begins
+    offset: usize,
+    start: LineCol,
+    // Verbatim spans are copied char for char from the source, others all map to `start`
+    verbatim: bool,
+}
+
+impl SourceMap {
+    /// A map for text copied verbatim from the source, beginning at `start`.
+    pub fn verbatim(start: LineCol) -> Self {
+        Self {
+            spans: vec![Span {
+                offset: 0,
+                start,
+                verbatim: true,
+            }],
+        }
+    }
+
+    /// Source position of the byte at `offset` in `text`, the string this map was built for.
+    pub fn locate(&self, text: &str, offset: usize) -> Option<LineCol> {
+        let offset = offset.min(text.len());
+        let idx = self.spans.partition_point(|span| span.offset <= offset);
+        let span = self.spans.get(idx.checked_sub(1)?)?;
+
+        if !span.verbatim {
+            return Some(span.start.clone());
+        }
+        text.get(span.offset..offset)
+            .map(|walked| span.start.advanced_by(walked))
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct ByteRange {
     start: usize,
+    end: usize,
 }
 
 impl From<TextRange> for ByteRange {
     fn from(value: TextRange) -> Self {
         Self {
             start: value.start().to_usize(),
+            end: value.end().to_usize(),
         }
     }
 }
@@ -49,12 +133,18 @@ impl From<TextRange> for ByteRange {
 struct PragmaMap {
     // Maps filename to a set of lines to ignore from the analysis
     ignores: HashSet<usize>,
+    // Dialects named by `# sqint: dialect=...` comments after code, by line
+    dialects: HashMap<usize, String>,
+    // Dialect named by the first such comment on a line of its own
+    file_dialect: Option<String>,
 }
 
 impl PragmaMap {
     fn new() -> Self {
         Self {
             ignores: HashSet::new(),
+            dialects: HashMap::new(),
+            file_dialect: None,
         }
     }
 
@@ -62,6 +152,25 @@ impl PragmaMap {
         self.ignores.insert(line);
     }
 
+    fn add_dialect(&mut self, line: usize, line_text: &str) {
+        let Some((code, comment)) = line_text.split_once('#') else {
+            return;
+        };
+        let Some(dialect) = comment
+            .trim()
+            .strip_prefix("sqint:")
+            .and_then(|pragma| pragma.trim_start().strip_prefix("dialect="))
+            .and_then(|dialect| dialect.split_whitespace().next())
+        else {
+            return;
+        };
+        if !code.trim().is_empty() {
+            self.dialects.insert(line, dialect.to_string());
+        } else if self.file_dialect.is_none() {
+            self.file_dialect = Some(dialect.to_string());
+        }
+    }
+
     pub fn should_ignore_line(&self, line: usize) -> bool {
         self.ignores.contains(&line)
     }
@@ -97,6 +206,7 @@ impl<'a> PreanalyzedFile<'a> {
                 if Self::line_has_pragma(line_text) {
                     pragmas.add_ignore(line);
                 }
+                pragmas.add_dialect(line, line_text);
 
                 range_map.insert(last_line_start..(offset + 1), line);
                 line += 1;
@@ -110,6 +220,7 @@ impl<'a> PreanalyzedFile<'a> {
             if Self::line_has_pragma(line_text) {
                 pragmas.add_ignore(line);
             }
+            pragmas.add_dialect(line, line_text);
         }
 
         range_map.insert(last_line_start..src.len() + 1, line);
@@ -132,23 +243,76 @@ impl<'a> PreanalyzedFile<'a> {
         false
     }
 
+    /// Dialect a `# sqint: dialect=...` comment after the code of `line` names for its SQL
+    pub(crate) fn dialect_at(&self, line: usize) -> Option<&str> {
+        self.pragmas.dialects.get(&line).map(String::as_str)
+    }
+
+    /// Dialect a `# sqint: dialect=...` comment on a line of its own names for the whole file
+    pub(crate) fn file_dialect(&self) -> Option<&str> {
+        self.pragmas.file_dialect.as_deref()
+    }
+
+    /// Source text of `range`
+    pub(crate) fn text(&self, range: &Range) -> &'a str {
+        &self.src[range.start.byte_offset()..range.end.byte_offset()]
+    }
+
+    pub(crate) fn src(&self, range: TextRange) -> &'a str {
+        &self.src[range.start().to_usize()..range.end().to_usize()]
+    }
+
     pub fn byterange_to_range(&self, byte_range: ByteRange) -> Range {
-        let (start_line_byte_range, start_line_number) = self
+        Range {
+            start: self.linecol_at(byte_range.start),
+            end: self.linecol_at(byte_range.end),
+        }
+    }
+
+    pub fn linecol_at(&self, byte_offset: usize) -> LineCol {
+        let (line_byte_range, line_number) = self
             .map
-            .get_key_value(&byte_range.start)
+            .get_key_value(&byte_offset)
             .expect("We analyze files right before calculating linecols.");
 
-        let start_col = self.src[start_line_byte_range.start..byte_range.start]
-            .chars()
-            .count()
-            + 1;
+        let col = self.src[line_byte_range.start..byte_offset].chars().count() + 1;
 
-        let start = LineCol {
-            col: start_col,
-            line: *start_line_number,
-            byte_offset: byte_range.start,
-        };
+        LineCol {
+            col,
+            line: *line_number,
+            byte_offset,
+        }
+    }
+
+    /// Builds the source map for `text`, given the source byte offset every byte came from.
+    pub(crate) fn source_map(&self, text: &str, origins: &[usize]) -> SourceMap {
+        let mut spans: Vec<Span> = Vec::new();
+        let mut prev: Option<(usize, usize)> = None;
+
+        for (offset, ch) in text.char_indices() {
+            let origin = origins[offset];
+            let continues = match (spans.last_mut(), prev) {
+                (Some(last), Some((prev_origin, prev_len))) => {
+                    let single_char = last.offset + prev_len == offset;
+                    if origin == prev_origin && (single_char || !last.verbatim) {
+                        last.verbatim = false;
+                        true
+                    } else {
+                        last.verbatim && origin == prev_origin + prev_len
+                    }
+                }
+                _ => false,
+            };
+            if !continues {
+                spans.push(Span {
+                    offset,
+                    start: self.linecol_at(origin),
+                    verbatim: true,
+                });
+            }
+            prev = Some((origin, ch.len_utf8()));
+        }
 
-        Range { start }
+        SourceMap { spans }
     }
 }
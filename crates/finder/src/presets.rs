@@ -0,0 +1,321 @@
+use rustpython_parser::ast;
+
+use crate::finder_types::DriverImport;
+
+/// Way query parameters are marked in SQL text, as in PEP 249's `paramstyle`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParamStyle {
+    /// `?`
+    Qmark,
+    /// `:name`
+    Named,
+    /// `%s`
+    Format,
+    /// `%(name)s`
+    Pyformat,
+    /// `$1`, as PostgreSQL and asyncpg take them
+    Dollar,
+}
+
+/// Styles DB API drivers may take, depending on the driver
+const DB_API_STYLES: &[ParamStyle] = &[
+    ParamStyle::Qmark,
+    ParamStyle::Named,
+    ParamStyle::Format,
+    ParamStyle::Pyformat,
+];
+
+const FORMAT_STYLES: &[ParamStyle] = &[ParamStyle::Format, ParamStyle::Pyformat];
+
+// Keyword names DB API drivers take the query parameters under
+pub(crate) const PARAM_KEYWORDS: &[&str] =
+    &["params", "parameters", "args", "vars", "seq_of_parameters"];
+
+/// Argument of a call, given by position or by one of its keyword names
+#[derive(Debug)]
+pub(crate) struct Argument {
+    pub position: Option<usize>,
+    pub keywords: &'static [&'static str],
+}
+
+impl Argument {
+    pub(crate) fn of<'a>(&self, call: &'a ast::ExprCall) -> Option<&'a ast::Expr> {
+        call.keywords
+            .iter()
+            .find(|kw| {
+                kw.arg
+                    .as_ref()
+                    .is_some_and(|arg| self.keywords.contains(&arg.as_str()))
+            })
+            .map(|kw| &kw.value)
+            .or_else(|| self.position.and_then(|position| call.args.get(position)))
+    }
+}
+
+/// Where a function takes the parameters of its query
+#[derive(Debug)]
+pub(crate) enum Params {
+    /// Doesn't take parameters
+    None,
+    /// As one argument, a sequence or mapping
+    Argument(Argument),
+    /// As every positional argument from this position on
+    Rest(usize),
+}
+
+/// Function of a database API that takes SQL
+#[derive(Debug)]
+pub(crate) struct ApiFunction {
+    /// Glob the called name is matched against
+    pub pattern: &'static str,
+    pub sql: Argument,
+    pub params: Params,
+    /// Styles of the parameter markers the SQL may hold
+    pub styles: &'static [ParamStyle],
+    /// Takes a sequence of parameter rows
+    pub many: bool,
+    /// Returns an object holding the SQL, which is executed in its place
+    pub wraps: bool,
+    /// Its result composes SQL with `.format()`, quoting the values filled in
+    pub composable: bool,
+}
+
+/// Functions of a database API, toggled together by name
+#[derive(Debug)]
+pub(crate) struct Preset {
+    pub name: &'static str,
+    /// Modules of the API. In files importing one of them, its functions describe the names
+    /// they match over those of presets matched before.
+    pub modules: &'static [&'static str],
+    pub functions: &'static [ApiFunction],
+}
+
+const fn sql(keywords: &'static [&'static str]) -> Argument {
+    Argument {
+        position: Some(0),
+        keywords,
+    }
+}
+
+const fn params(position: Option<usize>, keywords: &'static [&'static str]) -> Params {
+    Params::Argument(Argument { position, keywords })
+}
+
+const fn function(
+    pattern: &'static str,
+    sql: Argument,
+    params: Params,
+    styles: &'static [ParamStyle],
+) -> ApiFunction {
+    ApiFunction {
+        pattern,
+        sql,
+        params,
+        styles,
+        many: false,
+        wraps: false,
+        composable: false,
+    }
+}
+
+/// Presets in the order they are matched in. The first function matching a name describes it,
+/// unless a later preset belongs to a module the file imports.
+pub(crate) const PRESETS: &[Preset] = &[
+    Preset {
+        name: "dbapi",
+        modules: &[],
+        functions: &[
+            function(
+                "*.execute",
+                sql(&["operation", "query", "sql"]),
+                params(Some(1), PARAM_KEYWORDS),
+                DB_API_STYLES,
+            ),
+            ApiFunction {
+                many: true,
+                ..function(
+                    "*.executemany",
+                    sql(&["operation", "query", "sql"]),
+                    params(Some(1), PARAM_KEYWORDS),
+                    DB_API_STYLES,
+                )
+            },
+            function("*.executescript", sql(&["sql_script"]), Params::None, &[]),
+        ],
+    },
+    Preset {
+        name: "sqlalchemy",
+        modules: &["sqlalchemy"],
+        functions: &[
+            ApiFunction {
+                wraps: true,
+                ..function("text", sql(&["text"]), Params::None, &[ParamStyle::Named])
+            },
+            ApiFunction {
+                wraps: true,
+                ..function("*.text", sql(&["text"]), Params::None, &[ParamStyle::Named])
+            },
+            function(
+                "*.exec_driver_sql",
+                sql(&["statement"]),
+                params(Some(1), &["parameters"]),
+                DB_API_STYLES,
+            ),
+        ],
+    },
+    Preset {
+        name: "pandas",
+        modules: &["pandas"],
+        functions: &[
+            function(
+                "read_sql",
+                sql(&["sql"]),
+                params(None, &["params"]),
+                DB_API_STYLES,
+            ),
+            function(
+                "*.read_sql",
+                sql(&["sql"]),
+                params(None, &["params"]),
+                DB_API_STYLES,
+            ),
+            function(
+                "read_sql_query",
+                sql(&["sql"]),
+                params(None, &["params"]),
+                DB_API_STYLES,
+            ),
+            function(
+                "*.read_sql_query",
+                sql(&["sql"]),
+                params(None, &["params"]),
+                DB_API_STYLES,
+            ),
+        ],
+    },
+    Preset {
+        name: "django",
+        modules: &["django"],
+        functions: &[
+            function(
+                "*.objects.raw",
+                sql(&["raw_query"]),
+                params(Some(1), &["params"]),
+                FORMAT_STYLES,
+            ),
+            ApiFunction {
+                wraps: true,
+                ..function(
+                    "RawSQL",
+                    sql(&["sql"]),
+                    params(Some(1), &["params"]),
+                    FORMAT_STYLES,
+                )
+            },
+            ApiFunction {
+                wraps: true,
+                ..function(
+                    "*.RawSQL",
+                    sql(&["sql"]),
+                    params(Some(1), &["params"]),
+                    FORMAT_STYLES,
+                )
+            },
+            function(
+                "*.execute",
+                sql(&["sql"]),
+                params(Some(1), &["params"]),
+                FORMAT_STYLES,
+            ),
+        ],
+    },
+    Preset {
+        name: "asyncpg",
+        modules: &["asyncpg"],
+        functions: &[
+            function(
+                "*.fetch",
+                sql(&["query"]),
+                Params::Rest(1),
+                &[ParamStyle::Dollar],
+            ),
+            function(
+                "*.fetchrow",
+                sql(&["query"]),
+                Params::Rest(1),
+                &[ParamStyle::Dollar],
+            ),
+            function(
+                "*.execute",
+                sql(&["query"]),
+                Params::Rest(1),
+                &[ParamStyle::Dollar],
+            ),
+        ],
+    },
+    Preset {
+        name: "psycopg",
+        modules: &["psycopg", "psycopg2"],
+        functions: &[
+            ApiFunction {
+                wraps: true,
+                composable: true,
+                ..function("sql.SQL", sql(&["obj"]), Params::None, FORMAT_STYLES)
+            },
+            ApiFunction {
+                wraps: true,
+                composable: true,
+                ..function("*.sql.SQL", sql(&["obj"]), Params::None, FORMAT_STYLES)
+            },
+        ],
+    },
+];
+
+/// Modules of database drivers, with the dialect of the databases they connect to
+const DRIVER_DIALECTS: &[(&str, &str)] = &[
+    ("psycopg2", "postgres"),
+    ("psycopg", "postgres"),
+    ("asyncpg", "postgres"),
+    ("sqlite3", "sqlite"),
+    ("pymysql", "mysql"),
+    ("MySQLdb", "mysql"),
+    ("google.cloud.bigquery", "bigquery"),
+    ("snowflake.connector", "snowflake"),
+    ("clickhouse_driver", "clickhouse"),
+    ("duckdb", "duckdb"),
+];
+
+/// Drivers among imported modules, each once, in the order they're first imported
+pub(crate) fn imported_drivers(modules: &[String]) -> Vec<DriverImport> {
+    let mut drivers: Vec<DriverImport> = Vec::new();
+    for module in modules {
+        let driver = DRIVER_DIALECTS
+            .iter()
+            .find(|(driver, _)| is_within(module, driver));
+        if let Some(&(driver, dialect)) = driver
+            && !drivers.iter().any(|known| known.module == driver)
+        {
+            drivers.push(DriverImport {
+                module: driver.to_string(),
+                dialect: dialect.to_string(),
+            });
+        }
+    }
+    drivers
+}
+
+/// Whether `module` is `package` or one of its submodules
+pub(crate) fn is_within(module: &str, package: &str) -> bool {
+    module
+        .strip_prefix(package)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+/// Names of all presets
+#[must_use]
+pub fn preset_names() -> Vec<String> {
+    PRESETS
+        .iter()
+        .map(|preset| preset.name.to_string())
+        .collect()
+}
@@ -0,0 +1,235 @@
+use crate::preanalysis::{LineCol, PreanalyzedFile};
+use sqlparser::dialect::{Dialect, MySqlDialect};
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan, Tokenizer};
+use std::ops::Range;
+
+/// Words after `BEGIN` that start a transaction rather than a block
+const TRANSACTION_WORDS: [&str; 9] = [
+    "TRANSACTION",
+    "TRAN",
+    "WORK",
+    "DEFERRED",
+    "IMMEDIATE",
+    "EXCLUSIVE",
+    "ISOLATION",
+    "READ",
+    "DISTRIBUTED",
+];
+
+/// Words that follow an identifier, so a `BEGIN` before them is a column rather than a block
+const CLAUSE_WORDS: [&str; 20] = [
+    "FROM", "AS", "AND", "OR", "IS", "IN", "NOT", "BETWEEN", "LIKE", "ASC", "DESC", "WHERE",
+    "THEN", "ELSE", "WHEN", "END", "ON", "ORDER", "GROUP", "UNION",
+];
+
+/// Words after `END` that close a statement other than `BEGIN` or `CASE`, like `END IF`
+const END_WORDS: [&str; 5] = ["IF", "LOOP", "WHILE", "REPEAT", "FOR"];
+
+/// Statement of a SQL file: the byte range of its text, comments around it left out, and where it starts
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub range: Range<usize>,
+    pub start: LineCol,
+}
+
+/// Splits a SQL file into its statements, based on the tokens of `dialect` so delimiters in
+/// strings, comments and dollar-quoted bodies are left alone. Statements end at a `;` outside of
+/// `BEGIN ... END` blocks, or at the delimiter set by a `DELIMITER` line. `GO` lines end a batch.
+pub fn split(source: &str, dialect: &dyn Dialect) -> Vec<Statement> {
+    let file = PreanalyzedFile::from_src(source);
+    regions(source)
+        .into_iter()
+        .flat_map(|(range, delimiter)| split_region(source, range, delimiter, dialect))
+        .filter(|range| !range.is_empty())
+        .map(|range| Statement {
+            start: file.linecol_at(range.start),
+            range,
+        })
+        .collect()
+}
+
+/// Parts of the source between `DELIMITER` and `GO` lines, with the delimiter that ends their
+/// statements. These are client directives, so they're recognized per line like clients do.
+fn regions(source: &str) -> Vec<(Range<usize>, &str)> {
+    let mut regions = Vec::new();
+    let mut delimiter = ";";
+    let mut start = 0;
+    let mut offset = 0;
+
+    for line in source.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let mut words = line.split_whitespace();
+        let directive = match (words.next(), words.next(), words.next()) {
+            (Some(word), Some(new_delimiter), None) if word.eq_ignore_ascii_case("DELIMITER") => {
+                Some(new_delimiter)
+            }
+            (Some(word), count, None)
+                if word.eq_ignore_ascii_case("GO")
+                    && count.is_none_or(|count| count.parse::<u32>().is_ok()) =>
+            {
+                Some(delimiter)
+            }
+            _ => None,
+        };
+        if let Some(new_delimiter) = directive {
+            regions.push((start..line_start, delimiter));
+            delimiter = new_delimiter;
+            start = offset;
+        }
+    }
+    regions.push((start..source.len(), delimiter));
+    regions
+}
+
+fn split_region(
+    source: &str,
+    region: Range<usize>,
+    delimiter: &str,
+    dialect: &dyn Dialect,
+) -> Vec<Range<usize>> {
+    let text = &source[region.clone()];
+    // A custom delimiter like `$$` would start a dollar-quoted string in other dialects,
+    // DELIMITER is a MySQL directive anyway
+    let dialect: &dyn Dialect = if delimiter == ";" {
+        dialect
+    } else {
+        &MySqlDialect {}
+    };
+    let mut tokens = Vec::new();
+    let complete = Tokenizer::new(dialect, text)
+        .tokenize_with_location_into_buf(&mut tokens)
+        .is_ok();
+
+    let mut offsets = Offsets::new(text);
+    let significant: Vec<(&Token, Range<usize>)> = tokens
+        .iter()
+        .filter(|t| !matches!(t.token, Token::Whitespace(_)))
+        .map(|t| (&t.token, offsets.range_of(t)))
+        .collect();
+
+    let mut statements = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+    let mut depth = 0usize;
+    let mut skip_until = 0;
+    for (index, (token, range)) in significant.iter().enumerate() {
+        if range.start < skip_until {
+            continue;
+        }
+        let ends = if delimiter == ";" {
+            let previous = index.checked_sub(1).map(|i| significant[i].0);
+            let next = significant.get(index + 1).map(|(t, _)| *t);
+            depth = nesting(depth, token, previous, next);
+            depth == 0 && matches!(token, Token::SemiColon)
+        } else {
+            text[range.start..].starts_with(delimiter)
+        };
+        if ends {
+            statements.extend(current.take());
+            skip_until = range.start + delimiter.len();
+        } else {
+            current = Some(current.map_or(range.clone(), |c| c.start..range.end));
+        }
+    }
+
+    // Text the tokenizer gave up on is kept with the statement it's in, to be reported when parsed
+    if !complete {
+        let start = current.map_or(skip_until, |c| c.start);
+        let rest = &text[start..];
+        let leading = rest.len() - rest.trim_start().len();
+        current = Some(start + leading..start + rest.trim_end().len());
+    }
+    statements.extend(current);
+    statements
+        .into_iter()
+        .map(|range| region.start + range.start..region.start + range.end)
+        .collect()
+}
+
+/// Block depth after `token`: `BEGIN` and `CASE` open a block that `END` closes
+fn nesting(depth: usize, token: &Token, previous: Option<&Token>, next: Option<&Token>) -> usize {
+    let (Some(word), previous_word, next_word) =
+        (keyword(Some(token)), keyword(previous), keyword(next))
+    else {
+        return depth;
+    };
+    let is_any =
+        |words: &[&str]| next_word.is_some_and(|w| words.iter().any(|k| k.eq_ignore_ascii_case(w)));
+
+    if word.eq_ignore_ascii_case("CASE") {
+        // The `CASE` of `END CASE` was closed by its `END`
+        if previous_word.is_some_and(|w| w.eq_ignore_ascii_case("END")) {
+            depth
+        } else {
+            depth + 1
+        }
+    } else if word.eq_ignore_ascii_case("BEGIN") {
+        // `BEGIN;` and `BEGIN TRANSACTION` start a transaction
+        if next_word.is_none() || is_any(&TRANSACTION_WORDS) || is_any(&CLAUSE_WORDS) {
+            depth
+        } else {
+            depth + 1
+        }
+    } else if word.eq_ignore_ascii_case("END") && !is_any(&END_WORDS) {
+        depth.saturating_sub(1)
+    } else {
+        depth
+    }
+}
+
+/// Unquoted word of a token
+fn keyword(token: Option<&Token>) -> Option<&str> {
+    match token {
+        Some(Token::Word(word)) if word.quote_style.is_none() => Some(word.value.as_str()),
+        _ => None,
+    }
+}
+
+/// Turns token locations into byte offsets. Locations are asked for in order, so each line is
+/// only walked once.
+struct Offsets<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+    // Last location asked for and its offset
+    line: u64,
+    column: u64,
+    offset: usize,
+}
+
+impl<'a> Offsets<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            line_starts: std::iter::once(0)
+                .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+                .collect(),
+            line: 1,
+            column: 1,
+            offset: 0,
+        }
+    }
+
+    fn range_of(&mut self, token: &TokenWithSpan) -> Range<usize> {
+        self.offset(token.span.start)..self.offset(token.span.end)
+    }
+
+    // Locations count lines and characters from 1
+    fn offset(&mut self, location: Location) -> usize {
+        if location.line != self.line || location.column < self.column {
+            let line_start = usize::try_from(location.line.saturating_sub(1))
+                .ok()
+                .and_then(|line| self.line_starts.get(line));
+            let Some(&line_start) = line_start else {
+                return self.text.len();
+            };
+            (self.line, self.column, self.offset) = (location.line, 1, line_start);
+        }
+        let chars = usize::try_from(location.column - self.column).unwrap_or(usize::MAX);
+        self.offset = self.text[self.offset..]
+            .char_indices()
+            .nth(chars)
+            .map_or(self.text.len(), |(i, _)| self.offset + i);
+        self.column = location.column;
+        self.offset
+    }
+}
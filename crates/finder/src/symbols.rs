@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Module-level string constants of a module, by name
+pub(crate) type ModuleConstants = HashMap<String, String>;
+
+/// String constants of the Python modules of a project, which imports are resolved to. Modules
+/// are read the first time they're imported and shared by all files checked with the table.
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    modules: Mutex<HashMap<PathBuf, Arc<ModuleConstants>>>,
+}
+
+impl SymbolTable {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops what is known about the module at `path`, so it's read again, like after an edit.
+    pub fn forget(&self, path: &Path) {
+        self.lock().remove(path);
+    }
+
+    pub(crate) fn get(&self, path: &Path) -> Option<Arc<ModuleConstants>> {
+        self.lock().get(path).cloned()
+    }
+
+    pub(crate) fn insert(&self, path: PathBuf, constants: Arc<ModuleConstants>) {
+        self.lock().insert(path, constants);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PathBuf, Arc<ModuleConstants>>> {
+        self.modules
+            .lock()
+            .expect("Finding threads don't panic holding the lock")
+    }
+}
+
+/// File of the module `from <level dots><module> import ...` refers to in the file `importer`.
+/// Relative imports start from the package of the importer; absolute ones are looked for in the
+/// directory of the importer and the directories above it, so no search path needs configuring.
+pub(crate) fn resolve_module(
+    importer: &Path,
+    module: Option<&str>,
+    level: usize,
+) -> Option<PathBuf> {
+    let dir = importer.parent()?;
+    let parts: Vec<&str> = module
+        .map(|module| module.split('.').collect())
+        .unwrap_or_default();
+    if level > 0 {
+        let package = dir.ancestors().nth(level - 1)?;
+        return module_file(package, &parts);
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    dir.ancestors().find_map(|root| module_file(root, &parts))
+}
+
+fn module_file(root: &Path, parts: &[&str]) -> Option<PathBuf> {
+    let path: PathBuf = parts
+        .iter()
+        .fold(root.to_path_buf(), |path, part| path.join(part));
+    let package = path.join("__init__.py");
+    let module = path.with_extension("py");
+    if !parts.is_empty() && module.is_file() {
+        Some(module)
+    } else if package.is_file() {
+        Some(package)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod tests {
+    use crate::DEFAULT_MAX_VARIANTS;
+    use crate::tests::{config, harness};
+
+    type Found = (String, usize, Vec<(String, String)>);
+
+    fn harness_branches(code: &str, max_variants: usize) -> Vec<Found> {
+        harness(config(&["*.execute"]).with_max_variants(max_variants), code)
+            .into_iter()
+            .map(|s| {
+                let variants = s
+                    .variants()
+                    .map(|v| {
+                        let branches: Vec<String> =
+                            v.branches.iter().map(ToString::to_string).collect();
+                        (v.sql_content.clone(), branches.join(", "))
+                    })
+                    .collect();
+                (s.variable_name.clone(), s.range.start.line(), variants)
+            })
+            .collect()
+    }
+
+    fn found(name: &str, line: usize, variants: &[(&str, &str)]) -> Found {
+        let variants = variants
+            .iter()
+            .map(|(sql, branches)| (sql.to_string(), branches.to_string()))
+            .collect();
+        (name.to_string(), line, variants)
+    }
+
+    #[test]
+    fn conditional_expression() {
+        let code = r#"
+query = "SELECT * FROM t " + ("WHERE a = 1" if flag else "")
+"#;
+        assert_eq!(
+            harness_branches(code, DEFAULT_MAX_VARIANTS),
+            vec![found(
+                "query",
+                2,
+                &[
+                    ("SELECT * FROM t WHERE a = 1", "`flag` is true on line 2"),
+                    ("SELECT * FROM t ", "`flag` is false on line 2"),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn query_extended_after_if() {
+        let code = r#"
+query = "SELECT id FROM users WHERE 1 = 1"
+if active:
+    query += " AND active = 1"
+query += " ORDER BY id"
+"#;
+        assert_eq!(
+            harness_branches(code, DEFAULT_MAX_VARIANTS),
+            vec![found(
+                "query",
+                5,
+                &[
+                    (
+                        "SELECT id FROM users WHERE 1 = 1 AND active = 1 ORDER BY id",
+                        "`active` is true on line 3"
+                    ),
+                    (
+                        "SELECT id FROM users WHERE 1 = 1 ORDER BY id",
+                        "`active` is false on line 3"
+                    ),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn query_reported_in_both_branches_isnt_reported_again() {
+        let code = r#"
+query = "SELECT id FROM users"
+if active:
+    query += " WHERE active = 1"
+cursor.execute(query)
+"#;
+        assert_eq!(
+            harness_branches(code, DEFAULT_MAX_VARIANTS),
+            vec![
+                found("query", 2, &[("SELECT id FROM users", "")]),
+                found("query", 4, &[("SELECT id FROM users WHERE active = 1", "")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn elif_chain() {
+        let code = r#"
+if kind == "a":
+    table = "a_items"
+elif kind == "b":
+    table = "b_items"
+else:
+    table = "items"
+cursor.execute(f"SELECT id FROM {table}")
+"#;
+        assert_eq!(
+            harness_branches(code, DEFAULT_MAX_VARIANTS),
+            vec![found(
+                "cursor.execute",
+                8,
+                &[
+                    (
+                        "SELECT id FROM a_items",
+                        r#"`kind == "a"` is true on line 2"#
+                    ),
+                    (
+                        "SELECT id FROM b_items",
+                        r#"`kind == "a"` is false on line 2, `kind == "b"` is true on line 4"#
+                    ),
+                    (
+                        "SELECT id FROM items",
+                        r#"`kind == "a"` is false on line 2, `kind == "b"` is false on line 4"#
+                    ),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn values_of_the_same_branch_go_together() {
+        let code = r#"
+if archived:
+    table = "archived_users"
+    column = "archived_at"
+else:
+    table = "users"
+    column = "created_at"
+cursor.execute(f"SELECT {column} FROM {table}")
+"#;
+        assert_eq!(
+            harness_branches(code, DEFAULT_MAX_VARIANTS),
+            vec![found(
+                "cursor.execute",
+                8,
+                &[
+                    (
+                        "SELECT archived_at FROM archived_users",
+                        "`archived` is true on line 2"
+                    ),
+                    (
+                        "SELECT created_at FROM users",
+                        "`archived` is false on line 2"
+                    ),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn too_many_variants_are_unknown() {
+        let code = r#"
+sql = "SELECT id FROM users WHERE 1 = 1"
+if a:
+    sql += " AND a = 1"
+if b:
+    sql += " AND b = 1"
+cursor.execute(sql)
+"#;
+        let found = harness_branches(code, 4);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].2.len(), 4);
+        assert_eq!(
+            found[0].2[1],
+            (
+                "SELECT id FROM users WHERE 1 = 1 AND b = 1".to_string(),
+                "`a` is false on line 3, `b` is true on line 5".to_string()
+            )
+        );
+
+        assert_eq!(harness_branches(code, 3), vec![]);
+    }
+}
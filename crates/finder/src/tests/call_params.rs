@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use crate::CallParams;
+    use crate::tests::{config, harness};
+
+    fn harness_params(code: &str) -> Vec<Option<CallParams>> {
+        harness(config(&["*.execute", "*.executemany"]), code)
+            .into_iter()
+            .map(|sql_string| sql_string.params)
+            .collect()
+    }
+
+    #[test]
+    fn positional_literals() {
+        assert_eq!(
+            harness_params(r#"cur.execute("SELECT * FROM t WHERE a = ? AND b = ?", (a, b))"#),
+            vec![Some(CallParams::Positional(2))]
+        );
+        assert_eq!(
+            harness_params(r#"cur.execute("SELECT * FROM t WHERE a = %s", [a])"#),
+            vec![Some(CallParams::Positional(1))]
+        );
+    }
+
+    #[test]
+    fn named_literals() {
+        assert_eq!(
+            harness_params(
+                r#"cur.execute("SELECT * FROM t WHERE a = :a", params={"a": 1, "b": 2})"#
+            ),
+            vec![Some(CallParams::Named(vec![
+                "a".to_string(),
+                "b".to_string()
+            ]))]
+        );
+    }
+
+    #[test]
+    fn executemany_rows() {
+        assert_eq!(
+            harness_params(
+                r#"cur.executemany("INSERT INTO t (a, b) VALUES (?, ?)", [(1, 2), (3, 4)])"#
+            ),
+            vec![Some(CallParams::Positional(2))]
+        );
+    }
+
+    #[test]
+    fn unknown_params() {
+        assert_eq!(
+            harness_params(
+                r#"
+cur.execute("SELECT * FROM t WHERE a = ?", args)
+cur.execute("SELECT * FROM t WHERE a = ?", (*args,))
+cur.execute("SELECT * FROM t WHERE a = :a", {**defaults, "a": 1})
+cur.execute("SELECT * FROM t")
+"#
+            ),
+            vec![None, None, None, None]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::{config, found, harness, name_sql_line};
+
+    #[test]
+    fn concatenated_base() {
+        let code = r#"
+BASE = "SELECT id FROM users"
+query = BASE + " WHERE active = 1"
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(&["*.execute"]), code)),
+            found(&[("query", "SELECT id FROM users WHERE active = 1", 3)])
+        );
+    }
+
+    #[test]
+    fn aug_assign_reports_final_query() {
+        let code = r#"
+query = "SELECT id FROM users"
+query += " WHERE active = 1"
+query += " ORDER BY id"
+cursor.execute(query)
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(&["*.execute"]), code)),
+            found(&[(
+                "query",
+                "SELECT id FROM users WHERE active = 1 ORDER BY id",
+                4
+            )])
+        );
+    }
+
+    #[test]
+    fn self_referencing_assign_reports_final_query() {
+        let code = r#"
+query = "SELECT id FROM users"
+query = query + " WHERE id = %s"
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(&["*.execute"]), code)),
+            found(&[("query", "SELECT id FROM users WHERE id = %s", 3)])
+        );
+    }
+
+    #[test]
+    fn extension_in_branch_keeps_both() {
+        let code = r#"
+query = "SELECT id FROM users"
+if admin:
+    query += " WHERE admin = 1"
+cursor.execute(query)
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(&["*.execute"]), code)),
+            found(&[
+                ("query", "SELECT id FROM users", 2),
+                ("query", "SELECT id FROM users WHERE admin = 1", 4),
+            ])
+        );
+    }
+
+    #[test]
+    fn module_constants_in_functions() {
+        let code = r#"
+def active_users():
+    cursor.execute(SELECT + " WHERE active = 1")
+
+SELECT = "SELECT id FROM users"
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(&["*.execute"]), code)),
+            found(&[("cursor.execute", "SELECT id FROM users WHERE active = 1", 3)])
+        );
+    }
+
+    #[test]
+    fn reassigned_module_names_are_unknown_in_functions() {
+        let code = r#"
+TABLE = "users"
+TABLE = "admins"
+
+def find():
+    query = f"SELECT id FROM {TABLE}"
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(&["*.execute"]), code)),
+            found(&[("query", "SELECT id FROM PLACEHOLDER", 6)])
+        );
+    }
+
+    #[test]
+    fn function_locals_shadow_module_constants() {
+        let code = r#"
+TABLE = "users"
+
+def find(TABLE):
+    query = f"SELECT id FROM {TABLE}"
+
+query = f"SELECT name FROM {TABLE}"
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(&["*.execute"]), code)),
+            found(&[
+                ("query", "SELECT id FROM PLACEHOLDER", 5),
+                ("query", "SELECT name FROM users", 7),
+            ])
+        );
+    }
+
+    #[test]
+    fn branches_that_disagree_are_unknown() {
+        let code = r#"
+if archived:
+    table = "archived_users"
+else:
+    table = load_table()
+query = f"SELECT id FROM {table}"
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(&["*.execute"]), code)),
+            found(&[("query", "SELECT id FROM PLACEHOLDER", 6)])
+        );
+    }
+
+    #[test]
+    fn loops_invalidate_names() {
+        let code = r#"
+table = "users"
+for table in tables:
+    query = f"SELECT id FROM {table}"
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(&["*.execute"]), code)),
+            found(&[("query", "SELECT id FROM PLACEHOLDER", 4)])
+        );
+    }
+
+    #[test]
+    fn tuple_swap() {
+        let code = r#"
+a, b = "users", "orders"
+a, b = b, a
+query = f"SELECT id FROM {a}"
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(&["*.execute"]), code)),
+            found(&[("query", "SELECT id FROM orders", 4)])
+        );
+    }
+
+    #[test]
+    fn unknown_aug_assign_forgets_value() {
+        let code = r#"
+table = "users"
+table += suffix()
+query = f"SELECT id FROM {table}"
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(&["*.execute"]), code)),
+            found(&[("query", "SELECT id FROM PLACEHOLDER", 4)])
+        );
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use crate::SqlString;
+    use crate::tests::{config, harness};
+
+    fn dialects(strings: Vec<SqlString>) -> Vec<(String, Option<String>)> {
+        strings
+            .into_iter()
+            .map(|s| (s.variable_name, s.dialect))
+            .collect()
+    }
+
+    fn found(name: &str, dialect: Option<&str>) -> (String, Option<String>) {
+        (name.to_string(), dialect.map(ToString::to_string))
+    }
+
+    #[test]
+    fn function_contexts_name_dialects() {
+        let code = r#"
+bq_client.query("SELECT 1")
+cur.execute("SELECT 2")
+"#;
+        assert_eq!(
+            dialects(harness(
+                config(&["bq_client.query:bigquery", "*.execute"]),
+                code
+            )),
+            vec![
+                found("bq_client.query", Some("bigquery")),
+                found("cur.execute", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn pragmas_name_dialects() {
+        let code = r#"
+query = "SELECT 1"  # sqint: dialect=sqlite
+other_query = "SELECT 2"
+bq_client.query("SELECT 3")
+bq_client.query("SELECT 4")  # sqint:dialect=postgres
+"#;
+        assert_eq!(
+            dialects(harness(config(&["bq_client.query:bigquery"]), code)),
+            vec![
+                found("query", Some("sqlite")),
+                found("other_query", None),
+                found("bq_client.query", Some("bigquery")),
+                found("bq_client.query", Some("postgres")),
+            ]
+        );
+    }
+
+    #[test]
+    fn pragma_on_its_own_line_names_the_file_dialect() {
+        let code = r#"
+import db
+
+# sqint: dialect=mysql
+query = "SELECT 1"
+bq_client.query("SELECT 2")
+later_query = "SELECT 3"  # sqint: dialect=duckdb
+"#;
+        assert_eq!(
+            dialects(harness(config(&["bq_client.query:bigquery"]), code)),
+            vec![
+                found("query", Some("mysql")),
+                found("bq_client.query", Some("bigquery")),
+                found("later_query", Some("duckdb")),
+            ]
+        );
+    }
+}
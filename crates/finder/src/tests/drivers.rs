@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn drivers(code: &str) -> Vec<(String, String)> {
+        let config = FinderConfig::new(&["*query*".to_string()], &[]);
+        SqlFinder::new(config.into())
+            .analyze_source("test.py", code, false)
+            .expect("Failed to parse")
+            .drivers
+            .into_iter()
+            .map(|driver| (driver.module, driver.dialect))
+            .collect()
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(module, dialect)| ((*module).to_string(), (*dialect).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn imported_drivers_name_dialects() {
+        let code = r#"
+import psycopg2.extras
+from google.cloud import bigquery
+import snowflake.connector as sf
+from duckdb import connect
+import psycopg2
+
+def load():
+    import sqlite3
+"#;
+        assert_eq!(
+            drivers(code),
+            pairs(&[
+                ("psycopg2", "postgres"),
+                ("google.cloud.bigquery", "bigquery"),
+                ("snowflake.connector", "snowflake"),
+                ("duckdb", "duckdb"),
+                ("sqlite3", "sqlite"),
+            ])
+        );
+    }
+
+    #[test]
+    fn other_modules_are_not_drivers() {
+        let code = r#"
+import asyncpgx
+import snowflake
+from google.cloud import storage
+from . import sqlite3
+from .pymysql import connect
+"#;
+        assert!(drivers(code).is_empty());
+    }
+}
@@ -15,6 +15,7 @@ mod tests {
             "sql".to_string(),
             "also_query".to_string(),
             "queries".to_string(),
+            "main_sql".to_string(),
         ];
         let func_ctx = vec![
             "execute".to_string(),
@@ -25,7 +26,6 @@ mod tests {
             "also_query_fun".to_string(),
             "outer_func".to_string(),
         ];
-        let class_ctx = ["Ok".to_string()];
         SqlFinder::new(FinderConfig::new(&variable_ctx, &func_ctx).into())
     }
 
@@ -523,7 +523,7 @@ sql = "DELETE FROM temp_data"
 table = "users"
 query = f"select * from {table}"
             "#,
-            vec![("query", "select * from PLACEHOLDER")],
+            vec![("query", "select * from users")],
             "f-string simple variable substitution",
         );
     }
@@ -536,10 +536,7 @@ table = "users"
 status = "active"
 query = f"select * from {table} where status = '{status}'"
             "#,
-            vec![(
-                "query",
-                "select * from PLACEHOLDER where status = 'PLACEHOLDER'",
-            )],
+            vec![("query", "select * from users where status = 'active'")],
             "f-string multiple variables substitution",
         );
     }
@@ -552,10 +549,7 @@ table = "products"
 min_price = 100
 query = f"select * from {table} where price > {min_price}"
             "#,
-            vec![(
-                "query",
-                "select * from PLACEHOLDER where price > PLACEHOLDER",
-            )],
+            vec![("query", "select * from products where price > 100")],
             "f-string with number substitution",
         );
     }
@@ -632,7 +626,7 @@ query = f"""
             "#,
             vec![(
                 "query",
-                "\n    select \n        id,\n        name,\n        email\n    from PLACEHOLDER\n    where status = 'PLACEHOLDER'\n",
+                "\n    select \n        id,\n        name,\n        email\n    from users\n    where status = 'active'\n",
             )],
             "multiline f-string substitution",
         );
@@ -646,7 +640,7 @@ columns = ["id", "name", "email"]
 table = "users"
 query = "select {} from {}".format(", ".join(columns), table)
             "#,
-            vec![("query", "select PLACEHOLDER from PLACEHOLDER")],
+            vec![("query", "select PLACEHOLDER from users")],
             "format with join operation substitution",
         );
     }
@@ -738,21 +732,31 @@ prefix = "temp_"
 table = "users"
 query = "select * from {}".format(prefix + table)
             "#,
-            vec![("query", "select * from PLACEHOLDER")],
+            vec![("query", "select * from temp_users")],
             "format with string concatenation substitution",
         );
     }
 
     #[test]
     fn conditional_f_string() {
-        harness_find(
-            r#"
+        let code = r#"
 include_deleted = False
 table_suffix = "_all" if include_deleted else ""
 query = f"select * from users{table_suffix}"
-            "#,
-            vec![("query", "select * from usersPLACEHOLDER")],
-            "f-string with conditional substitution",
+            "#;
+        let range_file = PreanalyzedFile::from_src(code);
+        let parsed = ast::Suite::parse(code, "test.py").expect("Failed to parse");
+        let contexts = harness_create_test_finder().analyze_stmts(&parsed, &range_file);
+
+        assert_eq!(contexts.len(), 1);
+        // The condition isn't evaluated, so both ways are checked
+        let variants: Vec<&str> = contexts[0]
+            .variants()
+            .map(|variant| variant.sql_content.as_str())
+            .collect();
+        assert_eq!(
+            variants,
+            vec!["select * from users_all", "select * from users"]
         );
     }
 
@@ -840,7 +844,7 @@ query = "select * from users limit {}".format(20 * 5)
 table = "users"
 sql_fun(f"SELECT * FROM {table} WHERE active = 1")
         "#,
-            vec![("sql_fun", "SELECT * FROM PLACEHOLDER WHERE active = 1")],
+            vec![("sql_fun", "SELECT * FROM users WHERE active = 1")],
             "function call with f-string",
         );
     }
@@ -968,7 +972,6 @@ sql_fun(user_query)
         );
     }
 
-    #[ignore]
     #[test]
     fn function_call_return_statement() {
         harness_find(
@@ -1436,13 +1439,25 @@ query_fun(
     fn ignore_fully_dynamic_var() {
         harness_find(
             r#"
-s = "SELECT * FROM users"
+s = load_sql()
 query = s
         "#,
             vec![],
             "simple assignment",
         );
     }
+
+    #[test]
+    fn constant_var_assigned() {
+        harness_find(
+            r#"
+s = "SELECT * FROM users"
+query = s
+        "#,
+            vec![("query", "SELECT * FROM users")],
+            "constant assigned to a query variable",
+        );
+    }
     #[test]
     fn fstring_sql_join_variables() {
         harness_find(
@@ -1470,7 +1485,7 @@ query = f"SELECT u.id, u.name, {', '.join(['p.title', 'p.price', 'p.category'])}
         "#,
             vec![(
                 "query",
-                "SELECT u.id, u.name, PLACEHOLDER FROM users u JOIN PLACEHOLDER WHERE u.id = PLACEHOLDER",
+                "SELECT u.id, u.name, PLACEHOLDER FROM users u JOIN PLACEHOLDER WHERE u.id = 123",
             )],
             "f-string SQL with join operations on literal lists",
         );
@@ -1486,4 +1501,34 @@ query = not_sql % (days, days)
             "f-string SQL with join operations on literal lists",
         );
     }
+
+    #[test]
+    fn context_of_found_sql() {
+        let finder = SqlFinder::new(
+            FinderConfig::new(
+                &["*[!_]sql".to_string(), "*_query".to_string()],
+                &["*.execute".to_string()],
+            )
+            .into(),
+        );
+        let code = r#"
+user_query = "SELECT 1"
+cursor.execute("SELECT 2")
+"#;
+        let range_file = PreanalyzedFile::from_src(code);
+        let parsed = ast::Suite::parse(code, "test.py").expect("Failed to parse");
+        let contexts: Vec<_> = finder
+            .analyze_stmts(&parsed, &range_file)
+            .into_iter()
+            .map(|s| s.context)
+            .collect();
+        assert_eq!(
+            contexts,
+            [
+                Some(Context::Variable("*_query".to_string())),
+                Some(Context::Function("*.execute".to_string())),
+            ]
+        );
+        assert_eq!(finder.split_sql("SELECT 3;")[0].context, None);
+    }
 }
@@ -0,0 +1,140 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::{config, found, harness, name_sql_line};
+
+    const FUNCTIONS: &[&str] = &["*.execute", "run_sql"];
+
+    #[test]
+    fn returned_literal() {
+        let code = r#"
+def get_users_sql():
+    return "SELECT id FROM users"
+
+cursor.execute(get_users_sql())
+user_query = get_users_sql() + " WHERE id = ?"
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(FUNCTIONS), code)),
+            found(&[
+                ("cursor.execute", "SELECT id FROM users", 5),
+                ("user_query", "SELECT id FROM users WHERE id = ?", 6),
+            ])
+        );
+    }
+
+    #[test]
+    fn returned_string_built_from_arguments() {
+        let code = r#"
+def build_query(table, limit="10"):
+    return f"SELECT id FROM {table} LIMIT {limit}"
+
+def count_query(table):
+    return "SELECT count(*) FROM " + table
+
+cursor.execute(build_query("users"))
+cursor.execute(build_query(table="orders", limit="5"))
+cursor.execute(count_query(name))
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(FUNCTIONS), code)),
+            found(&[
+                ("cursor.execute", "SELECT id FROM users LIMIT 10", 8),
+                ("cursor.execute", "SELECT id FROM orders LIMIT 5", 9),
+            ])
+        );
+    }
+
+    #[test]
+    fn returns_that_differ_are_unknown() {
+        let code = r#"
+def pick(archived):
+    if archived:
+        return "SELECT id FROM archived_users"
+    return "SELECT id FROM users"
+
+def same(archived):
+    if archived:
+        return "SELECT id FROM users"
+    return "SELECT id FROM users"
+
+def recurse(n):
+    return recurse(n)
+
+query = pick(True)
+query = same(True)
+query = recurse(1)
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(FUNCTIONS), code)),
+            found(&[("query", "SELECT id FROM users", 16)])
+        );
+    }
+
+    #[test]
+    fn returned_query_is_reported_once() {
+        let code = r#"
+def get_sql():
+    query = "SELECT id FROM users"
+    query += " WHERE active = 1"
+    return query
+
+cursor.execute(get_sql())
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(FUNCTIONS), code)),
+            found(&[("query", "SELECT id FROM users WHERE active = 1", 4)])
+        );
+    }
+
+    #[test]
+    fn parameters_passed_the_same_sql() {
+        let code = r#"
+def fetch(cursor, sql):
+    cursor.execute(sql)
+
+def fetch_any(cursor, sql):
+    cursor.execute(sql)
+
+fetch(db, "SELECT id FROM users")
+fetch(other_db, sql="SELECT id FROM users")
+fetch_any(db, "SELECT 1")
+fetch_any(db, "SELECT 2")
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(FUNCTIONS), code)),
+            found(&[("cursor.execute", "SELECT id FROM users", 3)])
+        );
+    }
+
+    #[test]
+    fn parameters_checked_at_the_call() {
+        let code = r#"
+def run_sql(sql):
+    cursor.execute(sql)
+
+run_sql("SELECT id FROM users")
+"#;
+        assert_eq!(
+            name_sql_line(harness(config(FUNCTIONS), code)),
+            found(&[("run_sql", "SELECT id FROM users", 5)])
+        );
+    }
+
+    #[test]
+    fn shadowed_functions() {
+        let code = r#"
+def get_sql():
+    return "SELECT id FROM users"
+
+def report(get_sql):
+    query = get_sql()
+
+class Report:
+    def get_sql(self):
+        return "SELECT 1"
+
+    query = get_sql()
+"#;
+        assert_eq!(name_sql_line(harness(config(FUNCTIONS), code)), found(&[]));
+    }
+}
@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::{config, found, name_sql_line};
+    use crate::*;
+    use std::path::PathBuf;
+
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("finder-{name}-{}", std::process::id()));
+        for (file, source) in files {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, source).unwrap();
+        }
+        dir
+    }
+
+    fn harness_imports(
+        dir: &std::path::Path,
+        file: &str,
+        symbols: Option<Arc<SymbolTable>>,
+    ) -> Vec<(String, String, usize)> {
+        let mut finder = SqlFinder::new(config(&["*.execute"]).into());
+        if let Some(symbols) = symbols {
+            finder = finder.with_symbols(symbols);
+        }
+        let path = dir.join(file).to_string_lossy().into_owned();
+        name_sql_line(finder.analyze_file(&path, false).unwrap().strings)
+    }
+
+    #[test]
+    fn constants_from_imported_modules() {
+        let dir = project(
+            "from-imports",
+            &[
+                ("app/__init__.py", ""),
+                ("app/sql/__init__.py", ""),
+                ("app/sql/base.py", r#"USERS_BASE = "SELECT id FROM users""#),
+                (
+                    "app/sql/queries.py",
+                    "from .base import *\nACTIVE = USERS_BASE + \" WHERE active = 1\"\nTABLE = f\"users_{suffix}\"\n",
+                ),
+                (
+                    "app/db.py",
+                    r#"
+from app.sql.queries import ACTIVE, TABLE
+from .sql.base import USERS_BASE as BASE
+
+def find(cursor):
+    cursor.execute(BASE + " WHERE id = ?")
+    query = ACTIVE + " ORDER BY id"
+    cursor.execute(f"SELECT id FROM {TABLE}")
+"#,
+                ),
+            ],
+        );
+        let symbols = Arc::new(SymbolTable::new());
+        assert_eq!(
+            harness_imports(&dir, "app/db.py", Some(symbols.clone())),
+            found(&[
+                ("cursor.execute", "SELECT id FROM users WHERE id = ?", 6),
+                (
+                    "query",
+                    "SELECT id FROM users WHERE active = 1 ORDER BY id",
+                    7
+                ),
+                ("cursor.execute", "SELECT id FROM PLACEHOLDER", 8),
+            ])
+        );
+
+        // Imported modules are read once, until they're forgotten
+        let queries = dir.join("app/sql/queries.py");
+        std::fs::write(&queries, "ACTIVE = \"SELECT 1\"").unwrap();
+        assert_eq!(
+            harness_imports(&dir, "app/db.py", Some(symbols.clone()))[1].1,
+            "SELECT id FROM users WHERE active = 1 ORDER BY id"
+        );
+        symbols.forget(&queries);
+        assert_eq!(
+            harness_imports(&dir, "app/db.py", Some(symbols))[1].1,
+            "SELECT 1 ORDER BY id"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn attributes_of_imported_modules() {
+        let dir = project(
+            "module-imports",
+            &[
+                ("app/__init__.py", ""),
+                ("app/queries.py", r#"USERS = "SELECT id FROM users""#),
+                (
+                    "app/db.py",
+                    r#"
+import app.queries as q
+from . import queries
+
+query = q.USERS + " WHERE id = ?"
+other_query = queries.USERS + " LIMIT 1"
+unknown_query = f"{queries.MISSING} LIMIT 1"
+"#,
+                ),
+            ],
+        );
+        assert_eq!(
+            harness_imports(&dir, "app/db.py", Some(Arc::new(SymbolTable::new()))),
+            found(&[
+                ("query", "SELECT id FROM users WHERE id = ?", 5),
+                ("other_query", "SELECT id FROM users LIMIT 1", 6),
+                ("unknown_query", "PLACEHOLDER LIMIT 1", 7),
+            ])
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn import_cycles() {
+        let dir = project(
+            "import-cycles",
+            &[
+                (
+                    "a.py",
+                    "from b import B\nA = \"SELECT a FROM t\"\nquery = B + \" WHERE 1 = 1\"\n",
+                ),
+                (
+                    "b.py",
+                    "from a import A\nB = \"SELECT b FROM t\"\nB_AND_A = A + B\n",
+                ),
+            ],
+        );
+        assert_eq!(
+            harness_imports(&dir, "a.py", Some(Arc::new(SymbolTable::new()))),
+            found(&[("query", "SELECT b FROM t WHERE 1 = 1", 3)])
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn imports_without_symbols() {
+        let dir = project(
+            "no-symbols",
+            &[
+                ("queries.py", r#"USERS = "SELECT id FROM users""#),
+                (
+                    "db.py",
+                    "from queries import USERS\nquery = f\"{USERS} LIMIT 1\"\n",
+                ),
+            ],
+        );
+        assert_eq!(
+            harness_imports(&dir, "db.py", None),
+            found(&[("query", "PLACEHOLDER LIMIT 1", 2)])
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
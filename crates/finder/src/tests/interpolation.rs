@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::harness;
+    use crate::*;
+
+    fn harness_interpolations(code: &str) -> Vec<(InterpolationKind, String, (usize, usize))> {
+        let config = FinderConfig::new(&["query".to_string()], &["execute".to_string()]);
+        let contexts = harness(config, code);
+        let sql_string = contexts.first().expect("Expected an SQL string");
+
+        sql_string
+            .interpolations
+            .iter()
+            .map(|i| {
+                assert_eq!(&sql_string.sql_content[i.range.clone()], "PLACEHOLDER");
+                (
+                    i.kind,
+                    i.expr.clone(),
+                    (i.position.line(), i.position.col()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fstring_fields() {
+        assert_eq!(
+            harness_interpolations(
+                r#"query = f"SELECT * FROM {table} WHERE name = '{user.name}' AND id = {7}""#
+            ),
+            vec![
+                (InterpolationKind::FString, "table".to_string(), (1, 26)),
+                (InterpolationKind::FString, "user.name".to_string(), (1, 48)),
+            ]
+        );
+    }
+
+    #[test]
+    fn percent_formatting() {
+        assert_eq!(
+            harness_interpolations(
+                r#"query = "SELECT * FROM users WHERE id = %s AND kind = '%s'" % (uid, 'admin')"#
+            ),
+            vec![(InterpolationKind::PercentFormat, "uid".to_string(), (1, 64))]
+        );
+        assert_eq!(
+            harness_interpolations(
+                r#"query = "DELETE FROM t WHERE id = %(id)s" % {"id": row["id"]}"#
+            ),
+            vec![(
+                InterpolationKind::PercentFormat,
+                "row[\"id\"]".to_string(),
+                (1, 52)
+            )]
+        );
+        assert_eq!(
+            harness_interpolations(r#"query = "SELECT * FROM users WHERE id = %s" % uid"#),
+            vec![(InterpolationKind::PercentFormat, "uid".to_string(), (1, 47))]
+        );
+    }
+
+    #[test]
+    fn format_call() {
+        assert_eq!(
+            harness_interpolations(
+                r#"query = "SELECT {} FROM users WHERE id = {uid}".format(cols, uid=request.args["id"])"#
+            ),
+            vec![
+                (InterpolationKind::FormatCall, "cols".to_string(), (1, 56)),
+                (
+                    InterpolationKind::FormatCall,
+                    "request.args[\"id\"]".to_string(),
+                    (1, 66)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn constants_are_not_interpolations() {
+        assert!(
+            harness_interpolations(r#"query = "SELECT * FROM users WHERE id = %d" % 5"#).is_empty()
+        );
+        assert!(harness_interpolations(r#"query = f"SELECT {'id'} FROM users""#).is_empty());
+        let code = r#"
+PAGE_SIZE = 50
+MIN_SCORE = 0.5
+query = f"SELECT * FROM users WHERE score > {MIN_SCORE} LIMIT {PAGE_SIZE}"
+"#;
+        assert!(harness_interpolations(code).is_empty());
+    }
+
+    #[test]
+    fn concatenation_keeps_sites() {
+        let code = r#"query = "SELECT * FROM users " + f"WHERE id = {uid}" + " AND active""#;
+        assert_eq!(
+            harness_interpolations(code),
+            vec![(InterpolationKind::FString, "uid".to_string(), (1, 48))]
+        );
+    }
+}
@@ -1 +1,44 @@
+mod branches;
+mod call_params;
+mod constants;
+mod dialects;
+mod drivers;
 mod features;
+mod functions;
+mod imports;
+mod interpolation;
+mod notebook;
+mod presets;
+mod source_map;
+mod sql_files;
+
+use crate::{FinderConfig, SqlFinder, SqlString};
+
+/// Config finding the SQL of `*query*` variables and of calls to `functions`
+fn config(functions: &[&str]) -> FinderConfig {
+    let functions: Vec<String> = functions.iter().map(ToString::to_string).collect();
+    FinderConfig::new(&["*query*".to_string()], &functions)
+}
+
+/// SQL strings found with `config` in the Python source `code`
+fn harness(config: FinderConfig, code: &str) -> Vec<SqlString> {
+    SqlFinder::new(config.into())
+        .analyze_source("test.py", code, false)
+        .expect("Failed to parse")
+        .strings
+}
+
+/// Name, SQL and line of each string, to compare with `found`
+fn name_sql_line(strings: Vec<SqlString>) -> Vec<(String, String, usize)> {
+    strings
+        .into_iter()
+        .map(|s| (s.variable_name, s.sql_content, s.range.start.line()))
+        .collect()
+}
+
+fn found(expected: &[(&str, &str, usize)]) -> Vec<(String, String, usize)> {
+    expected
+        .iter()
+        .map(|(name, sql, line)| (name.to_string(), sql.to_string(), *line))
+        .collect()
+}
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    type Found = Vec<(String, String, (usize, usize, usize))>;
+
+    fn notebook(cells: &[(&str, &str)]) -> String {
+        let cells: Vec<String> = cells
+            .iter()
+            .map(|(cell_type, source)| {
+                format!(r#"{{"cell_type": "{cell_type}", "metadata": {{}}, "source": {source}}}"#)
+            })
+            .collect();
+        format!(
+            r#"{{"cells": [{}], "metadata": {{"language_info": {{"name": "python"}}}}, "nbformat": 4, "nbformat_minor": 5}}"#,
+            cells.join(", ")
+        )
+    }
+
+    fn harness_notebook(json: &str) -> Option<Found> {
+        let finder = SqlFinder::new(
+            FinderConfig::new(&["*query*".to_string()], &["execute".to_string()]).into(),
+        );
+        let extract = finder.analyze_notebook("test.ipynb", json)?;
+        let cells = extract.cells.expect("Notebooks map their cells");
+        Some(
+            extract
+                .strings
+                .iter()
+                .map(|s| {
+                    let (cell, line) = cells.locate(s.range.start.line());
+                    (
+                        s.variable_name.clone(),
+                        s.sql_content.clone(),
+                        (cell, line, s.range.start.col()),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn code_cells_in_order() {
+        let json = notebook(&[
+            ("markdown", r##"["# Users\n"]"##),
+            ("code", r#"["import sqlite3\n", "table = 'users'\n"]"#),
+            (
+                "code",
+                r#"["\n", "sql_query = f\"SELECT * FROM {table}\"\n", "query = 'SELECT 1'"]"#,
+            ),
+        ]);
+        assert_eq!(
+            harness_notebook(&json).unwrap(),
+            vec![
+                (
+                    "sql_query".to_string(),
+                    "SELECT * FROM users".to_string(),
+                    (2, 2, 13)
+                ),
+                ("query".to_string(), "SELECT 1".to_string(), (2, 3, 9)),
+            ]
+        );
+    }
+
+    #[test]
+    fn magics_are_stripped() {
+        let json = notebook(&[
+            (
+                "code",
+                r#"["%load_ext sql\n", "!pip install pandas\n", "files = !ls\n", "if True:\n", "    %time x = 1\n", "query = 'SELECT 2'\n"]"#,
+            ),
+            ("code", r#""%%time\nsql_query = 'SELECT 3'""#),
+            ("code", r#""%%bash\nquery = 'SELECT 4'""#),
+        ]);
+        assert_eq!(
+            harness_notebook(&json).unwrap(),
+            vec![
+                ("query".to_string(), "SELECT 2".to_string(), (1, 6, 9)),
+                ("sql_query".to_string(), "SELECT 3".to_string(), (2, 2, 13)),
+            ]
+        );
+    }
+
+    #[test]
+    fn magic_lookalikes_in_strings_are_kept() {
+        let json = notebook(&[(
+            "code",
+            r#"["query = \"\"\"INSERT INTO t VALUES (\n", "%(a)s)\"\"\"\n"]"#,
+        )]);
+        assert_eq!(
+            harness_notebook(&json).unwrap(),
+            vec![(
+                "query".to_string(),
+                "INSERT INTO t VALUES (\n%(a)s)".to_string(),
+                (1, 1, 9)
+            )]
+        );
+    }
+
+    #[test]
+    fn sql_cell_magic() {
+        let json = notebook(&[
+            ("code", r#"["x = 1\n"]"#),
+            ("code", r#""%%sql sqlite://\n\n  SELECT *\n  FROM users\n""#),
+            ("code", r#""%%sql\n""#),
+        ]);
+        assert_eq!(
+            harness_notebook(&json).unwrap(),
+            vec![(
+                "%%sql".to_string(),
+                "SELECT *\n  FROM users".to_string(),
+                (2, 3, 3)
+            )]
+        );
+        // After the first cell's 6 bytes, the `%%sql` line and the skipped whitespace
+        let notebook = crate::notebook::Notebook::parse(&json).unwrap();
+        assert_eq!(notebook.sql_cells[0].1.start.byte_offset(), 6 + 16 + 3);
+    }
+
+    #[test]
+    fn invalid_cells_are_skipped() {
+        let json = notebook(&[
+            ("code", r#"["def broken(:\n", "    pass\n"]"#),
+            ("code", r#"["query = 'SELECT 5'"]"#),
+        ]);
+        assert_eq!(
+            harness_notebook(&json).unwrap(),
+            vec![("query".to_string(), "SELECT 5".to_string(), (2, 1, 9))]
+        );
+
+        let json = notebook(&[("code", r#"["query = 'SELECT 6'"]"#)])
+            .replace(r#""name": "python""#, r#""name": "R""#);
+        assert!(harness_notebook(&json).is_none());
+        assert!(harness_notebook("{\"cells\": 1}").is_none());
+    }
+}
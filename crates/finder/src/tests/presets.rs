@@ -0,0 +1,192 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::{config, harness};
+    use crate::*;
+
+    fn harness_presets(code: &str, presets: &[&str]) -> Vec<SqlString> {
+        let presets: Vec<String> = presets.iter().map(ToString::to_string).collect();
+        harness(config(&[]).with_presets(&presets), code)
+    }
+
+    fn contents(found: &[SqlString]) -> Vec<&str> {
+        found.iter().map(|s| s.sql_content.as_str()).collect()
+    }
+
+    #[test]
+    fn presets_are_off_by_default() {
+        let code = r#"cur.execute("SELECT * FROM users")"#;
+        assert!(harness_presets(code, &[]).is_empty());
+        assert_eq!(
+            contents(&harness_presets(code, &["dbapi"])),
+            vec!["SELECT * FROM users"]
+        );
+    }
+
+    #[test]
+    fn dbapi_cursor() {
+        let code = r#"
+cur.execute("SELECT * FROM users WHERE id = %s", (user_id,))
+cur.executemany("INSERT INTO t (a, b) VALUES (?, ?)", [(1, 2), (3, 4)])
+cur.executescript("DELETE FROM t; DELETE FROM u")
+cur.execute(params={"id": 1}, operation="SELECT * FROM t WHERE id = :id")
+"#;
+        let found = harness_presets(code, &["dbapi"]);
+        assert_eq!(
+            contents(&found),
+            vec![
+                "SELECT * FROM users WHERE id = %s",
+                "INSERT INTO t (a, b) VALUES (?, ?)",
+                "DELETE FROM t; DELETE FROM u",
+                "SELECT * FROM t WHERE id = :id",
+            ]
+        );
+        let params: Vec<_> = found.iter().map(|s| s.params.clone()).collect();
+        assert_eq!(
+            params,
+            vec![
+                Some(CallParams::Positional(1)),
+                Some(CallParams::Positional(2)),
+                None,
+                Some(CallParams::Named(vec!["id".to_string()])),
+            ]
+        );
+        assert!(found[0].param_styles.contains(&ParamStyle::Format));
+        assert!(found[2].param_styles.is_empty());
+        assert_eq!(
+            found[0].context,
+            Some(Context::Function("*.execute".to_string()))
+        );
+    }
+
+    #[test]
+    fn sql_in_wrapper_objects() {
+        let code = r#"
+conn.execute(text("SELECT * FROM users WHERE id = :id"), {"id": 1})
+conn.exec_driver_sql("SELECT * FROM users WHERE id = %(id)s", {"id": 1})
+Book.objects.raw("SELECT * FROM books WHERE author = %s", [author])
+Book.objects.annotate(n=RawSQL("SELECT 1", ()))
+"#;
+        let found = harness_presets(code, &["dbapi", "sqlalchemy", "django"]);
+        assert_eq!(
+            contents(&found),
+            vec![
+                "SELECT * FROM users WHERE id = :id",
+                "SELECT * FROM users WHERE id = %(id)s",
+                "SELECT * FROM books WHERE author = %s",
+            ]
+        );
+        assert_eq!(
+            found[0].params,
+            Some(CallParams::Named(vec!["id".to_string()]))
+        );
+        assert_eq!(
+            found[2].param_styles,
+            [ParamStyle::Format, ParamStyle::Pyformat]
+        );
+    }
+
+    #[test]
+    fn pandas_params_keyword() {
+        let code = r#"
+pd.read_sql("SELECT * FROM t WHERE a = ?", conn, params=(a,))
+read_sql_query(sql="SELECT * FROM t", con=conn)
+pd.read_sql("users", conn)
+"#;
+        let found = harness_presets(code, &["pandas"]);
+        assert_eq!(
+            contents(&found),
+            vec!["SELECT * FROM t WHERE a = ?", "SELECT * FROM t"]
+        );
+        assert_eq!(found[0].params, Some(CallParams::Positional(1)));
+        assert_eq!(found[1].params, None);
+    }
+
+    #[test]
+    fn assigned_results_of_calls() {
+        let code = r#"
+df = pd.read_sql("SELECT * FROM t", conn)
+query = text("SELECT * FROM users")
+q = text("SELECT * FROM orders")
+conn.execute(q)
+"#;
+        let found = harness_presets(code, &["dbapi", "sqlalchemy", "pandas"]);
+        let names: Vec<&str> = found.iter().map(|s| s.variable_name.as_str()).collect();
+        assert_eq!(names, vec!["pd.read_sql", "query", "conn.execute"]);
+        assert_eq!(
+            contents(&found),
+            vec![
+                "SELECT * FROM t",
+                "SELECT * FROM users",
+                "SELECT * FROM orders"
+            ]
+        );
+    }
+
+    #[test]
+    fn asyncpg_takes_the_rest_of_the_arguments() {
+        let code = r#"
+await conn.fetch("SELECT * FROM t WHERE a = $1 AND b = $2", a, b)
+await conn.fetchrow("SELECT * FROM t WHERE a = $1", *args)
+"#;
+        let found = harness_presets(code, &["asyncpg"]);
+        assert_eq!(found[0].params, Some(CallParams::Positional(2)));
+        assert_eq!(found[0].param_styles, [ParamStyle::Dollar]);
+        assert_eq!(found[1].params, None);
+    }
+
+    #[test]
+    fn first_enabled_preset_describes_a_function() {
+        let code = r#"conn.execute("SELECT * FROM t WHERE a = $1", a)"#;
+        assert_eq!(harness_presets(code, &["dbapi", "asyncpg"])[0].params, None);
+        assert_eq!(
+            harness_presets(code, &["asyncpg"])[0].params,
+            Some(CallParams::Positional(1))
+        );
+    }
+
+    #[test]
+    fn imported_module_tells_the_preset() {
+        let code = r#"
+import asyncpg
+
+async def insert(conn):
+    await conn.execute("INSERT INTO t (a, b) VALUES ($1, $2)", 1)
+"#;
+        let found = harness_presets(code, &["dbapi", "django", "asyncpg"]);
+        assert_eq!(found[0].params, Some(CallParams::Positional(1)));
+        assert_eq!(found[0].param_styles, [ParamStyle::Dollar]);
+        assert_eq!(
+            found[0].context,
+            Some(Context::Function("*.execute".to_string()))
+        );
+
+        let code = r#"
+from django.db import connection
+
+connection.cursor().execute("SELECT * FROM t WHERE a = %s", [a])
+"#;
+        let found = harness_presets(code, &["dbapi", "django", "asyncpg"]);
+        assert_eq!(
+            found[0].param_styles,
+            [ParamStyle::Format, ParamStyle::Pyformat]
+        );
+    }
+
+    #[test]
+    fn psycopg_composition_is_not_splicing() {
+        let code = r#"
+cur.execute(sql.SQL("SELECT * FROM {} WHERE id = %s").format(sql.Identifier(table)), (1,))
+cur.execute("SELECT * FROM {} WHERE id = %s".format(table), (1,))
+"#;
+        let found = harness_presets(code, &["dbapi", "psycopg"]);
+        assert_eq!(
+            contents(&found),
+            vec![
+                "SELECT * FROM PLACEHOLDER WHERE id = %s",
+                "SELECT * FROM PLACEHOLDER WHERE id = %s",
+            ]
+        );
+        assert!(found[0].interpolations.is_empty());
+        assert_eq!(found[1].interpolations.len(), 1);
+    }
+}
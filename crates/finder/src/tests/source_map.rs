@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::harness;
+    use crate::*;
+
+    fn harness_position_of(code: &str, needle: &str) -> (usize, usize) {
+        let config = FinderConfig::new(&["query".to_string()], &["execute".to_string()]);
+        let contexts = harness(config, code);
+        let sql_string = contexts.first().expect("Expected an SQL string");
+
+        let offset = sql_string
+            .sql_content
+            .find(needle)
+            .unwrap_or_else(|| panic!("'{needle}' not in '{}'", sql_string.sql_content));
+        let pos = sql_string.position_at(offset);
+        (pos.line(), pos.col())
+    }
+
+    #[test]
+    fn plain_string() {
+        assert_eq!(
+            harness_position_of(r#"query = "SELECT id FORM users""#, "FORM"),
+            (1, 20)
+        );
+    }
+
+    #[test]
+    fn triple_quoted_string() {
+        let code = r#"
+def load():
+    query = """
+        SELECT id
+        FROM users
+        WHERE active = 1
+        ORDER id
+    """
+"#;
+        assert_eq!(harness_position_of(code, "ORDER"), (7, 9));
+    }
+
+    #[test]
+    fn escapes_shift_columns() {
+        assert_eq!(
+            harness_position_of(r#"query = "SELECT\t'it\'s'\n FORM users""#, "FORM"),
+            (1, 28)
+        );
+    }
+
+    #[test]
+    fn raw_string_keeps_backslashes() {
+        assert_eq!(
+            harness_position_of(r#"query = r"SELECT '\d' FORM users""#, "FORM"),
+            (1, 23)
+        );
+    }
+
+    #[test]
+    fn implicit_concatenation() {
+        let code = r#"
+query = (
+    "SELECT id "  # the columns
+    'FORM users'
+)
+"#;
+        assert_eq!(harness_position_of(code, "FORM"), (4, 6));
+    }
+
+    #[test]
+    fn f_string_literal_after_field() {
+        assert_eq!(
+            harness_position_of(r#"query = f"SELECT {cols} FORM {{t}} users""#, "FORM"),
+            (1, 25)
+        );
+    }
+
+    #[test]
+    fn f_string_field_maps_to_expression() {
+        assert_eq!(
+            harness_position_of(r#"query = f"SELECT {cols} FROM users""#, "PLACEHOLDER"),
+            (1, 19)
+        );
+    }
+
+    #[test]
+    fn multiline_f_string() {
+        let code = r#"
+query = f"""
+    SELECT {col}
+    FORM {table}
+"""
+"#;
+        assert_eq!(harness_position_of(code, "FORM"), (4, 5));
+    }
+
+    #[test]
+    fn percent_formatting() {
+        assert_eq!(
+            harness_position_of(r#"query = "SELECT %s FORM %s" % ("id", table)"#, "FORM"),
+            (1, 20)
+        );
+        assert_eq!(
+            harness_position_of(r#"query = "SELECT %s FORM %s" % ("id", table)"#, "id"),
+            (1, 33)
+        );
+    }
+
+    #[test]
+    fn format_method() {
+        assert_eq!(
+            harness_position_of(r#"query = "SELECT {} FORM users".format(col)"#, "FORM"),
+            (1, 20)
+        );
+    }
+
+    #[test]
+    fn concatenation_across_lines() {
+        let code = "query = (\"SELECT id \"\n    + \"FORM users\")\n";
+        assert_eq!(harness_position_of(code, "FORM"), (2, 8));
+    }
+
+    #[test]
+    fn function_call_argument() {
+        assert_eq!(
+            harness_position_of(r#"execute("SELECT id FORM users")"#, "FORM"),
+            (1, 20)
+        );
+    }
+
+    #[test]
+    fn source_text_of_the_expression() {
+        let code =
+            "BASE = \"SELECT id FROM users\"\nquery = BASE\nquery = f\"{BASE} WHERE id = {uid}\"\n";
+        let sources: Vec<String> = harness(FinderConfig::new(&["query".to_string()], &[]), code)
+            .into_iter()
+            .map(|sql_string| sql_string.source)
+            .collect();
+        assert_eq!(sources, vec!["BASE", "f\"{BASE} WHERE id = {uid}\""]);
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use sqlparser::dialect::{Dialect, GenericDialect, MsSqlDialect, PostgreSqlDialect};
+
+    fn harness_split(
+        dialect: Box<dyn Dialect + Send + Sync>,
+        sql: &str,
+    ) -> Vec<(String, (usize, usize))> {
+        let finder = SqlFinder::new(FinderConfig::new(&[], &[]).with_dialect(dialect).into());
+        finder
+            .split_sql(sql)
+            .into_iter()
+            .map(|s| (s.sql_content, (s.range.start.line(), s.range.start.col())))
+            .collect()
+    }
+
+    fn statements(found: &[(String, (usize, usize))]) -> Vec<&str> {
+        found.iter().map(|(sql, _)| sql.as_str()).collect()
+    }
+
+    #[test]
+    fn semicolons_in_strings_and_comments() {
+        let sql = "-- setup; not a statement\nINSERT INTO t VALUES ('a;b'); /* ; */ SELECT \"x;y\" FROM t\n-- trailing;\n;\n  UPDATE t SET a = 'é;' ; ";
+        assert_eq!(
+            harness_split(Box::new(GenericDialect {}), sql),
+            vec![
+                ("INSERT INTO t VALUES ('a;b')".to_string(), (2, 1)),
+                ("SELECT \"x;y\" FROM t".to_string(), (2, 39)),
+                ("UPDATE t SET a = 'é;'".to_string(), (5, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn dollar_quoted_bodies() {
+        let sql = "CREATE FUNCTION f() RETURNS int AS $$\nBEGIN\n  RETURN 1;\nEND;\n$$ LANGUAGE plpgsql;\nDO $body$ BEGIN PERFORM 1; END $body$;\nSELECT 1;";
+        let found = harness_split(Box::new(PostgreSqlDialect {}), sql);
+        assert_eq!(
+            statements(&found),
+            vec![
+                "CREATE FUNCTION f() RETURNS int AS $$\nBEGIN\n  RETURN 1;\nEND;\n$$ LANGUAGE plpgsql",
+                "DO $body$ BEGIN PERFORM 1; END $body$",
+                "SELECT 1",
+            ]
+        );
+        assert_eq!(found[2].1, (7, 1));
+    }
+
+    #[test]
+    fn begin_end_blocks() {
+        let sql = "CREATE TRIGGER t AFTER INSERT ON a FOR EACH ROW\nBEGIN\n  UPDATE b SET n = CASE WHEN n > 0 THEN n ELSE 0 END;\n  INSERT INTO c VALUES (1);\nEND;\nBEGIN;\nSELECT begin, \"end\" FROM spans;\nBEGIN TRANSACTION;\nCOMMIT;";
+        assert_eq!(
+            statements(&harness_split(Box::new(GenericDialect {}), sql)),
+            vec![
+                "CREATE TRIGGER t AFTER INSERT ON a FOR EACH ROW\nBEGIN\n  UPDATE b SET n = CASE WHEN n > 0 THEN n ELSE 0 END;\n  INSERT INTO c VALUES (1);\nEND",
+                "BEGIN",
+                "SELECT begin, \"end\" FROM spans",
+                "BEGIN TRANSACTION",
+                "COMMIT",
+            ]
+        );
+
+        let sql = "CREATE PROCEDURE p() BEGIN\n  IF x THEN SELECT 1; END IF;\n  CASE y WHEN 1 THEN SELECT 2; END CASE;\nEND;\nSELECT 3;";
+        assert_eq!(
+            statements(&harness_split(Box::new(GenericDialect {}), sql)),
+            vec![
+                "CREATE PROCEDURE p() BEGIN\n  IF x THEN SELECT 1; END IF;\n  CASE y WHEN 1 THEN SELECT 2; END CASE;\nEND",
+                "SELECT 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn delimiter_directives() {
+        let sql = "SELECT 1;\nDELIMITER //\nCREATE PROCEDURE p()\nBEGIN\n  SELECT 2;\nEND //\nDELIMITER $$\nCREATE FUNCTION f() RETURNS INT RETURN 3; $$\ndelimiter ;\nSELECT 4; SELECT 5;";
+        let found = harness_split(Box::new(PostgreSqlDialect {}), sql);
+        assert_eq!(
+            statements(&found),
+            vec![
+                "SELECT 1",
+                "CREATE PROCEDURE p()\nBEGIN\n  SELECT 2;\nEND",
+                "CREATE FUNCTION f() RETURNS INT RETURN 3;",
+                "SELECT 4",
+                "SELECT 5",
+            ]
+        );
+        assert_eq!(found[1].1, (3, 1));
+        assert_eq!(found[4].1, (10, 11));
+    }
+
+    #[test]
+    fn go_batches() {
+        let sql =
+            "CREATE TABLE t (a INT)\nGO\nINSERT INTO t VALUES (1)\ngo 2\nSELECT a AS go FROM t\n";
+        let found = harness_split(Box::new(MsSqlDialect {}), sql);
+        assert_eq!(
+            found,
+            vec![
+                ("CREATE TABLE t (a INT)".to_string(), (1, 1)),
+                ("INSERT INTO t VALUES (1)".to_string(), (3, 1)),
+                ("SELECT a AS go FROM t".to_string(), (5, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_string_keeps_the_rest() {
+        let sql = "SELECT 1;\nSELECT 'oops;\nSELECT 2;";
+        assert_eq!(
+            harness_split(Box::new(GenericDialect {}), sql),
+            vec![
+                ("SELECT 1".to_string(), (1, 1)),
+                ("SELECT 'oops;\nSELECT 2;".to_string(), (2, 1)),
+            ]
+        );
+    }
+}
@@ -1,7 +1,14 @@
 #![allow(clippy::needless_collect, clippy::single_match_else)]
-use crate::finder_types::{FinderType, SqlResult};
+use crate::constants::{self, Binding, ScopeKind};
+use crate::finder_types::{
+    Branch, CallParams, Context, FinderType, Interpolation, InterpolationKind, MappedStr,
+    SqlResult, Variant,
+};
 use crate::format::format_python_string;
+use crate::functions;
+use crate::literal::{LiteralPart, decode_literal};
 use crate::preanalysis::PreanalyzedFile;
+use crate::presets::{PARAM_KEYWORDS, Params};
 use crate::{SqlFinder, SqlString};
 use logging::{bail, bail_with};
 use rustpython_parser::ast::{Operator, Ranged};
@@ -17,24 +24,20 @@ impl SqlFinder {
         assign: &ast::StmtAssign,
         range_file: &PreanalyzedFile,
     ) -> Vec<SqlString> {
-        let mut sql_strings = vec![];
-
-        assign.targets.iter().for_each(|target| {
-            sql_strings = self
-                .process_assignment_target(target, &assign.value)
-                .into_iter()
-                .filter_map(|result| sql_result_to_string(result, range_file))
-                .collect();
-        });
-        sql_strings
+        assign
+            .targets
+            .iter()
+            .flat_map(|target| self.process_assignment_target(target, &assign.value, range_file))
+            .filter_map(|result| sql_result_to_string(result, range_file))
+            .collect()
     }
 
-    pub(super) fn analyze_stmt_expr(
+    pub(super) fn analyze_expr(
         &self,
-        e: &ast::StmtExpr,
+        value: &ast::Expr,
         range_file: &PreanalyzedFile,
     ) -> Vec<SqlString> {
-        self.process_expr_stmt(&e.value)
+        self.process_expr_stmt(value, range_file)
             .into_iter()
             .filter_map(|result| sql_result_to_string(result, range_file))
             .collect()
@@ -46,7 +49,7 @@ impl SqlFinder {
         range_file: &PreanalyzedFile,
     ) -> Vec<SqlString> {
         assign.value.as_ref().map_or_else(Vec::new, |val| {
-            self.process_assignment_target(&assign.target, val)
+            self.process_assignment_target(&assign.target, val, range_file)
                 .into_iter()
                 .filter_map(|result| sql_result_to_string(result, range_file))
                 .collect()
@@ -54,15 +57,337 @@ impl SqlFinder {
     }
 }
 
+// Constant propagation
+impl SqlFinder {
+    /// Remembers the strings assigned to the names of `target`, along with the SQL reported
+    /// for them in `found`
+    pub(super) fn bind_target(
+        &self,
+        target: &ast::Expr,
+        value: &ast::Expr,
+        found: &[SqlString],
+        range_file: &PreanalyzedFile,
+    ) {
+        let mut bindings = Vec::new();
+        self.target_bindings(target, value, found, range_file, &mut bindings);
+        // All values are known before any is assigned, like in `a, b = b, a`
+        let mut constants = self.constants.borrow_mut();
+        for (name, binding) in bindings {
+            constants.bind(&name, binding);
+        }
+    }
+
+    fn target_bindings(
+        &self,
+        target: &ast::Expr,
+        value: &ast::Expr,
+        found: &[SqlString],
+        range_file: &PreanalyzedFile,
+        bindings: &mut Vec<(String, Binding)>,
+    ) {
+        match (target, value) {
+            (ast::Expr::Name(name), _) => {
+                let string = self.string_value(value, range_file);
+                let mut constants = self.constants.borrow_mut();
+                // `query = query + "..."` extends the SQL that was reported for `query`
+                if string.is_some()
+                    && constants::mentions(value, &name.id)
+                    && let Some(previous) = constants.lookup(&name.id)
+                {
+                    constants.supersede(&previous);
+                }
+                let reported = string.as_ref().and_then(|_| {
+                    found.iter().find(|s| {
+                        s.variable_name == name.id.as_str()
+                            && matches!(s.context, Some(Context::Variable(_)))
+                    })
+                });
+                let binding = Binding {
+                    value: string.or_else(|| Self::number_value(value)),
+                    reported: reported
+                        .map(|s| {
+                            (
+                                (s.range.start.byte_offset(), s.variable_name.clone()),
+                                constants.block(),
+                            )
+                        })
+                        .into_iter()
+                        .collect(),
+                    module: None,
+                };
+                bindings.push((name.id.to_string(), binding));
+            }
+            (
+                ast::Expr::Tuple(ast::ExprTuple { elts: targets, .. })
+                | ast::Expr::List(ast::ExprList { elts: targets, .. }),
+                ast::Expr::Tuple(ast::ExprTuple { elts: values, .. })
+                | ast::Expr::List(ast::ExprList { elts: values, .. }),
+            ) if targets.len() == values.len()
+                && !targets.iter().any(|t| matches!(t, ast::Expr::Starred(_))) =>
+            {
+                for (target, value) in targets.iter().zip(values) {
+                    self.target_bindings(target, value, found, range_file, bindings);
+                }
+            }
+            _ => constants::target_names(target, &mut |name| {
+                bindings.push((name.to_string(), Binding::default()));
+            }),
+        }
+    }
+
+    /// `query += "..."` extends the string of `query`, the SQL reported for it before in the
+    /// same block is replaced by the extended SQL
+    pub(super) fn analyze_aug_assignment(
+        &self,
+        assign: &ast::StmtAugAssign,
+        range_file: &PreanalyzedFile,
+    ) -> Vec<SqlString> {
+        let ast::Expr::Name(name) = &*assign.target else {
+            return vec![];
+        };
+        let previous = self.constants.borrow().lookup(&name.id).unwrap_or_default();
+        let value = previous.value.clone().and_then(|current| {
+            let rhs = self.extract_content(&assign.value, range_file)?;
+            match self.apply_operator(current, rhs, assign.op)? {
+                value @ (FinderType::Str(_) | FinderType::Variants(_)) => Some(value),
+                _ => None,
+            }
+        });
+        let Some(value) = value else {
+            self.constants
+                .borrow_mut()
+                .bind(&name.id, Binding::default());
+            return vec![];
+        };
+
+        let found: Vec<SqlString> = self
+            .config
+            .is_sql_variable_name(&name.id)
+            .then(|| SqlResult {
+                byte_range: assign.range.into(),
+                variable_name: name.id.to_string(),
+                content: value.clone(),
+                params: None,
+                context: self.config.variable_context(&name.id),
+                param_styles: &[],
+                dialect: None,
+            })
+            .and_then(|result| sql_result_to_string(result, range_file))
+            .into_iter()
+            .collect();
+
+        let mut constants = self.constants.borrow_mut();
+        constants.supersede(&previous);
+        let reported = found
+            .first()
+            .map(|s| {
+                (
+                    (s.range.start.byte_offset(), s.variable_name.clone()),
+                    constants.block(),
+                )
+            })
+            .into_iter()
+            .collect();
+        constants.bind(
+            &name.id,
+            Binding {
+                value: Some(value),
+                reported,
+                module: None,
+            },
+        );
+        found
+    }
+
+    /// String value of an expression, or its variants, if it's one that can produce a known
+    /// string
+    pub(crate) fn string_value(
+        &self,
+        expr: &ast::Expr,
+        range_file: &PreanalyzedFile,
+    ) -> Option<FinderType> {
+        let is_string_expr = match expr {
+            ast::Expr::Constant(c) => matches!(c.value, ast::Constant::Str(_)),
+            ast::Expr::JoinedStr(_)
+            | ast::Expr::BinOp(_)
+            | ast::Expr::Name(_)
+            | ast::Expr::IfExp(_) => true,
+            ast::Expr::Call(call) => match &*call.func {
+                ast::Expr::Attribute(attr) if attr.attr.as_str() == "format" => true,
+                ast::Expr::Name(name) if self.constants.borrow().function(&name.id).is_some() => {
+                    true
+                }
+                // SQL objects like `text()` hold their SQL
+                func @ (ast::Expr::Name(_) | ast::Expr::Attribute(_)) => self
+                    .api_function(&Self::extract_function_name(func))
+                    .is_some_and(|function| function.wraps),
+                _ => false,
+            },
+            _ => false,
+        };
+        if !is_string_expr {
+            return None;
+        }
+        match self.extract_content(expr, range_file)? {
+            value @ (FinderType::Str(_) | FinderType::Variants(_)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Value of an int or float literal, which is spliced into SQL as the number it is
+    pub(super) fn number_value(expr: &ast::Expr) -> Option<FinderType> {
+        match expr {
+            ast::Expr::Constant(c)
+                if matches!(c.value, ast::Constant::Int(_) | ast::Constant::Float(_)) =>
+            {
+                Self::extract_const(&c.value, c.range.start().to_usize())
+            }
+            _ => None,
+        }
+    }
+
+    /// Value of a name, a placeholder if it isn't known
+    fn resolve_name(&self, name: &str) -> FinderType {
+        self.constants
+            .borrow()
+            .lookup(name)
+            .and_then(|binding| binding.value)
+            .unwrap_or(FinderType::Placeholder)
+    }
+
+    /// Value of `module.NAME` for an imported module, a placeholder if it isn't known
+    fn resolve_attribute(&self, attr: &ast::ExprAttribute) -> FinderType {
+        let ast::Expr::Name(name) = &*attr.value else {
+            return FinderType::Placeholder;
+        };
+        let module = self
+            .constants
+            .borrow()
+            .lookup(&name.id)
+            .and_then(|binding| binding.module);
+        module
+            .and_then(|module| self.imported_module(&module))
+            .and_then(|constants| constants.get(attr.attr.as_str()).cloned())
+            .map_or(FinderType::Placeholder, |text| {
+                FinderType::Str(MappedStr::opaque(text, attr.range.start().to_usize()))
+            })
+    }
+
+    /// Whether a name holds a string that was reported as SQL already
+    fn is_reported(&self, name: &str) -> bool {
+        self.constants
+            .borrow()
+            .lookup(name)
+            .is_some_and(|binding| binding.value.is_some() && !binding.reported.is_empty())
+    }
+
+    /// Branch taken when `test` holds, with the condition as written
+    pub(super) fn branch(test: &ast::Expr, range_file: &PreanalyzedFile) -> Branch {
+        Branch {
+            condition: range_file.src(test.range()).to_string(),
+            line: range_file
+                .linecol_at(test.range().start().to_usize())
+                .line(),
+            taken: true,
+        }
+    }
+}
+
+// Interprocedural analysis
+impl SqlFinder {
+    /// Value a call of a module function returns, known when all of its `return` statements
+    /// return the same string for the arguments of the call. Also tells whether the string was
+    /// reported where the function built it, which is the case for a function without
+    /// parameters returning a name holding SQL.
+    fn call_value(
+        &self,
+        call: &ast::ExprCall,
+        range_file: &PreanalyzedFile,
+    ) -> Option<(FinderType, bool)> {
+        let ast::Expr::Name(name) = &*call.func else {
+            return None;
+        };
+        let function = self.constants.borrow().function(&name.id)?;
+        let arguments: Vec<(String, Binding)> = functions::arguments(&function.args, call)?
+            .into_iter()
+            .map(|(param, arg)| {
+                let binding = Binding {
+                    value: arg.and_then(|arg| self.string_value(arg, range_file)),
+                    ..Binding::default()
+                };
+                (param.to_string(), binding)
+            })
+            .collect();
+
+        let mut constants = self.constants.borrow_mut();
+        if !constants.enter_call(&name.id) {
+            return None;
+        }
+        constants.push(
+            ScopeKind::Function,
+            constants::function_locals(&function.args, &function.body),
+        );
+        for (param, binding) in &arguments {
+            constants.bind(param, binding.clone());
+        }
+        drop(constants);
+        // Only what the function returns matters, the SQL in it is reported at its definition
+        let _ = self.analyze_block(&function.body, range_file);
+        let mut constants = self.constants.borrow_mut();
+        constants.pop();
+        let returns = constants.exit_call();
+
+        let first = returns.first()?.as_ref()?;
+        let value = first.value.clone()?;
+        // Returns at different places agree if they return the same text
+        if !returns.iter().all(|r| {
+            r.as_ref()
+                .and_then(|r| r.value.as_ref())
+                .is_some_and(|other| other.same_text(&value))
+        }) {
+            return None;
+        }
+        let reported = arguments.is_empty()
+            && returns
+                .iter()
+                .all(|r| r.as_ref().is_some_and(|r| !r.reported.is_empty()));
+        Some((value, reported))
+    }
+
+    /// What a `return` statement returns, along with the SQL reported for a returned name
+    pub(super) fn returned_binding(
+        &self,
+        value: &ast::Expr,
+        range_file: &PreanalyzedFile,
+    ) -> Binding {
+        let reported = match value {
+            ast::Expr::Name(name) => self
+                .constants
+                .borrow()
+                .lookup(&name.id)
+                .map(|binding| binding.reported)
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        Binding {
+            value: self.string_value(value, range_file),
+            reported,
+            module: None,
+        }
+    }
+}
+
 // Internal processing
 impl SqlFinder {
-    fn process_expr_stmt(&self, value: &ast::Expr) -> Vec<SqlResult> {
+    fn process_expr_stmt(&self, value: &ast::Expr, rf: &PreanalyzedFile) -> Vec<SqlResult> {
         match value {
-            ast::Expr::Call(call) => self.process_call_expr(call),
+            ast::Expr::Call(call) => self.process_call_expr(call, rf),
             ast::Expr::Attribute(_) => match value {
-                ast::Expr::Call(call) => self.process_call_expr(call),
+                ast::Expr::Call(call) => self.process_call_expr(call, rf),
                 _ => bail_with!(vec![], "Unhandled expr_stmt value pattern: {value:?}"),
             },
+            // Calls of async database APIs
+            ast::Expr::Await(a) => self.process_expr_stmt(&a.value, rf),
             ast::Expr::Constant(_) => vec![],
             _ => {
                 bail_with!(vec![], "Unhandled expr_stmt value pattern: {value:?}")
@@ -70,55 +395,151 @@ impl SqlFinder {
         }
     }
 
-    fn process_call_expr(&self, call: &ast::ExprCall) -> Vec<SqlResult> {
+    fn process_call_expr(&self, call: &ast::ExprCall, rf: &PreanalyzedFile) -> Vec<SqlResult> {
         let function_name = Self::extract_function_name(&call.func);
 
         if !self.config.is_sql_function_name(&function_name) {
             return vec![];
         }
 
-        let process_expr = |expr: &ast::Expr| -> Option<SqlResult> {
-            self.extract_content(expr).and_then(|content| {
+        let api_function = self.api_function(&function_name);
+        // SQL objects are checked where they are executed
+        if api_function.is_some_and(|function| function.wraps) {
+            return vec![];
+        }
+        // executemany takes a sequence of parameter rows
+        let many = api_function.map_or_else(|| function_name.ends_with("executemany"), |f| f.many);
+        let params_kw = call
+            .keywords
+            .iter()
+            .find(|kw| {
+                kw.arg
+                    .as_ref()
+                    .is_some_and(|arg| PARAM_KEYWORDS.contains(&arg.as_str()))
+            })
+            .map(|kw| &kw.value);
+
+        let process_expr = |expr: &ast::Expr, params: Option<CallParams>| -> Option<SqlResult> {
+            // SQL passed by a name was checked where the name was assigned, and SQL a function
+            // returns where the function built it
+            let reported = match expr {
+                ast::Expr::Name(name) => self.is_reported(&name.id),
+                ast::Expr::Call(call) => self
+                    .call_value(call, rf)
+                    .is_some_and(|(_, reported)| reported),
+                _ => false,
+            };
+            if reported {
+                return None;
+            }
+            self.extract_content(expr, rf).and_then(|content| {
                 content
-                    .get_str()
-                    .is_some_and(|s| self.config.is_sql_str(s))
-                    .then_some(SqlResult {
+                    .texts()
+                    .into_iter()
+                    .any(|s| self.config.is_sql_str(s))
+                    .then(|| SqlResult {
                         byte_range: call.range.into(),
                         variable_name: function_name.clone(),
                         content,
+                        params,
+                        context: self.config.function_context(&function_name, api_function),
+                        param_styles: api_function.map_or(&[], |f| f.styles),
+                        dialect: self.config.function_dialect(&function_name),
                     })
             })
         };
-        let kwargs = call
-            .keywords
-            .iter()
-            .filter_map(|kw| process_expr(&kw.value));
 
+        // Preset functions take the SQL and its parameters in known arguments
+        if let Some(function) = api_function {
+            let params = match &function.params {
+                Params::None => None,
+                Params::Argument(argument) => argument
+                    .of(call)
+                    .and_then(|params| Self::call_params(params, many)),
+                Params::Rest(position) => {
+                    let rest = call.args.get(*position..).unwrap_or_default();
+                    (!rest.iter().any(|e| matches!(e, ast::Expr::Starred(_))))
+                        .then_some(CallParams::Positional(rest.len()))
+                }
+            };
+            return function
+                .sql
+                .of(call)
+                .and_then(|sql| process_expr(sql, params))
+                .into_iter()
+                .collect();
+        }
+
+        let kwargs = call.keywords.iter().filter_map(|kw| {
+            process_expr(
+                &kw.value,
+                params_kw.and_then(|p| Self::call_params(p, many)),
+            )
+        });
+
+        // Parameters follow the SQL, unless they are passed by keyword
         call.args
             .iter()
-            .filter_map(process_expr)
+            .enumerate()
+            .filter_map(|(idx, arg)| {
+                let params = call.args.get(idx + 1).or(params_kw);
+                process_expr(arg, params.and_then(|p| Self::call_params(p, many)))
+            })
             .chain(kwargs)
             .collect()
     }
 
-    fn extract_content_flattened(&self, expr: &ast::Expr, variable_name: &str) -> Vec<SqlResult> {
+    /// Parameters given as a literal, `None` if their number or keys aren't known statically.
+    fn call_params(expr: &ast::Expr, many: bool) -> Option<CallParams> {
+        match expr {
+            ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+            | ast::Expr::List(ast::ExprList { elts, .. }) => {
+                if many {
+                    return elts.first().and_then(|row| Self::call_params(row, false));
+                }
+                (!elts.iter().any(|e| matches!(e, ast::Expr::Starred(_))))
+                    .then_some(CallParams::Positional(elts.len()))
+            }
+            ast::Expr::Dict(d) if !many => d
+                .keys
+                .iter()
+                .map(|key| match key {
+                    Some(ast::Expr::Constant(ast::ExprConstant {
+                        value: ast::Constant::Str(key),
+                        ..
+                    })) => Some(key.clone()),
+                    // Unpacked dicts and computed keys
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(CallParams::Named),
+            _ => None,
+        }
+    }
+
+    fn extract_content_flattened(
+        &self,
+        expr: &ast::Expr,
+        variable_name: &str,
+        rf: &PreanalyzedFile,
+    ) -> Vec<SqlResult> {
         match expr {
             ast::Expr::List(ast::ExprList { elts, .. }) => elts
                 .iter()
-                .flat_map(|elem| self.extract_content_flattened(elem, variable_name))
+                .flat_map(|elem| self.extract_content_flattened(elem, variable_name, rf))
                 .collect(),
             ast::Expr::Tuple(ast::ExprTuple { elts, .. }) => elts
                 .iter()
-                .flat_map(|elem| self.extract_content_flattened(elem, variable_name))
+                .flat_map(|elem| self.extract_content_flattened(elem, variable_name, rf))
                 .collect(),
 
             ast::Expr::Dict(ast::ExprDict { values, .. }) => values
                 .iter()
-                .flat_map(|elem| self.extract_content_flattened(elem, variable_name))
+                .flat_map(|elem| self.extract_content_flattened(elem, variable_name, rf))
                 .collect(),
             ast::Expr::BoolOp(ast::ExprBoolOp { values, .. }) => values
                 .iter()
-                .flat_map(|elem| self.extract_content_flattened(elem, variable_name))
+                .flat_map(|elem| self.extract_content_flattened(elem, variable_name, rf))
                 .collect(),
 
             ast::Expr::BinOp(bin @ ast::ExprBinOp { op, .. })
@@ -127,45 +548,70 @@ impl SqlFinder {
                     || *op == Operator::Mult
                     || *op == Operator::Div =>
             {
-                self.extract_from_bin_op(bin)
+                self.extract_from_bin_op(bin, rf)
                     .map_or_else(Vec::new, |content| {
                         vec![SqlResult {
                             byte_range: expr.range().into(),
                             variable_name: variable_name.to_string(),
                             content,
+                            params: None,
+                            context: self.config.variable_context(variable_name),
+                            param_styles: &[],
+                            dialect: None,
                         }]
                     })
             }
 
-            _ => self.extract_content(expr).map_or_else(Vec::new, |content| {
-                vec![SqlResult {
-                    byte_range: expr.range().into(),
-                    variable_name: variable_name.to_string(),
-                    content,
-                }]
-            }),
+            _ => self
+                .extract_content(expr, rf)
+                .map_or_else(Vec::new, |content| {
+                    vec![SqlResult {
+                        byte_range: expr.range().into(),
+                        variable_name: variable_name.to_string(),
+                        content,
+                        params: None,
+                        context: self.config.variable_context(variable_name),
+                        param_styles: &[],
+                        dialect: None,
+                    }]
+                }),
         }
     }
 
-    fn process_assignment_target(&self, target: &ast::Expr, value: &ast::Expr) -> Vec<SqlResult> {
+    fn process_assignment_target(
+        &self,
+        target: &ast::Expr,
+        value: &ast::Expr,
+        rf: &PreanalyzedFile,
+    ) -> Vec<SqlResult> {
         match target {
-            ast::Expr::Name(name) => self.process_by_ident(&name.id, value),
-            ast::Expr::Attribute(att) => self.process_by_ident(&att.attr, value),
-            ast::Expr::Tuple(tuple) => self.handle_tuple_assignment(&tuple.elts, value),
-            ast::Expr::List(list) => self.handle_tuple_assignment(&list.elts, value),
+            ast::Expr::Name(name) => self.process_by_ident(&name.id, value, rf),
+            ast::Expr::Attribute(att) => self.process_by_ident(&att.attr, value, rf),
+            ast::Expr::Tuple(tuple) => self.handle_tuple_assignment(&tuple.elts, value, rf),
+            ast::Expr::List(list) => self.handle_tuple_assignment(&list.elts, value, rf),
             ast::Expr::Subscript(_) => vec![],
             _ => bail_with!(vec![], "Unhandled assignment target pattern: {:?}", target),
         }
     }
 
-    fn process_by_ident(&self, name: &Identifier, value: &ast::Expr) -> Vec<SqlResult> {
+    fn process_by_ident(
+        &self,
+        name: &Identifier,
+        value: &ast::Expr,
+        rf: &PreanalyzedFile,
+    ) -> Vec<SqlResult> {
         if self.config.is_sql_variable_name(name) {
-            return self.extract_content_flattened(value, name);
+            return self.extract_content_flattened(value, name, rf);
         }
         vec![]
     }
 
-    fn handle_tuple_assignment(&self, targets: &[ast::Expr], value: &ast::Expr) -> Vec<SqlResult> {
+    fn handle_tuple_assignment(
+        &self,
+        targets: &[ast::Expr],
+        value: &ast::Expr,
+        rf: &PreanalyzedFile,
+    ) -> Vec<SqlResult> {
         let has_sql_target = targets
             .iter()
             .any(|target| self.target_contains_sql_variable(target));
@@ -176,10 +622,10 @@ impl SqlFinder {
 
         match value {
             ast::Expr::Tuple(tuple_value) => {
-                self.process_paired_assignments(targets, &tuple_value.elts)
+                self.process_paired_assignments(targets, &tuple_value.elts, rf)
             }
             ast::Expr::List(list_value) => {
-                self.process_paired_assignments(targets, &list_value.elts)
+                self.process_paired_assignments(targets, &list_value.elts, rf)
             }
             _ => bail_with!(vec![], "Unhandled tuple assignment value: {:?}", value),
         }
@@ -189,6 +635,7 @@ impl SqlFinder {
         &self,
         targets: &[ast::Expr],
         values: &[ast::Expr],
+        rf: &PreanalyzedFile,
     ) -> Vec<SqlResult> {
         let mut results = Vec::new();
         let mut value_idx = 0;
@@ -209,12 +656,13 @@ impl SqlFinder {
                     });
 
                     let target_results =
-                        self.process_assignment_target(starred_target, &new_list_expr);
+                        self.process_assignment_target(starred_target, &new_list_expr, rf);
                     results.extend(target_results);
                     value_idx += starred_count;
                 }
                 _ => {
-                    let target_results = self.process_assignment_target(target, &values[value_idx]);
+                    let target_results =
+                        self.process_assignment_target(target, &values[value_idx], rf);
                     results.extend(target_results);
                     value_idx += 1;
                 }
@@ -260,47 +708,141 @@ impl SqlFinder {
         }
     }
 
-    fn extract_content(&self, expr: &ast::Expr) -> Option<FinderType> {
+    fn extract_content(&self, expr: &ast::Expr, rf: &PreanalyzedFile) -> Option<FinderType> {
         match expr {
-            ast::Expr::Constant(c) => Self::extract_expr_const(c),
-            ast::Expr::Call(c) => self.extract_call(c),
-            ast::Expr::FormattedValue(f) => self.extract_content(&f.value),
-            ast::Expr::BinOp(b) => self.extract_from_bin_op(b),
+            ast::Expr::Constant(c) => Self::extract_expr_const(c, rf),
+            ast::Expr::Call(c) => self.extract_call(c, rf),
+            ast::Expr::FormattedValue(f) => self.extract_content(&f.value, rf),
+            ast::Expr::BinOp(b) => self.extract_from_bin_op(b, rf),
 
-            ast::Expr::Subscript(_) | ast::Expr::Name(_) | ast::Expr::Attribute(_) => {
-                Some(FinderType::Placeholder)
+            ast::Expr::Name(name) => Some(self.resolve_name(&name.id)),
+            ast::Expr::Attribute(attr) => Some(self.resolve_attribute(attr)),
+            ast::Expr::Subscript(_) => Some(FinderType::Placeholder),
+
+            ast::Expr::JoinedStr(j) => self.extract_joined_str(j, rf),
+            ast::Expr::IfExp(i) => {
+                let body = self.extract_content(&i.body, rf)?;
+                let orelse = self.extract_content(&i.orelse, rf)?;
+                Some(FinderType::either(
+                    body,
+                    orelse,
+                    &Self::branch(&i.test, rf),
+                    self.config.max_variants(),
+                ))
             }
+            _ => bail_with!(None, "Not extractable content: {:?}", expr),
+        }
+    }
 
-            ast::Expr::JoinedStr(j) => {
-                let parts: Option<Vec<FinderType>> = j
-                    .values
-                    .iter()
-                    .map(|val| self.extract_content(val))
-                    .collect();
+    fn extract_joined_str(
+        &self,
+        j: &ast::ExprJoinedStr<TextRange>,
+        rf: &PreanalyzedFile,
+    ) -> Option<FinderType> {
+        let values = j
+            .values
+            .iter()
+            .filter_map(|val| match val {
+                ast::Expr::FormattedValue(f) => Some(self.extract_content(&f.value, rf)),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        FinderType::combine(values, self.config.max_variants(), |values| {
+            Self::join_str(j, values, rf)
+        })
+    }
 
-                parts.map(|parts| {
-                    let combined = parts.into_iter().map(|p| p.to_string()).collect::<String>();
-                    FinderType::Str(combined)
-                })
+    /// F-string with the values of its replacement fields filled in
+    fn join_str(
+        j: &ast::ExprJoinedStr<TextRange>,
+        values: Vec<FinderType>,
+        rf: &PreanalyzedFile,
+    ) -> Option<FinderType> {
+        let start = j.range.start().to_usize();
+        let mut values = values.into_iter();
+        let mut literal = String::new();
+        let mut fields = vec![];
+        let mut combined = MappedStr::default();
+
+        for val in &j.values {
+            let origin = val.range().start().to_usize();
+            match val {
+                // Literal parts all report the range of the whole f-string, so only the
+                // replacement fields carry a usable position until the source is decoded.
+                ast::Expr::Constant(c) => {
+                    let content = Self::extract_const(&c.value, origin)?;
+                    if let FinderType::Str(s) = &content {
+                        literal.push_str(s);
+                    }
+                    combined.push_mapped(&content.to_mapped(origin));
+                }
+                ast::Expr::FormattedValue(f) => {
+                    let mapped = values
+                        .next()?
+                        .to_spliced(f.value.range(), InterpolationKind::FString);
+                    combined.push_mapped(&mapped);
+                    fields.push(mapped);
+                }
+                _ => bail!(None, "Unexpected f-string part: {:?}", val),
             }
-            _ => bail_with!(None, "Not extractable content: {:?}", expr),
         }
+
+        let Some(parts) = decode_literal(rf.src(j.range), start) else {
+            return Some(FinderType::Str(combined));
+        };
+        let decoded_literal: String = parts
+            .iter()
+            .filter_map(|part| match part {
+                LiteralPart::Text(text) => Some(&**text),
+                LiteralPart::Field => None,
+            })
+            .collect();
+        let field_cnt = parts
+            .iter()
+            .filter(|part| matches!(part, LiteralPart::Field))
+            .count();
+        if decoded_literal != literal || field_cnt != fields.len() {
+            return Some(FinderType::Str(combined));
+        }
+
+        let mut fields = fields.into_iter();
+        let mapped = parts
+            .into_iter()
+            .fold(MappedStr::default(), |mut acc, part| {
+                match part {
+                    LiteralPart::Text(text) => acc.push_mapped(&text),
+                    LiteralPart::Field => {
+                        acc.push_mapped(&fields.next().expect("Field counts were compared"));
+                    }
+                }
+                acc
+            });
+        Some(FinderType::Str(mapped))
     }
 
-    fn extract_from_bin_op(&self, v: &ast::ExprBinOp<TextRange>) -> Option<FinderType> {
+    fn extract_from_bin_op(
+        &self,
+        v: &ast::ExprBinOp<TextRange>,
+        rf: &PreanalyzedFile,
+    ) -> Option<FinderType> {
         match &v.op {
             ast::Operator::Mod => {
-                let expr_content = self.extract_content(&v.left)?;
+                let expr_content = self.extract_content(&v.left, rf)?;
+                let located = |e: &ast::Expr| {
+                    self.extract_content(e, rf)
+                        .map(|content| (content, e.range()))
+                };
                 let (args, kwargs) = match &*v.right {
-                    ast::Expr::Constant(c) => {
-                        (Self::extract_expr_const(c).into_iter().collect(), vec![])
-                    }
+                    ast::Expr::Constant(c) => (
+                        Self::extract_expr_const(c, rf)
+                            .map(|content| (content, c.range))
+                            .into_iter()
+                            .collect(),
+                        vec![],
+                    ),
                     ast::Expr::Tuple(ast::ExprTuple { elts, .. })
                     | ast::Expr::List(ast::ExprList { elts, .. }) => {
-                        let args = elts
-                            .iter()
-                            .filter_map(|e| self.extract_content(e))
-                            .collect();
+                        let args = elts.iter().filter_map(located).collect();
                         (args, vec![])
                     }
                     ast::Expr::Dict(d) => {
@@ -308,27 +850,50 @@ impl SqlFinder {
                             .keys
                             .iter()
                             .filter_map(|k| k.as_ref())
-                            .filter_map(|e| self.extract_content(e))
+                            .filter_map(|e| self.extract_content(e, rf))
                             .map(|k| k.to_string())
                             .collect();
-                        let values: Vec<FinderType> = d
-                            .values
-                            .iter()
-                            .filter_map(|e| self.extract_content(e))
+                        let values: Vec<(FinderType, TextRange)> =
+                            d.values.iter().filter_map(located).collect();
+                        let kwargs: Vec<_> = keys
+                            .into_iter()
+                            .zip(values)
+                            .map(|(k, (v, source))| (k, v, source))
                             .collect();
-                        let kwargs: Vec<_> = keys.into_iter().zip(values).collect();
                         (vec![], kwargs)
                     }
+                    // A single runtime value, e.g. `"... WHERE id = %s" % user_id`
+                    ast::Expr::Name(_) | ast::Expr::Attribute(_) | ast::Expr::Subscript(_) => {
+                        (located(&v.right).into_iter().collect(), vec![])
+                    }
                     _ => bail_with!((vec![], vec![]), "Unhandled rhs expr type: {:?}", v.right),
                 };
-                match expr_content {
-                    FinderType::Str(fmt_string) => {
-                        format_python_string(&fmt_string, &args, &kwargs).map(FinderType::Str)
+                let values = std::iter::once(expr_content)
+                    .chain(args.iter().map(|(value, _)| value.clone()))
+                    .chain(kwargs.iter().map(|(_, value, _)| value.clone()))
+                    .collect();
+                FinderType::combine(values, self.config.max_variants(), |values| {
+                    let (fmt, values) = values.split_first()?;
+                    let (arg_values, kwarg_values) = values.split_at(args.len());
+                    let args: Vec<(FinderType, TextRange)> = arg_values
+                        .iter()
+                        .cloned()
+                        .zip(args.iter().map(|(_, source)| *source))
+                        .collect();
+                    let kwargs: Vec<(String, FinderType, TextRange)> = kwargs
+                        .iter()
+                        .zip(kwarg_values)
+                        .map(|((key, _, source), value)| (key.clone(), value.clone(), *source))
+                        .collect();
+                    match fmt {
+                        FinderType::Str(fmt_string) => {
+                            format_python_string(fmt_string, &args, &kwargs).map(FinderType::Str)
+                        }
+                        other => Some(other.clone()),
                     }
-                    other => Some(other),
-                }
+                })
             }
-            _ => self.extract_arithmetic(&v.left, &v.right, v.op),
+            _ => self.extract_arithmetic(&v.left, &v.right, v.op, rf),
         }
     }
 
@@ -337,29 +902,49 @@ impl SqlFinder {
         lhs: &ast::Expr,
         rhs: &ast::Expr,
         op: ast::Operator,
+        rf: &PreanalyzedFile,
     ) -> Option<FinderType> {
-        let lhs_content = self.extract_content(lhs)?;
-        let rhs_content = self.extract_content(rhs)?;
+        let lhs_content = self.extract_content(lhs, rf)?;
+        let rhs_content = self.extract_content(rhs, rf)?;
+        self.apply_operator(lhs_content, rhs_content, op)
+    }
 
-        match op {
-            ast::Operator::Add => lhs_content + rhs_content,
-            ast::Operator::Sub => lhs_content - rhs_content,
-            ast::Operator::Mult => lhs_content * rhs_content,
-            ast::Operator::Div => lhs_content / rhs_content,
-            _ => bail!(None, "Unexpected operator in extraction: {:?}", op),
-        }
+    fn apply_operator(
+        &self,
+        lhs: FinderType,
+        rhs: FinderType,
+        op: ast::Operator,
+    ) -> Option<FinderType> {
+        FinderType::combine(vec![lhs, rhs], self.config.max_variants(), |operands| {
+            let [lhs, rhs] = <[FinderType; 2]>::try_from(operands).ok()?;
+            match op {
+                ast::Operator::Add => lhs + rhs,
+                ast::Operator::Sub => lhs - rhs,
+                ast::Operator::Mult => lhs * rhs,
+                ast::Operator::Div => lhs / rhs,
+                _ => bail!(None, "Unexpected operator in extraction: {:?}", op),
+            }
+        })
     }
 
-    fn extract_call(&self, v: &ast::ExprCall<TextRange>) -> Option<FinderType> {
+    fn extract_call(
+        &self,
+        v: &ast::ExprCall<TextRange>,
+        rf: &PreanalyzedFile,
+    ) -> Option<FinderType> {
         match &*v.func {
-            ast::Expr::Call(nested_call) => self.extract_call(nested_call),
+            ast::Expr::Call(nested_call) => self.extract_call(nested_call, rf),
             ast::Expr::Attribute(ast::ExprAttribute { attr, value, .. }) => match attr.as_str() {
-                "format" => self.extract_format_call(&v.args, &v.keywords, value),
-                _ => Some(FinderType::Placeholder),
+                "format" => self.extract_format_call(&v.args, &v.keywords, value, rf),
+                _ => Some(self.wrapped_sql(v, rf).unwrap_or(FinderType::Placeholder)),
             },
             ast::Expr::Name(name) => {
-                if self.config.is_sql_function_name(&name.id) {
-                    v.args.iter().find_map(|arg| self.extract_content(arg))
+                if let Some((value, _)) = self.call_value(v, rf) {
+                    Some(value)
+                } else if let Some(value) = self.wrapped_sql(v, rf) {
+                    Some(value)
+                } else if self.config.is_sql_function_name(&name.id) {
+                    v.args.iter().find_map(|arg| self.extract_content(arg, rf))
                 } else {
                     None
                 }
@@ -368,75 +953,129 @@ impl SqlFinder {
         }
     }
 
+    /// SQL held by the object a preset function like `text()` wraps it in
+    fn wrapped_sql(&self, call: &ast::ExprCall, rf: &PreanalyzedFile) -> Option<FinderType> {
+        let function = self
+            .api_function(&Self::extract_function_name(&call.func))
+            .filter(|function| function.wraps)?;
+        self.extract_content(function.sql.of(call)?, rf)
+    }
+
     fn extract_format_call(
         &self,
         args: &[ast::Expr],
         kwargs: &[ast::Keyword],
         value: &ast::Expr,
+        rf: &PreanalyzedFile,
     ) -> Option<FinderType> {
-        let mut pos_fills = vec![];
+        let mut pos_fills: Vec<(FinderType, TextRange)> = vec![];
         let mut kw_fills = vec![];
         let mut has_unpacked_dict = false;
         for a in args {
             let parsed = match a {
-                ast::Expr::Constant(c) => Self::extract_expr_const(c).into_iter().collect(),
-                ast::Expr::Subscript(_) | ast::Expr::Name(_) | ast::Expr::Call(_) => {
-                    vec![FinderType::Placeholder]
-                }
+                ast::Expr::Constant(c) => Self::extract_expr_const(c, rf).into_iter().collect(),
+                ast::Expr::Name(name) => vec![self.resolve_name(&name.id)],
+                ast::Expr::Subscript(_) | ast::Expr::Call(_) => vec![FinderType::Placeholder],
                 ast::Expr::List(els) => els
                     .elts
                     .iter()
-                    .filter_map(|e| self.extract_content(e))
+                    .filter_map(|e| self.extract_content(e, rf))
                     .collect(),
                 ast::Expr::BinOp(b) => self
-                    .extract_from_bin_op(b)
-                    .map_or_else(|| vec![], |content| vec![content]),
+                    .extract_from_bin_op(b, rf)
+                    .map_or_else(Vec::new, |content| vec![content]),
                 _ => bail_with!(vec![], "Unhandled value in args: {a:?}"),
             };
-            pos_fills.extend(parsed.iter().map(std::string::ToString::to_string));
+            pos_fills.extend(parsed.into_iter().map(|p| (p, a.range())));
         }
         for kw in kwargs {
             match &kw.arg {
                 Some(name) => {
-                    if let Some(val) = self.extract_content(&kw.value) {
-                        kw_fills.push((name.clone(), val));
+                    if let Some(val) = self.extract_content(&kw.value, rf) {
+                        kw_fills.push((name.clone(), val, kw.value.range()));
                     }
                 }
                 None => has_unpacked_dict = true,
             }
         }
-        let base_content = self.extract_content(value)?;
-        let mut result = base_content.to_string();
-        if !has_unpacked_dict {
+        let base_content = self.extract_content(value, rf)?;
+        // Composing SQL objects quotes the values filled in, which aren't spliced then
+        let composed = match value {
+            ast::Expr::Call(call) => self
+                .api_function(&Self::extract_function_name(&call.func))
+                .is_some_and(|function| function.composable),
+            _ => false,
+        };
+        let fill = |value: &FinderType, source: TextRange| {
+            if composed {
+                value.to_mapped(source.start().to_usize())
+            } else {
+                value.to_spliced(source, InterpolationKind::FormatCall)
+            }
+        };
+        let values = std::iter::once(base_content)
+            .chain(pos_fills.iter().map(|(fill, _)| fill.clone()))
+            .chain(kw_fills.iter().map(|(_, fill, _)| fill.clone()))
+            .collect();
+        FinderType::combine(values, self.config.max_variants(), |values| {
+            let (base, values) = values.split_first()?;
+            let (pos_values, kw_values) = values.split_at(pos_fills.len());
+            let mut result = base.to_mapped(value.range().start().to_usize());
+            if has_unpacked_dict {
+                return Some(FinderType::Str(result));
+            }
+            let pos_fills: Vec<MappedStr> = pos_values
+                .iter()
+                .zip(&pos_fills)
+                .map(|(value, (_, source))| fill(value, *source))
+                .collect();
+            for (idx, f) in pos_fills.iter().enumerate() {
+                result = result.replace_all(&format!("{{{idx}}}"), f);
+            }
             for f in pos_fills {
                 result = result.replacen("{}", &f, 1);
             }
-            for (kw_name, val) in &kw_fills {
+            for (val, (kw_name, _, source)) in kw_values.iter().zip(&kw_fills) {
                 let pat = format!("{{{kw_name}}}");
-                result = result.replace(&pat, &val.to_string());
+                result = result.replace_all(&pat, &fill(val, *source));
             }
-        }
-        Some(FinderType::Str(result))
+            Some(FinderType::Str(result))
+        })
     }
 
-    fn extract_expr_const(c: &ast::ExprConstant<TextRange>) -> Option<FinderType> {
-        Self::extract_const(&c.value)
+    fn extract_expr_const(
+        c: &ast::ExprConstant<TextRange>,
+        rf: &PreanalyzedFile,
+    ) -> Option<FinderType> {
+        let start = c.range.start().to_usize();
+        match &c.value {
+            ast::Constant::Str(s) => {
+                let mapped = decode_literal(rf.src(c.range), start)
+                    .and_then(|parts| match parts.as_slice() {
+                        [LiteralPart::Text(text)] if **text == **s => Some(text.clone()),
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| MappedStr::opaque(s.clone(), start));
+                Some(FinderType::Str(mapped))
+            }
+            other => Self::extract_const(other, start),
+        }
     }
 
-    fn extract_const(c: &ast::Constant) -> Option<FinderType> {
-        let result = match c {
-            ast::Constant::Str(s) => Some(FinderType::Str(s.clone())),
+    fn extract_const(c: &ast::Constant, origin: usize) -> Option<FinderType> {
+        match c {
+            ast::Constant::Str(s) => Some(FinderType::Str(MappedStr::opaque(s.clone(), origin))),
             ast::Constant::Int(i) => Some(FinderType::Int(i.to_string())),
             ast::Constant::Bool(b) => Some(FinderType::Bool(*b)),
             ast::Constant::Float(f) => Some(FinderType::Float(*f)),
             ast::Constant::None => None,
             ast::Constant::Tuple(t) => Some(FinderType::Tuple(
-                t.iter().filter_map(Self::extract_const).collect(),
+                t.iter()
+                    .filter_map(|c| Self::extract_const(c, origin))
+                    .collect(),
             )),
             _ => bail_with!(None, "Unhandled Constant: {:?}", c),
-        };
-
-        result
+        }
     }
 }
 
@@ -444,9 +1083,58 @@ fn sql_result_to_string(res: SqlResult, range_file: &PreanalyzedFile) -> Option<
     if res.content.is_placeholder() {
         return None;
     }
-    Some(SqlString {
-        variable_name: res.variable_name,
-        range: range_file.byterange_to_range(res.byte_range),
-        sql_content: res.content.to_string(),
-    })
+    let range = range_file.byterange_to_range(res.byte_range);
+    let variants = match &res.content {
+        FinderType::Variants(variants) => variants.clone(),
+        other => vec![Variant {
+            value: other.to_mapped(range.start.byte_offset()),
+            branches: Vec::new(),
+        }],
+    };
+    let mut variants = variants.into_iter().map(|variant| SqlString {
+        branches: variant.branches,
+        ..mapped_sql_string(&variant.value, &res, range.clone(), range_file)
+    });
+    let mut sql_string = variants.next()?;
+    sql_string.alternatives = variants.collect();
+    Some(sql_string)
+}
+
+fn mapped_sql_string(
+    mapped: &MappedStr,
+    res: &SqlResult,
+    range: crate::preanalysis::Range,
+    range_file: &PreanalyzedFile,
+) -> SqlString {
+    let mut interpolations: Vec<Interpolation> = mapped
+        .splices()
+        .iter()
+        .map(|splice| Interpolation {
+            kind: splice.kind,
+            expr: range_file.src(splice.source).to_string(),
+            position: range_file.linecol_at(splice.source.start().to_usize()),
+            range: splice.range.clone(),
+        })
+        .collect();
+    interpolations.sort_by_key(|interpolation| interpolation.range.start);
+    let line = range.start.line();
+    SqlString {
+        variable_name: res.variable_name.clone(),
+        source_map: range_file.source_map(mapped, mapped.origins()),
+        sql_content: mapped.to_string(),
+        source: range_file.text(&range).to_string(),
+        range,
+        interpolations,
+        params: res.params.clone(),
+        param_styles: res.param_styles.to_vec(),
+        context: Some(res.context.clone()),
+        // A pragma on the line names the dialect over the function, and the function over the file
+        dialect: range_file
+            .dialect_at(line)
+            .map(ToString::to_string)
+            .or_else(|| res.dialect.clone())
+            .or_else(|| range_file.file_dialect().map(ToString::to_string)),
+        branches: Vec::new(),
+        alternatives: Vec::new(),
+    }
 }
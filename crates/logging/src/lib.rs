@@ -7,7 +7,10 @@ use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
 static GLOBAL_LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Error as u8);
 static LOGGER_INITIALIZED: OnceLock<()> = OnceLock::new();
 static HAS_ERROR_OCCURRED: AtomicBool = AtomicBool::new(false);
+static PLAIN_OUTPUT: AtomicBool = AtomicBool::new(false);
+static STDERR_ONLY: AtomicBool = AtomicBool::new(false);
 static SQL_ERROR_TOTAL_CNT: AtomicU64 = AtomicU64::new(0);
+static SQL_WARNING_TOTAL_CNT: AtomicU64 = AtomicU64::new(0);
 static SQL_VAR_TOTAL_CNT: AtomicU64 = AtomicU64::new(0);
 
 #[derive(
@@ -48,7 +51,8 @@ impl LogLevel {
     }
 
     fn should_use_color() -> bool {
-        !(std::env::var("NO_COLOR").is_ok()
+        !(PLAIN_OUTPUT.load(Ordering::Relaxed)
+            || std::env::var("NO_COLOR").is_ok()
             || std::env::var("CI").is_ok()
             || !atty::is(atty::Stream::Stdout))
     }
@@ -63,6 +67,16 @@ impl Logger {
         });
     }
 
+    /// Disables colored output regardless of the terminal capabilities
+    pub fn use_plain_output() {
+        PLAIN_OUTPUT.store(true, Ordering::Relaxed);
+    }
+
+    /// Sends all log output to stderr, keeping stdout free for machine-readable reports
+    pub fn use_stderr_only() {
+        STDERR_ONLY.store(true, Ordering::Relaxed);
+    }
+
     pub fn current_level() -> LogLevel {
         let level_u8 = GLOBAL_LOG_LEVEL.load(Ordering::Relaxed);
         match level_u8 {
@@ -132,20 +146,22 @@ impl Logger {
             }
         };
 
-        match level {
-            LogLevel::Error | LogLevel::Bail => {
-                let _ = writeln!(io::stderr(), "{output}");
-            }
-            _ => {
-                let _ = writeln!(io::stdout(), "{output}");
-            }
+        if matches!(level, LogLevel::Error | LogLevel::Bail) || STDERR_ONLY.load(Ordering::Relaxed)
+        {
+            let _ = writeln!(io::stderr(), "{output}");
+        } else {
+            let _ = writeln!(io::stdout(), "{output}");
         }
 
         if level == LogLevel::Error {
-            HAS_ERROR_OCCURRED.store(true, Ordering::Relaxed);
+            Self::set_error_occurred();
         }
     }
 
+    pub fn set_error_occurred() {
+        HAS_ERROR_OCCURRED.store(true, Ordering::Relaxed);
+    }
+
     pub fn has_error_occurred() -> bool {
         HAS_ERROR_OCCURRED.load(Ordering::Relaxed)
     }
@@ -160,6 +176,10 @@ impl Logger {
         SQL_ERROR_TOTAL_CNT.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub fn increment_sql_warning_count() {
+        SQL_WARNING_TOTAL_CNT.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn increment_sql_var_count() {
         SQL_VAR_TOTAL_CNT.fetch_add(1, Ordering::Relaxed);
     }
@@ -168,6 +188,10 @@ impl Logger {
         SQL_ERROR_TOTAL_CNT.load(Ordering::Relaxed)
     }
 
+    pub fn get_sql_warning_count() -> u64 {
+        SQL_WARNING_TOTAL_CNT.load(Ordering::Relaxed)
+    }
+
     pub fn get_sql_var_count() -> u64 {
         SQL_VAR_TOTAL_CNT.load(Ordering::Relaxed)
     }
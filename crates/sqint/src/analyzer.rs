@@ -1,117 +1,319 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use sqlparser::dialect::{
-    AnsiDialect, BigQueryDialect, ClickHouseDialect, DuckDbDialect, GenericDialect, HiveDialect,
-    MsSqlDialect, MySqlDialect, PostgreSqlDialect, RedshiftSqlDialect, SQLiteDialect,
-    SnowflakeDialect,
-};
-
+use sqlparser::dialect::Dialect;
 use sqlparser::parser::{Parser, ParserError};
 
-use finder::{SqlExtract, SqlString};
-use logging::{sql_error, sql_info};
-
-#[derive(Debug, Clone)]
-pub enum SqlDialect {
-    Generic,
-    PostgreSQL,
-    SQLite,
-    Ansi,
-    BigQuery,
-    ClickHouse,
-    DuckDb,
-    Hive,
-    MsSql,
-    MySql,
-    RedshiftSql,
-    Snowflake,
-}
+use finder::{Branch, LineCol, MappedStr, ParamStyle, SqlString};
+
+use crate::dialect::SqlDialect;
+use crate::placeholders::Substitutions;
+use crate::report::{Diagnostic, DiagnosticKind, Location, Severity};
+use crate::rules::RuleSet;
+
+/// Parsable stand-in for parameter markers and values only known at runtime
+pub const PLACEHOLDER: &str = "PLACEHOLDER";
 
 pub struct SqlAnalyzer {
-    dialect: Box<dyn sqlparser::dialect::Dialect>,
-    mappings: HashMap<String, String>,
+    dialect_kind: SqlDialect,
+    dialect: Box<dyn Dialect>,
+    substitutions: Substitutions,
+    rules: Arc<RuleSet>,
 }
 
 impl SqlAnalyzer {
     pub fn new(
         dialect: &SqlDialect,
-        mut dialect_mappings: HashMap<String, String>,
+        dialect_mappings: HashMap<String, String>,
         placeholders: &[String],
+        rules: Arc<RuleSet>,
     ) -> Self {
-        let dialect: Box<dyn sqlparser::dialect::Dialect> = match dialect {
-            SqlDialect::Generic => Box::new(GenericDialect {}),
-            SqlDialect::PostgreSQL => Box::new(PostgreSqlDialect {}),
-            SqlDialect::SQLite => Box::new(SQLiteDialect {}),
-            SqlDialect::Ansi => Box::new(AnsiDialect {}),
-            SqlDialect::BigQuery => Box::new(BigQueryDialect {}),
-            SqlDialect::ClickHouse => Box::new(ClickHouseDialect {}),
-            SqlDialect::DuckDb => Box::new(DuckDbDialect {}),
-            SqlDialect::Hive => Box::new(HiveDialect {}),
-            SqlDialect::MsSql => Box::new(MsSqlDialect {}),
-            SqlDialect::MySql => Box::new(MySqlDialect {}),
-            SqlDialect::RedshiftSql => Box::new(RedshiftSqlDialect {}),
-            SqlDialect::Snowflake => Box::new(SnowflakeDialect {}),
-        };
-        for p in placeholders {
-            dialect_mappings.insert(p.clone(), "PLACEHOLDER".to_string());
+        Self {
+            dialect_kind: *dialect,
+            dialect: dialect.parser_dialect(),
+            substitutions: Substitutions::new(dialect_mappings, placeholders),
+            rules,
         }
+    }
 
-        Self {
-            dialect,
-            mappings: dialect_mappings,
+    pub const fn dialect(&self) -> SqlDialect {
+        self.dialect_kind
+    }
+
+    /// Checks a single SQL string in each of its variants, returning one diagnostic per finding
+    /// or a single valid one. Findings only some variants have name the branches one was built in.
+    pub fn analyze_sql_string(
+        &self,
+        sql_string: &SqlString,
+        filename: &str,
+        migration: bool,
+    ) -> Vec<Diagnostic> {
+        let variants: Vec<&SqlString> = sql_string.variants().collect();
+        let mut found: Vec<(Diagnostic, &[Branch], usize)> = Vec::new();
+        for variant in &variants {
+            for diagnostic in self.analyze_variant(variant, filename, migration) {
+                let same = found.iter_mut().find(|(d, ..)| {
+                    (d.location.line, d.location.col, d.kind, &d.reason)
+                        == (
+                            diagnostic.location.line,
+                            diagnostic.location.col,
+                            diagnostic.kind,
+                            &diagnostic.reason,
+                        )
+                });
+                match same {
+                    Some((_, _, count)) => *count += 1,
+                    None => found.push((diagnostic, &variant.branches, 1)),
+                }
+            }
+        }
+
+        if found.is_empty() {
+            let filled_sql =
+                self.fill_placeholders(&sql_string.sql_content, &sql_string.param_styles);
+            let position = sql_string.range.start.clone();
+            return vec![self.diagnostic(sql_string, filename, &filled_sql, &position, None)];
         }
+        found
+            .into_iter()
+            .map(|(mut diagnostic, branches, count)| {
+                if count < variants.len()
+                    && !branches.is_empty()
+                    && let Some(reason) = &mut diagnostic.reason
+                {
+                    let branches: Vec<String> = branches.iter().map(ToString::to_string).collect();
+                    *reason = format!("{reason}, when {}", branches.join(" and "));
+                }
+                diagnostic
+            })
+            .collect()
     }
 
-    pub fn analyze_sql_extract(&self, extract: &SqlExtract) {
-        extract
-            .strings
-            .iter()
-            .for_each(|sql_string| self.analyze_sql_string(sql_string, &extract.rel_path));
+    /// Why a SQL string doesn't parse in the dialect, in the first variant that doesn't.
+    /// Only the parser decides, whatever rules are configured.
+    pub fn syntax_error(&self, sql_string: &SqlString) -> Option<String> {
+        sql_string.variants().find_map(|variant| {
+            let filled_sql = self.fill_placeholders(&variant.sql_content, &variant.param_styles);
+            Parser::parse_sql(&*self.dialect, &filled_sql)
+                .err()
+                .map(|e| SqlError::from_parser_error(e).reason)
+        })
     }
 
-    fn analyze_sql_string(&self, sql_string: &SqlString, filename: &str) {
-        let filled_sql = self.fill_placeholders(&sql_string.sql_content);
+    /// Diagnostics of the findings in a single variant of a SQL string
+    fn analyze_variant(
+        &self,
+        sql_string: &SqlString,
+        filename: &str,
+        migration: bool,
+    ) -> Vec<Diagnostic> {
+        let filled_sql = self.fill_placeholders(&sql_string.sql_content, &sql_string.param_styles);
+
+        let mut findings: Vec<(LineCol, DiagnosticKind, String)> =
+            match Parser::parse_sql(&*self.dialect, &filled_sql) {
+                Ok(statements) => self
+                    .rules
+                    .check(&statements, sql_string, migration)
+                    .into_iter()
+                    .map(|violation| {
+                        let offset = violation
+                            .location
+                            .and_then(|(line, col)| offset_of(&filled_sql, line, col));
+                        (
+                            Self::source_position(sql_string, &filled_sql, offset),
+                            violation.kind,
+                            violation.reason,
+                        )
+                    })
+                    .collect(),
+                Err(e) => {
+                    let error = SqlError::from_parser_error(e);
+                    vec![(
+                        Self::error_position(sql_string, &filled_sql, &error),
+                        DiagnosticKind::SyntaxError,
+                        error.reason,
+                    )]
+                }
+            };
+
+        // Values spliced into the SQL point at the Python expression they come from
+        findings.extend(sql_string.interpolations.iter().map(|interpolation| {
+            (
+                interpolation.position.clone(),
+                DiagnosticKind::SqlInjection,
+                format!(
+                    "`{}` is spliced into the SQL by {}, pass it as a bound parameter instead",
+                    interpolation.expr, interpolation.kind
+                ),
+            )
+        }));
 
-        match Parser::parse_sql(&*self.dialect, &filled_sql) {
-            Ok(_) => {
-                sql_info!(
-                    "./{}:{}:{}: Valid sql string: `{}`",
+        // Findings of rules turned off in the config are dropped, the rest get the configured severity
+        findings
+            .into_iter()
+            .filter_map(|(position, kind, reason)| {
+                let severity = self.rules.severity(kind)?;
+                Some(self.diagnostic(
+                    sql_string,
                     filename,
-                    sql_string.range.start,
-                    sql_string.variable_name,
-                    filled_sql
-                );
+                    &filled_sql,
+                    &position,
+                    Some((kind, severity, reason)),
+                ))
+            })
+            .collect()
+    }
+
+    fn diagnostic(
+        &self,
+        sql_string: &SqlString,
+        filename: &str,
+        sql: &str,
+        position: &LineCol,
+        finding: Option<(DiagnosticKind, Severity, String)>,
+    ) -> Diagnostic {
+        let (kind, severity, reason) = match finding {
+            Some((kind, severity, reason)) => (Some(kind), severity, Some(reason)),
+            None => (None, Severity::Info, None),
+        };
+        Diagnostic {
+            location: Location {
+                file: filename.to_string(),
+                cell: None,
+                line: position.line(),
+                col: position.col(),
+                name: sql_string.variable_name.clone(),
+            },
+            sql: sql.to_string(),
+            source: sql_string.source.clone(),
+            dialect: self.dialect_kind.name(),
+            severity,
+            kind,
+            reason,
+        }
+    }
+
+    /// Source position the error points at. Errors without a position, which sqlparser reports
+    /// for an unexpected end of input, point right after the end of the SQL.
+    fn error_position(sql_string: &SqlString, filled: &MappedStr, error: &SqlError) -> LineCol {
+        let filled_offset = error.location.map_or(Some(filled.len()), |(line, col)| {
+            offset_of(filled, line, col)
+        });
+        Self::source_position(sql_string, filled, filled_offset)
+    }
+
+    /// Source position of a byte offset in the filled SQL, the start of the string if unknown.
+    fn source_position(
+        sql_string: &SqlString,
+        filled: &MappedStr,
+        filled_offset: Option<usize>,
+    ) -> LineCol {
+        filled_offset.map_or_else(
+            || sql_string.range.start.clone(),
+            |offset| {
+                // The end of the filled SQL is the end of the original one
+                let origin = (offset < filled.len())
+                    .then(|| filled.origin_at(offset))
+                    .flatten()
+                    .unwrap_or(sql_string.sql_content.len());
+                sql_string.position_at(origin)
+            },
+        )
+    }
+
+    /// SQL with placeholders and dialect mappings substituted, every byte remembering the
+    /// offset in `sql` it was produced from.
+    fn fill_placeholders(&self, sql: &str, styles: &[ParamStyle]) -> MappedStr {
+        let mut filled = MappedStr::verbatim(sql, 0);
+        // Replaced from the back, so the ranges found in `sql` still hold
+        for (range, to) in self.substitutions.find(sql, styles).into_iter().rev() {
+            let with = MappedStr::opaque(to.to_string(), range.start);
+            filled.replace_range(range, &with);
+        }
+        filled
+    }
+}
+
+/// Checks a SQL string in each of the dialects it must parse in, one analyzer per dialect.
+/// The same finding is reported once, naming the dialects it's found in unless it's all of them.
+pub fn analyze_in_dialects(
+    analyzers: &[&SqlAnalyzer],
+    sql_string: &SqlString,
+    filename: &str,
+    migration: bool,
+) -> Vec<Diagnostic> {
+    if let [analyzer] = analyzers {
+        return analyzer.analyze_sql_string(sql_string, filename, migration);
+    }
+    let mut valid = None;
+    let mut found: Vec<(Diagnostic, Vec<&'static str>)> = Vec::new();
+    for analyzer in analyzers {
+        for diagnostic in analyzer.analyze_sql_string(sql_string, filename, migration) {
+            if diagnostic.kind.is_none() {
+                valid.get_or_insert(diagnostic);
+                continue;
             }
-            Err(e) => {
-                sql_error!(
-                    "./{}:{}:{}: `{}` => {}",
-                    filename,
-                    sql_string.range.start,
-                    sql_string.variable_name,
-                    filled_sql,
-                    SqlError::from_parser_error(e).reason
-                );
+            let same = found.iter_mut().find(|(d, _)| {
+                (d.location.line, d.location.col, d.kind, &d.reason)
+                    == (
+                        diagnostic.location.line,
+                        diagnostic.location.col,
+                        diagnostic.kind,
+                        &diagnostic.reason,
+                    )
+            });
+            match same {
+                Some((_, dialects)) => dialects.push(diagnostic.dialect),
+                None => {
+                    let dialect = diagnostic.dialect;
+                    found.push((diagnostic, vec![dialect]));
+                }
             }
         }
     }
 
-    // Multipass fill doesnt' seem to induce much of a performance loss on a reasonable scale.
-    // So singlepass is probably not needed for now.
-    fn fill_placeholders(&self, sql: &str) -> String {
-        self.mappings
-            .iter()
-            .fold(sql.to_string(), |acc, (k, v)| acc.replace(k, v))
+    if found.is_empty() {
+        return valid.into_iter().collect();
     }
+    found
+        .into_iter()
+        .map(|(mut diagnostic, dialects)| {
+            if dialects.len() < analyzers.len()
+                && let Some(reason) = &mut diagnostic.reason
+            {
+                *reason = format!("{reason}, in {}", dialects.join(" and "));
+            }
+            diagnostic
+        })
+        .collect()
+}
+
+/// Byte offset of a 1-based line and char column in `text`, as reported by sqlparser.
+fn offset_of(text: &str, line: u64, col: u64) -> Option<usize> {
+    let mut curr = (1, 1);
+    for (offset, ch) in text.char_indices() {
+        if curr == (line, col) {
+            return Some(offset);
+        }
+        curr = if ch == '\n' {
+            (curr.0 + 1, 1)
+        } else {
+            (curr.0, curr.1 + 1)
+        };
+    }
+    (curr == (line, col)).then_some(text.len())
 }
 
 #[derive(Debug, Default)]
 struct SqlError {
     pub reason: String,
+    // 1-based line and column within the parsed SQL
+    pub location: Option<(u64, u64)>,
 }
 
 impl SqlError {
-    const fn new(reason: String) -> Self {
-        Self { reason }
+    const fn new(reason: String, location: Option<(u64, u64)>) -> Self {
+        Self { reason, location }
     }
 
     fn from_parser_error(e: ParserError) -> Self {
@@ -122,71 +324,235 @@ impl SqlError {
 
                 // if line information is present in msg
                 msg.find(line_marker).map_or_else(
-                    || Self::new("SQL parsing error with no position information".to_string()),
-                    {
-                        |line_start_idx| {
-                            let line_num_start = line_start_idx + line_marker.len();
-
-                            // if col information is also present
-                            msg[line_num_start..].find(col_marker).map_or_else(
-                                || {
-                                    Self::new(
-                                        "Malformed error message: missing column information"
-                                            .to_string(),
-                                    )
-                                },
-                                |_| {
-                                    let reason_msg = msg[..line_start_idx].to_string();
-                                    Self::new(reason_msg)
-                                },
-                            )
-                        }
+                    || Self::new(msg.clone(), None),
+                    |line_start_idx| {
+                        let reason_msg = msg[..line_start_idx].to_string();
+                        let position = &msg[line_start_idx + line_marker.len()..];
+
+                        // if col information is also present
+                        let location = position.split_once(col_marker).and_then(|(line, col)| {
+                            let col_end =
+                                col.find(|c: char| !c.is_ascii_digit()).unwrap_or(col.len());
+                            Some((line.parse().ok()?, col[..col_end].parse().ok()?))
+                        });
+                        Self::new(reason_msg, location)
                     },
                 )
             }
             ParserError::RecursionLimitExceeded => {
-                Self::new("Recursion Limit Exceeded".to_string())
+                Self::new("Recursion Limit Exceeded".to_string(), None)
             }
         }
     }
 }
 
-impl SqlDialect {
-    pub fn from_str(dialect_str: &str) -> Option<Self> {
-        let normalized = dialect_str.to_lowercase();
-
-        match normalized.as_str() {
-            "postgres" => Some(Self::PostgreSQL),
-            "mysql" => Some(Self::MySql),
-            "sqlite" => Some(Self::SQLite),
-            "mssql" => Some(Self::MsSql),
-            "bigquery" => Some(Self::BigQuery),
-            "snowflake" => Some(Self::Snowflake),
-            "redshift" => Some(Self::RedshiftSql),
-            "clickhouse" => Some(Self::ClickHouse),
-            "duckdb" => Some(Self::DuckDb),
-            "hive" => Some(Self::Hive),
-            "ansi" => Some(Self::Ansi),
-            "generic" | "default" => Some(Self::Generic),
-            _ => None,
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, RuleLevel};
+    use crate::schema::Catalog;
+    use finder::preanalysis::{LineCol, Range};
+
+    fn analyzer(mappings: &[(&str, &str)], placeholders: &[&str]) -> SqlAnalyzer {
+        SqlAnalyzer::new(
+            &SqlDialect::Generic,
+            mappings
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect(),
+            &placeholders
+                .iter()
+                .map(|p| (*p).to_string())
+                .collect::<Vec<_>>(),
+            Arc::new(RuleSet::new(&Config::default(), None)),
+        )
+    }
+
+    fn error_position(analyzer: &SqlAnalyzer, sql_string: &SqlString) -> LineCol {
+        let filled = analyzer.fill_placeholders(&sql_string.sql_content, &[]);
+        let err = Parser::parse_sql(&*analyzer.dialect, &filled).unwrap_err();
+        SqlAnalyzer::error_position(sql_string, &filled, &SqlError::from_parser_error(err))
+    }
+
+    fn sql_at(sql: &str, line: usize, col: usize) -> SqlString {
+        SqlString::new(
+            "query".to_string(),
+            sql.to_string(),
+            Range::verbatim(LineCol::new(line, col, 0), sql),
+        )
+    }
+
+    #[test]
+    fn parser_error_keeps_location() {
+        let err = SqlError::from_parser_error(ParserError::ParserError(
+            "Expected: end of statement, found: FORM at Line: 3, Column: 10".to_string(),
+        ));
+        assert_eq!(err.reason, "Expected: end of statement, found: FORM");
+        assert_eq!(err.location, Some((3, 10)));
+    }
+
+    #[test]
+    fn parser_error_without_location() {
+        let err = SqlError::from_parser_error(ParserError::ParserError(
+            "Expected: an expression, found: EOF".to_string(),
+        ));
+        assert_eq!(err.reason, "Expected: an expression, found: EOF");
+        assert_eq!(err.location, None);
+    }
+
+    #[test]
+    fn error_on_later_line_of_multiline_sql() {
+        let sql_string = sql_at("SELECT id\n  FROM users\n  WHERE id = 1 ORDER id", 5, 9);
+        let pos = error_position(&analyzer(&[], &[]), &sql_string);
+        assert_eq!((pos.line(), pos.col()), (7, 16));
+    }
+
+    #[test]
+    fn error_after_longer_placeholder() {
+        let sql_string = sql_at("SELECT * FROM t WHERE a = ? AND b = ? ORDER id", 1, 1);
+        let pos = error_position(&analyzer(&[], &["?"]), &sql_string);
+        assert_eq!((pos.line(), pos.col()), (1, 39));
+    }
+
+    #[test]
+    fn error_after_shorter_mapping() {
+        let sql_string = sql_at("SELECT a FROM t WHERE a NOTNULL ORDER a", 2, 5);
+        let pos = error_position(&analyzer(&[("NOTNULL", "IS NOT NULL")], &[]), &sql_string);
+        assert_eq!((pos.line(), pos.col()), (2, 37));
+    }
+
+    #[test]
+    fn error_at_end_of_input() {
+        let sql_string = sql_at("SELECT * FROM users WHERE", 1, 10);
+        let pos = error_position(&analyzer(&[], &[]), &sql_string);
+        assert_eq!((pos.line(), pos.col()), (1, 35));
+    }
+
+    #[test]
+    fn schema_issue_after_placeholder() {
+        let mut catalog = Catalog::default();
+        catalog.add_ddl(
+            "CREATE TABLE users (id INT, name TEXT)",
+            &sqlparser::dialect::GenericDialect {},
+            "schema.sql",
+        );
+        let analyzer = SqlAnalyzer {
+            rules: Arc::new(RuleSet::new(&Config::default(), Some(&Arc::new(catalog)))),
+            ..analyzer(&[], &["?"])
+        };
+
+        let sql_string = sql_at("SELECT name FROM users WHERE id = ? AND mail = ?", 3, 13);
+        let diagnostics = analyzer.analyze_sql_string(&sql_string, "app.py", false);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, Some(DiagnosticKind::UnknownColumn));
+        assert_eq!(
+            (diagnostics[0].location.line, diagnostics[0].location.col),
+            (3, 53)
+        );
+    }
+
+    #[test]
+    fn configured_rule_levels() {
+        let config = Config {
+            rules: HashMap::from([("SQ001".to_string(), RuleLevel::Warning)]),
+            ..Config::default()
+        };
+        let checked = SqlAnalyzer {
+            rules: Arc::new(RuleSet::new(&config, None)),
+            ..analyzer(&[], &[])
+        };
+        let diagnostics =
+            checked.analyze_sql_string(&sql_at("SELECT * FORM users", 1, 1), "app.py", false);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+
+        let config = Config {
+            rules: HashMap::from([("syntax-error".to_string(), RuleLevel::Off)]),
+            ..Config::default()
+        };
+        let checked = SqlAnalyzer {
+            rules: Arc::new(RuleSet::new(&config, None)),
+            ..analyzer(&[], &[])
+        };
+        let diagnostics =
+            checked.analyze_sql_string(&sql_at("SELECT * FORM users", 1, 1), "app.py", false);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, None);
     }
 
-    pub fn supported_dialects() -> Vec<&'static str> {
-        vec![
-            "postgres",
-            "mysql",
-            "sqlite",
-            "mssql",
-            "bigquery",
-            "snowflake",
-            "redshift",
-            "clickhouse",
-            "duckdb",
-            "hive",
-            "ansi",
-            "generic",
-            "default",
-        ]
+    #[test]
+    fn findings_of_some_variants_name_their_branches() {
+        let branch = |taken| finder::Branch {
+            condition: "active".to_string(),
+            line: 3,
+            taken,
+        };
+        let mut sql_string = sql_at("SELECT * FORM users WHERE active = 1", 1, 1);
+        sql_string.branches = vec![branch(true)];
+        let mut other = sql_at("SELECT * FORM users", 1, 1);
+        other.branches = vec![branch(false)];
+        sql_string.alternatives.push(other);
+
+        let diagnostics = analyzer(&[], &[]).analyze_sql_string(&sql_string, "app.py", false);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].reason.as_deref(),
+            Some("Expected: end of statement, found: FORM")
+        );
+
+        sql_string.sql_content = "SELECT * FROM users WHERE active = 1 ORDER id".to_string();
+        let diagnostics = analyzer(&[], &[]).analyze_sql_string(&sql_string, "app.py", false);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0].reason.as_deref(),
+            Some("Expected: end of statement, found: ORDER, when `active` is true on line 3")
+        );
+        assert_eq!(
+            diagnostics[1].reason.as_deref(),
+            Some("Expected: end of statement, found: FORM, when `active` is false on line 3")
+        );
+    }
+
+    #[test]
+    fn markers_of_the_param_styles_are_filled() {
+        let sql = "SELECT * FROM t WHERE a = %s AND b = %(b)s AND c LIKE '%s' AND d = ?";
+        let filled =
+            analyzer(&[], &[]).fill_placeholders(sql, &[ParamStyle::Format, ParamStyle::Pyformat]);
+        assert_eq!(
+            &*filled,
+            "SELECT * FROM t WHERE a = PLACEHOLDER AND b = PLACEHOLDER AND c LIKE '%s' AND d = ?"
+        );
+
+        let mut sql_string = sql_at("SELECT * FROM users WHERE id = %s ORDER id", 2, 1);
+        sql_string.param_styles = vec![ParamStyle::Format];
+        let diagnostics = analyzer(&[], &[]).analyze_sql_string(&sql_string, "app.py", false);
+        assert_eq!(
+            (diagnostics[0].location.line, diagnostics[0].location.col),
+            (2, 35)
+        );
+    }
+
+    #[test]
+    fn spliced_value_points_at_expression() {
+        let mut sql_string = sql_at("SELECT * FROM users WHERE id = PLACEHOLDER", 4, 13);
+        sql_string.interpolations.push(finder::Interpolation {
+            kind: finder::InterpolationKind::FString,
+            expr: "user_id".to_string(),
+            position: LineCol::new(4, 46, 0),
+            range: 31..42,
+        });
+
+        let diagnostics = analyzer(&[], &[]).analyze_sql_string(&sql_string, "app.py", false);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, Some(DiagnosticKind::SqlInjection));
+        assert_eq!(
+            (diagnostics[0].location.line, diagnostics[0].location.col),
+            (4, 46)
+        );
+        assert_eq!(
+            diagnostics[0].reason.as_deref(),
+            Some(
+                "`user_id` is spliced into the SQL by an f-string, pass it as a bound parameter instead"
+            )
+        );
     }
 }
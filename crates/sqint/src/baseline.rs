@@ -0,0 +1,315 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::report::Diagnostic;
+
+const BASELINE_VERSION: u32 = 1;
+
+/// Accepted findings, so only new ones are reported. Entries are identified by the file, the
+/// variable name, the rule and a hash of the SQL rather than a line number, so they survive edits.
+pub struct Baseline {
+    path: PathBuf,
+    // Files are stored relative to the directory of the baseline
+    dir: PathBuf,
+    entries: HashMap<Key, usize>,
+    // A baseline being created accepts everything found
+    creating: bool,
+    found: Mutex<Found>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Key {
+    pub file: String,
+    pub name: String,
+    pub code: String,
+    pub sql_hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    #[serde(flatten)]
+    key: Key,
+    count: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BaselineFile {
+    version: u32,
+    entries: Vec<Entry>,
+}
+
+#[derive(Default)]
+struct Found {
+    counts: HashMap<Key, usize>,
+    files: HashSet<String>,
+    suppressed: usize,
+}
+
+impl Baseline {
+    /// Loads the baseline at `path`, which is empty if there is no such file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut baseline = Self::new(path, false);
+        if !path.exists() {
+            return Ok(baseline);
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read baseline file: {e}"))?;
+        let file: BaselineFile = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse baseline file: {e}"))?;
+        if file.version != BASELINE_VERSION {
+            return Err(format!(
+                "Unsupported baseline version {}, recreate it with `sqint baseline create`",
+                file.version
+            ));
+        }
+        baseline.entries = file
+            .entries
+            .into_iter()
+            .map(|entry| (entry.key, entry.count))
+            .collect();
+        Ok(baseline)
+    }
+
+    /// Baseline to be written to `path`, taking every finding of the check.
+    pub fn create(path: &Path) -> Self {
+        Self::new(path, true)
+    }
+
+    fn new(path: &Path, creating: bool) -> Self {
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        Self {
+            path: path.to_path_buf(),
+            dir: std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf()),
+            entries: HashMap::new(),
+            creating,
+            found: Mutex::new(Found::default()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the diagnostics of a checked file that aren't in the baseline. A finding that
+    /// occurs more often than the baseline has it is reported for the extra occurrences.
+    pub fn filter(&self, file_path: &str, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        let file = self.relative(file_path);
+        let mut found = self
+            .found
+            .lock()
+            .expect("Checking threads don't panic holding the lock");
+        found.files.insert(file.clone());
+
+        let mut suppressed = 0;
+        let new: Vec<Diagnostic> = diagnostics
+            .into_iter()
+            .filter(|diagnostic| {
+                let Some(key) = Self::key(&file, diagnostic) else {
+                    return true;
+                };
+                let count = found.counts.entry(key.clone()).or_default();
+                *count += 1;
+                let accepted =
+                    self.creating || *count <= self.entries.get(&key).copied().unwrap_or(0);
+                suppressed += usize::from(accepted);
+                !accepted
+            })
+            .collect();
+        found.suppressed += suppressed;
+        new
+    }
+
+    /// Number of findings that were left out because they're in the baseline
+    pub fn suppressed(&self) -> usize {
+        self.found
+            .lock()
+            .expect("Checking threads don't panic holding the lock")
+            .suppressed
+    }
+
+    /// Entries that weren't found anymore in the checked files or whose file is gone, with how
+    /// many times they're missing
+    pub fn stale(&self) -> Vec<(Key, usize)> {
+        let found = self
+            .found
+            .lock()
+            .expect("Checking threads don't panic holding the lock");
+        let mut stale: Vec<(Key, usize)> = self
+            .entries
+            .iter()
+            .filter(|(key, _)| {
+                found.files.contains(&key.file) || !self.dir.join(&key.file).exists()
+            })
+            .filter_map(|(key, &count)| {
+                let missing = count.saturating_sub(found.counts.get(key).copied().unwrap_or(0));
+                (missing > 0).then(|| (key.clone(), missing))
+            })
+            .collect();
+        stale.sort();
+        stale
+    }
+
+    /// Writes everything found to the baseline file, returning the number of findings.
+    pub fn write(&self) -> Result<usize, String> {
+        let found = self
+            .found
+            .lock()
+            .expect("Checking threads don't panic holding the lock");
+        let mut entries: Vec<Entry> = found
+            .counts
+            .iter()
+            .map(|(key, &count)| Entry {
+                key: key.clone(),
+                count,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let file = BaselineFile {
+            version: BASELINE_VERSION,
+            entries,
+        };
+        let json = serde_json::to_string_pretty(&file)
+            .map_err(|e| format!("Failed to serialize baseline: {e}"))?;
+        std::fs::write(&self.path, json + "\n")
+            .map_err(|e| format!("Failed to write baseline file: {e}"))?;
+        Ok(found.counts.values().sum())
+    }
+
+    fn relative(&self, file_path: &str) -> String {
+        let path = Path::new(file_path);
+        path.strip_prefix(&self.dir)
+            .unwrap_or(path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Findings are identified by their rule and SQL, valid strings aren't part of the baseline
+    fn key(file: &str, diagnostic: &Diagnostic) -> Option<Key> {
+        let kind = diagnostic.kind?;
+        Some(Key {
+            file: file.to_string(),
+            name: diagnostic.location.name.clone(),
+            code: kind.code().to_string(),
+            sql_hash: sql_hash(&diagnostic.sql),
+        })
+    }
+}
+
+/// FNV-1a hash of the SQL with its whitespace normalized, so reformatting keeps the entry
+fn sql_hash(sql: &str) -> String {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+    let hash = sql
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .bytes()
+        .fold(FNV_OFFSET, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        });
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{DiagnosticKind, Location, Severity};
+
+    fn finding(name: &str, sql: &str) -> Diagnostic {
+        Diagnostic {
+            location: Location {
+                file: "project/app/db.py".to_string(),
+                cell: None,
+                line: 1,
+                col: 1,
+                name: name.to_string(),
+            },
+            sql: sql.to_string(),
+            source: format!("{sql:?}"),
+            dialect: "generic",
+            severity: Severity::Error,
+            kind: Some(DiagnosticKind::SyntaxError),
+            reason: Some("Expected: an SQL statement".to_string()),
+        }
+    }
+
+    fn names(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics
+            .iter()
+            .map(|d| d.location.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn sql_hash_ignores_whitespace() {
+        assert_eq!(sql_hash("SELEC 1"), sql_hash("  SELEC\n    1 "));
+        assert_ne!(sql_hash("SELEC 1"), sql_hash("SELEC 2"));
+        assert_eq!(sql_hash(""), "cbf29ce484222325");
+    }
+
+    #[test]
+    fn create_then_check() {
+        let dir = std::env::temp_dir().join(format!("sqint-baseline-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("app")).unwrap();
+        std::fs::write(dir.join("app/db.py"), "").unwrap();
+        let path = dir.join(crate::config::DEFAULT_BASELINE_NAME);
+        let file = dir.join("app/db.py").to_string_lossy().into_owned();
+
+        let created = Baseline::create(&path);
+        let mut valid = finding("valid_query", "SELECT 1");
+        valid.kind = None;
+        let found = vec![
+            finding("query", "SELEC 1"),
+            finding("query", "SELEC 1"),
+            finding("other_query", "SELEC 2"),
+            valid,
+        ];
+        assert_eq!(names(&created.filter(&file, found)), ["valid_query"]);
+        assert_eq!(created.write(), Ok(3));
+
+        let baseline = Baseline::load(&path).unwrap();
+        let found = vec![
+            finding("query", "SELEC\n  1"),
+            finding("query", "SELEC 1"),
+            finding("query", "SELEC 1"),
+            finding("new_query", "SELEC 3"),
+        ];
+        assert_eq!(
+            names(&baseline.filter(&file, found)),
+            ["query", "new_query"]
+        );
+        assert_eq!(baseline.suppressed(), 2);
+
+        let stale = baseline.stale();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(
+            (
+                stale[0].0.file.as_str(),
+                stale[0].0.name.as_str(),
+                stale[0].1
+            ),
+            ("app/db.py", "other_query", 1)
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(Baseline::load(&path).unwrap().stale().len(), 0);
+        assert!(Baseline::load(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unknown_version() {
+        let path = std::env::temp_dir().join(format!("sqint-baseline-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"version": 2, "entries": []}"#).unwrap();
+        let loaded = Baseline::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded.is_err_and(|e| e.contains("version 2")));
+    }
+}
@@ -1,7 +1,8 @@
-use crate::config::Config;
+use crate::config::{Config, OutputFormat};
+use crate::dialect::SqlDialect;
 
 use super::config::DEFAULT_CONFIG_NAME;
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use logging::LogLevel;
 use std::path::PathBuf;
 
@@ -42,35 +43,60 @@ pub struct Cli {
 }
 
 impl Cli {
+    /// Merges the file config with the CLI arguments, which take precedence.
     pub fn merge_with_config(&self, cfg: Config) -> Config {
+        let args = self.check_args();
         Config {
-            variable_contexts: cfg.variable_contexts,
+            variable_contexts: or_config(args.variable_contexts, cfg.variable_contexts),
             baseline_branch: self.baseline_branch.clone().unwrap_or(cfg.baseline_branch),
-            dialect: cfg.dialect,
+            dialect: if args.dialect.is_empty() {
+                cfg.dialect
+            } else {
+                args.dialect.iter().map(|d| d.name().to_string()).collect()
+            },
             dialect_mappings: cfg.dialect_mappings,
-            exclude_patterns: cfg.exclude_patterns,
-            file_patterns: cfg.file_patterns,
-            raw_sql_file_patterns: cfg.raw_sql_file_patterns,
-            function_contexts: cfg.function_contexts,
+            dialect_overrides: cfg.dialect_overrides,
+            exclude_patterns: or_config(args.exclude, cfg.exclude_patterns),
+            migration_patterns: cfg.migration_patterns,
+            file_patterns: or_config(args.file_patterns, cfg.file_patterns),
+            raw_sql_file_patterns: or_config(args.sql_patterns, cfg.raw_sql_file_patterns),
+            function_contexts: or_config(args.function_contexts, cfg.function_contexts),
+            presets: cfg.presets,
+            max_variants: cfg.max_variants,
             include_hidden_files: self.include_hidden_files || cfg.include_hidden_files,
             include_staged: self.include_staged || cfg.include_staged,
             incremental_mode: self.incremental || cfg.incremental_mode,
             loglevel: self.loglevel.unwrap_or(cfg.loglevel),
-            max_threads: self.check_args.max_threads.unwrap_or(cfg.max_threads),
-            parallel_processing: self
-                .check_args
-                .parallel_processing
-                .unwrap_or(cfg.parallel_processing),
+            output_format: args.output_format.unwrap_or(cfg.output_format),
+            max_threads: args.max_threads.unwrap_or(cfg.max_threads),
+            parallel_processing: args.parallel_processing.unwrap_or(cfg.parallel_processing),
             param_markers: cfg.param_markers,
             respect_git_exclude: cfg.respect_git_exclude,
-            respect_gitignore: self
-                .check_args
-                .respect_gitignore
-                .unwrap_or(cfg.respect_gitignore),
+            respect_gitignore: args.respect_gitignore.unwrap_or(cfg.respect_gitignore),
             respect_global_gitignore: cfg.respect_global_gitignore,
+            schema_files: or_config(args.schema_files, cfg.schema_files),
+            baseline_file: args.baseline_file.unwrap_or(cfg.baseline_file),
+            rules: cfg.rules,
         }
     }
 
+    /// Arguments of the check, those given to the `check` subcommand override the top-level ones.
+    pub fn check_args(&self) -> CheckArgs {
+        let mut args = match &self.command {
+            Some(
+                Commands::Check(args)
+                | Commands::Baseline(BaselineCommand::Create(args))
+                | Commands::Extract(ExtractArgs { check: args, .. })
+                | Commands::Portability(PortabilityArgs { check: args, .. }),
+            ) => args.or(&self.check_args),
+            _ => self.check_args.clone(),
+        };
+        if args.paths.is_empty() {
+            args.paths.push(PathBuf::from("."));
+        }
+        args
+    }
+
     /// Returns true if colored output should be used
     pub const fn use_colored_output(&self) -> bool {
         !self.plain
@@ -83,19 +109,40 @@ pub enum Commands {
     Check(CheckArgs),
     /// Initialize a new configuration file
     Init(InitArgs),
+    /// Manage the baseline of accepted findings
+    #[command(subcommand)]
+    Baseline(BaselineCommand),
+    /// Serve diagnostics to editors over the Language Server Protocol on stdio
+    Lsp,
+    /// List every SQL string found, without checking it
+    Extract(ExtractArgs),
+    /// Report which of the target dialects parse each SQL string found
+    Portability(PortabilityArgs),
 }
 
-#[derive(Args, Debug)]
+#[derive(Subcommand, Debug)]
+pub enum BaselineCommand {
+    /// Write the findings of a check to the baseline file, replacing its entries
+    Create(CheckArgs),
+}
+
+#[derive(Args, Debug, Clone)]
 pub struct CheckArgs {
-    #[arg(value_name = "PATH", default_value = ".")]
+    /// Files and directories to check (default: .)
+    #[arg(value_name = "PATH")]
     pub paths: Vec<PathBuf>,
 
     /// File patterns to exclude (e.g., "test_*.py")
     #[arg(long, value_delimiter = ',')]
     pub exclude: Vec<String>,
 
+    /// Dialects the SQL must parse in (e.g., "postgres,mysql,sqlite")
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub dialect: Vec<SqlDialect>,
+
+    /// Format of the reported results
     #[arg(long, value_enum)]
-    pub dialect: Option<SqlDialect>,
+    pub output_format: Option<OutputFormat>,
 
     #[arg(long)]
     pub parallel_processing: Option<bool>,
@@ -111,9 +158,9 @@ pub struct CheckArgs {
     #[arg(long, value_delimiter = ',')]
     pub file_patterns: Vec<String>,
 
-    /// File patterns that are raw sql to parse
+    /// File patterns that are raw sql to parse (e.g., "*.sql")
     #[arg(long, value_delimiter = ',')]
-    pub sql_patterns: Vec<PathBuf>,
+    pub sql_patterns: Vec<String>,
 
     /// Variable name patterns to look for (e.g., "*query*,*sql*")
     #[arg(long, value_delimiter = ',')]
@@ -122,6 +169,96 @@ pub struct CheckArgs {
     /// Function names with arguments to validate (e.g., "execute,execute_*,fetchall")
     #[arg(long, value_delimiter = ',')]
     pub function_contexts: Vec<String>,
+
+    /// DDL files describing the database schema (e.g., "db/schema.sql")
+    #[arg(long, value_delimiter = ',')]
+    pub schema_files: Vec<String>,
+
+    /// Baseline of accepted findings (default: .sqint-baseline.json)
+    #[arg(long)]
+    pub baseline_file: Option<String>,
+}
+
+impl CheckArgs {
+    /// These arguments, falling back to `base` for the ones that weren't given.
+    fn or(&self, base: &Self) -> Self {
+        let given =
+            |own: &Vec<String>, base: &Vec<String>| if own.is_empty() { base } else { own }.clone();
+        Self {
+            paths: if self.paths.is_empty() {
+                base.paths.clone()
+            } else {
+                self.paths.clone()
+            },
+            exclude: given(&self.exclude, &base.exclude),
+            dialect: if self.dialect.is_empty() {
+                base.dialect.clone()
+            } else {
+                self.dialect.clone()
+            },
+            output_format: self.output_format.or(base.output_format),
+            parallel_processing: self.parallel_processing.or(base.parallel_processing),
+            max_threads: self.max_threads.or(base.max_threads),
+            respect_gitignore: self.respect_gitignore.or(base.respect_gitignore),
+            file_patterns: given(&self.file_patterns, &base.file_patterns),
+            sql_patterns: given(&self.sql_patterns, &base.sql_patterns),
+            variable_contexts: given(&self.variable_contexts, &base.variable_contexts),
+            function_contexts: given(&self.function_contexts, &base.function_contexts),
+            schema_files: given(&self.schema_files, &base.schema_files),
+            baseline_file: self
+                .baseline_file
+                .clone()
+                .or_else(|| base.baseline_file.clone()),
+        }
+    }
+}
+
+// Lists given on the command line replace the ones from the config
+fn or_config(cli: Vec<String>, cfg: Vec<String>) -> Vec<String> {
+    if cli.is_empty() { cfg } else { cli }
+}
+
+#[derive(Args, Debug)]
+pub struct ExtractArgs {
+    #[command(flatten)]
+    pub check: CheckArgs,
+
+    /// Format the SQL strings are written to stdout in
+    #[arg(long, value_enum, default_value = "jsonl")]
+    pub format: ExtractFormat,
+
+    /// Write identical SQL once, with the number of times it was found
+    #[arg(long)]
+    pub dedupe: bool,
+
+    /// Include the variable_contexts or function_contexts pattern each SQL was found by
+    #[arg(long)]
+    pub with_context: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExtractFormat {
+    /// JSON Lines, one object per SQL string
+    Jsonl,
+    Csv,
+}
+
+#[derive(Args, Debug)]
+pub struct PortabilityArgs {
+    #[command(flatten)]
+    pub check: CheckArgs,
+
+    /// Format the report is written to stdout in
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: PortabilityFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortabilityFormat {
+    /// One line per SQL string
+    Text,
+    /// JSON Lines, one object per SQL string
+    Jsonl,
 }
 
 #[derive(Args, Debug)]
@@ -135,23 +272,6 @@ pub struct InitArgs {
     pub force: bool,
 }
 
-#[derive(clap::ValueEnum, Clone, Debug)]
-pub enum SqlDialect {
-    Generic,
-    PostgreSQL,
-    Oracle,
-    SQLite,
-    Ansi,
-    BigQuery,
-    ClickHouse,
-    DuckDb,
-    Hive,
-    MsSql,
-    MySql,
-    RedshiftSql,
-    Snowflake,
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -161,4 +281,180 @@ mod tests {
     fn verify_cli() {
         Cli::command().debug_assert()
     }
+
+    const FILE_CONFIG: &str = r#"
+dialect = "postgres"
+exclude_patterns = ["*_test.py"]
+file_patterns = ["*.py"]
+raw_sql_file_patterns = ["*.ddl"]
+variable_contexts = ["*query*"]
+function_contexts = ["execute"]
+max_threads = 4
+output_format = "json"
+"#;
+
+    fn effective(args: &[&str], file: Option<&str>) -> Config {
+        let cli = Cli::try_parse_from(std::iter::once("sqint").chain(args.iter().copied()))
+            .expect("Valid arguments");
+        let config = file.map_or_else(Config::default, |file| {
+            Config::from_toml(file).expect("Valid config")
+        });
+        cli.merge_with_config(config)
+    }
+
+    #[test]
+    fn defaults_without_file_or_flags() {
+        let config = effective(&[], None);
+        let default = Config::default();
+        assert_eq!(config.dialect, default.dialect);
+        assert_eq!(config.exclude_patterns, default.exclude_patterns);
+        assert_eq!(config.file_patterns, default.file_patterns);
+        assert_eq!(config.raw_sql_file_patterns, default.raw_sql_file_patterns);
+        assert_eq!(config.variable_contexts, default.variable_contexts);
+        assert_eq!(config.function_contexts, default.function_contexts);
+        assert_eq!(config.max_threads, default.max_threads);
+        assert_eq!(config.output_format, default.output_format);
+    }
+
+    #[test]
+    fn dialects_given_as_lists() {
+        let file = r#"dialect = ["postgres", "mysql"]"#;
+        assert_eq!(effective(&[], Some(file)).dialect, ["postgres", "mysql"]);
+        assert_eq!(
+            effective(&["--dialect", "sqlite,duckdb"], Some(file)).dialect,
+            ["sqlite", "duckdb"]
+        );
+    }
+
+    #[test]
+    fn file_overrides_defaults() {
+        let config = effective(&[], Some(FILE_CONFIG));
+        assert_eq!(config.dialect, ["postgres"]);
+        assert_eq!(config.exclude_patterns, ["*_test.py"]);
+        assert_eq!(config.file_patterns, ["*.py"]);
+        assert_eq!(config.raw_sql_file_patterns, ["*.ddl"]);
+        assert_eq!(config.variable_contexts, ["*query*"]);
+        assert_eq!(config.function_contexts, ["execute"]);
+        assert_eq!(config.max_threads, 4);
+        assert_eq!(config.output_format, OutputFormat::Json);
+    }
+
+    #[test]
+    fn flags_override_file() {
+        let config = effective(
+            &[
+                "--dialect",
+                "mysql",
+                "--exclude",
+                "legacy/*,*_old.py",
+                "--file-patterns",
+                "*.pyi",
+                "--sql-patterns",
+                "*.sql",
+                "--variable-contexts",
+                "*stmt*",
+                "--function-contexts",
+                "fetch*,run",
+                "--max-threads",
+                "2",
+                "--output-format",
+                "sarif",
+            ],
+            Some(FILE_CONFIG),
+        );
+        assert_eq!(config.dialect, ["mysql"]);
+        assert_eq!(config.exclude_patterns, ["legacy/*", "*_old.py"]);
+        assert_eq!(config.file_patterns, ["*.pyi"]);
+        assert_eq!(config.raw_sql_file_patterns, ["*.sql"]);
+        assert_eq!(config.variable_contexts, ["*stmt*"]);
+        assert_eq!(config.function_contexts, ["fetch*", "run"]);
+        assert_eq!(config.max_threads, 2);
+        assert_eq!(config.output_format, OutputFormat::Sarif);
+    }
+
+    #[test]
+    fn flags_only_override_what_they_set() {
+        let config = effective(&["--dialect", "sqlite"], Some(FILE_CONFIG));
+        assert_eq!(config.dialect, ["sqlite"]);
+        assert_eq!(config.exclude_patterns, ["*_test.py"]);
+        assert_eq!(config.max_threads, 4);
+    }
+
+    #[test]
+    fn check_subcommand_flags() {
+        let config = effective(
+            &[
+                "check",
+                "--dialect",
+                "bigquery",
+                "--exclude",
+                "gen/*",
+                "src",
+            ],
+            Some(FILE_CONFIG),
+        );
+        assert_eq!(config.dialect, ["bigquery"]);
+        assert_eq!(config.exclude_patterns, ["gen/*"]);
+        assert_eq!(config.function_contexts, ["execute"]);
+
+        let cli = Cli::try_parse_from(["sqint", "check", "src"]).unwrap();
+        assert_eq!(cli.check_args().paths, [PathBuf::from("src")]);
+    }
+
+    #[test]
+    fn subcommand_paths_only_fall_back_when_not_given() {
+        let paths = |args: &[&str]| {
+            let args = std::iter::once("sqint").chain(args.iter().copied());
+            Cli::try_parse_from(args).unwrap().check_args().paths
+        };
+        assert_eq!(paths(&[]), [PathBuf::from(".")]);
+        assert_eq!(paths(&["check"]), [PathBuf::from(".")]);
+        assert_eq!(paths(&["check", "."]), [PathBuf::from(".")]);
+        assert_eq!(
+            paths(&["check", "src", "."]),
+            ["src", "."].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn check_subcommand_overrides_top_level() {
+        let config = effective(
+            &[
+                "--dialect",
+                "postgres",
+                "--max-threads",
+                "8",
+                "check",
+                "--dialect",
+                "duckdb",
+            ],
+            None,
+        );
+        assert_eq!(config.dialect, ["duckdb"]);
+        assert_eq!(config.max_threads, 8);
+    }
+
+    #[test]
+    fn config_flag_is_loaded() {
+        let path = std::env::temp_dir().join(format!("sqint-cli-{}.toml", std::process::id()));
+        std::fs::write(&path, FILE_CONFIG).unwrap();
+        let cli = Cli::try_parse_from([
+            "sqint".as_ref(),
+            "--config".as_ref(),
+            path.as_os_str(),
+            "--max-threads".as_ref(),
+            "1".as_ref(),
+        ])
+        .unwrap();
+
+        let configs = crate::files::ConfigTree::new(&cli);
+        std::fs::remove_file(&path).unwrap();
+        let (_, config) = configs
+            .expect("Config file exists")
+            .resolve(&std::env::temp_dir());
+        assert_eq!(config.dialect, ["postgres"]);
+        assert_eq!(config.max_threads, 1);
+
+        assert!(crate::files::ConfigTree::new(&cli).is_none());
+    }
 }
@@ -1,11 +1,12 @@
 use logging::LogLevel;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
 pub const DEFAULT_CONFIG_NAME: &str = "sqint.toml";
 pub const PYPROJECT_CONFIG_NAME: &str = "pyproject.toml";
+pub const DEFAULT_BASELINE_NAME: &str = ".sqint-baseline.json";
 pub const DEFAULT_CONFIG: &str = include_str!("./assets/default.toml");
 
 #[allow(clippy::struct_excessive_bools)]
@@ -15,11 +16,14 @@ pub struct Config {
     // Detection Settings
     pub variable_contexts: Vec<String>,
     pub function_contexts: Vec<String>,
+    pub presets: Vec<String>,
+    pub max_variants: usize,
 
     // File Processing
     pub file_patterns: Vec<String>,
     pub raw_sql_file_patterns: Vec<String>,
     pub exclude_patterns: Vec<String>,
+    pub migration_patterns: Vec<String>,
     pub respect_gitignore: bool,
     pub respect_global_gitignore: bool,
     pub respect_git_exclude: bool,
@@ -36,11 +40,60 @@ pub struct Config {
 
     // Output Settings
     pub loglevel: LogLevel,
+    pub output_format: OutputFormat,
 
     // SQL Parsing Settings
-    pub dialect: String,
+    /// Dialects SQL must parse in, a single one or every database it must run on
+    #[serde(deserialize_with = "one_or_many")]
+    pub dialect: Vec<String>,
+    pub dialect_overrides: HashMap<String, String>,
     pub param_markers: Vec<String>,
     pub dialect_mappings: HashMap<String, String>,
+
+    // Schema Settings
+    pub schema_files: Vec<String>,
+
+    // Baseline of accepted findings
+    pub baseline_file: String,
+
+    // Rule Settings, keyed by rule code or name
+    pub rules: HashMap<String, RuleLevel>,
+}
+
+/// Format of the results reported by `sqint check`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human readable log lines
+    #[default]
+    Text,
+    /// One JSON object per checked SQL string and a final summary object (JSON Lines)
+    Json,
+    /// SARIF 2.1.0 log of all findings, for code scanning tools
+    Sarif,
+}
+
+/// Level a rule is reported at, set per rule in the `[rules]` table
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Off,
+    Warning,
+    Error,
+}
+
+/// Reads an option given as a single value or as a list of them
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
 }
 
 /// Wrapper for pyproject.toml structure
@@ -66,6 +119,8 @@ impl Default for Config {
                 "*stmt*".to_string(),
             ],
             function_contexts: vec![],
+            presets: finder::preset_names(),
+            max_variants: finder::DEFAULT_MAX_VARIANTS,
 
             // File Processing
             file_patterns: vec![
@@ -75,6 +130,11 @@ impl Default for Config {
             ],
             raw_sql_file_patterns: vec!["*.sql".to_string()],
             exclude_patterns: vec![],
+            migration_patterns: vec![
+                "*/migrations/*".to_string(),
+                "*/migration/*".to_string(),
+                "*/alembic/*".to_string(),
+            ],
             respect_gitignore: true,
             respect_global_gitignore: false,
             respect_git_exclude: true,
@@ -91,9 +151,11 @@ impl Default for Config {
 
             // Output Settings
             loglevel: LogLevel::default(),
+            output_format: OutputFormat::default(),
 
             // SQL Parsing Settings
-            dialect: "generic".to_string(),
+            dialect: vec![crate::dialect::AUTO.to_string()],
+            dialect_overrides: HashMap::new(),
             param_markers: vec!["?".to_string()],
             dialect_mappings: {
                 let mut map = HashMap::new();
@@ -101,6 +163,15 @@ impl Default for Config {
                 map.insert("ISNULL".to_string(), "IS NULL".to_string());
                 map
             },
+
+            // Schema Settings
+            schema_files: vec![],
+
+            // Baseline Settings
+            baseline_file: DEFAULT_BASELINE_NAME.to_string(),
+
+            // Rule Settings
+            rules: HashMap::new(),
         }
     }
 }
@@ -137,67 +208,80 @@ impl Config {
         }
     }
 
-    /// Merge this config with another, preferring values from the other config
-    pub fn merge_with(&mut self, other: Self) {
-        // Detection Settings
-        if !other.variable_contexts.is_empty() {
-            self.variable_contexts = other.variable_contexts;
-        }
-        if !other.function_contexts.is_empty() {
-            self.function_contexts = other.function_contexts;
+    /// Builds the config set by the given files, each overriding the settings of the ones before it.
+    /// Relative `schema_files` and `baseline_file` are resolved against the directory of the file
+    /// that sets them.
+    pub fn from_files<P: AsRef<Path>>(paths: &[P]) -> Result<Self, ConfigError> {
+        let mut merged = toml::Table::new();
+        for path in paths {
+            let path = path.as_ref();
+            let mut table = Self::read_table(path)?.ok_or_else(|| {
+                ConfigError::Parse(format!(
+                    "No [tool.sqint] section found in {}",
+                    path.display()
+                ))
+            })?;
+            if let Some(dir) = path.parent() {
+                resolve_paths(&mut table, dir);
+            }
+            merge_tables(&mut merged, table);
         }
+        toml::Value::Table(merged)
+            .try_into()
+            .map_err(|e| ConfigError::Parse(format!("Failed to parse TOML: {e}")))
+    }
 
-        // File Processing
-        if !other.file_patterns.is_empty() {
-            self.file_patterns = other.file_patterns;
-        }
-        if !other.raw_sql_file_patterns.is_empty() {
-            self.raw_sql_file_patterns = other.raw_sql_file_patterns;
-        }
-        if !other.exclude_patterns.is_empty() {
-            self.exclude_patterns = other.exclude_patterns;
-        }
-        if other.respect_gitignore {
-            self.respect_gitignore = other.respect_gitignore;
-        }
-        if other.respect_global_gitignore {
-            self.respect_global_gitignore = other.respect_global_gitignore;
-        }
-        if other.respect_git_exclude {
-            self.respect_git_exclude = other.respect_git_exclude;
-        }
-        if other.include_hidden_files {
-            self.include_hidden_files = other.include_hidden_files;
-        }
+    /// Reads the settings of a config file without applying defaults.
+    /// Returns `None` for a pyproject.toml without a `[tool.sqint]` section.
+    pub fn read_table(path: &Path) -> Result<Option<toml::Table>, ConfigError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| ConfigError::Io(format!("Failed to read config file: {e}")))?;
+        let mut table: toml::Table = toml::from_str(&content)
+            .map_err(|e| ConfigError::Parse(format!("Failed to parse TOML: {e}")))?;
 
-        // Threading Settings
-        if other.parallel_processing {
-            self.parallel_processing = other.parallel_processing;
+        if path.file_name().and_then(|name| name.to_str()) != Some(PYPROJECT_CONFIG_NAME) {
+            return Ok(Some(table));
         }
-        if other.max_threads != 0 {
-            self.max_threads = other.max_threads;
+        match table.remove("tool") {
+            Some(toml::Value::Table(mut tool)) => match tool.remove("sqint") {
+                Some(toml::Value::Table(sqint)) => Ok(Some(sqint)),
+                Some(_) => Err(ConfigError::Parse(
+                    "[tool.sqint] in pyproject.toml must be a table".to_string(),
+                )),
+                None => Ok(None),
+            },
+            _ => Ok(None),
         }
+    }
+}
 
-        self.loglevel = other.loglevel;
-
-        // Incremental Mode
-        if other.incremental_mode {
-            self.incremental_mode = other.incremental_mode;
-        }
-        if other.baseline_branch != "main" {
-            self.baseline_branch = other.baseline_branch;
-        }
-        if other.include_staged {
-            self.include_staged = other.include_staged;
+/// Overrides the settings of `base` with those of `other`, tables like `[rules]` are merged per key.
+fn merge_tables(base: &mut toml::Table, other: toml::Table) {
+    for (key, value) in other {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(other)) => {
+                merge_tables(base, other)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
         }
+    }
+}
 
-        // SQL Parsing Settings
-        if !other.param_markers.is_empty() {
-            self.param_markers = other.param_markers;
-        }
-        if !other.dialect_mappings.is_empty() {
-            self.dialect_mappings = other.dialect_mappings;
+fn resolve_paths(table: &mut toml::Table, dir: &Path) {
+    let resolve = |value: &mut toml::Value| {
+        if let toml::Value::String(path) = value
+            && Path::new(path.as_str()).is_relative()
+        {
+            *path = dir.join(&*path).to_string_lossy().into_owned();
         }
+    };
+    if let Some(toml::Value::Array(files)) = table.get_mut("schema_files") {
+        files.iter_mut().for_each(resolve);
+    }
+    if let Some(file) = table.get_mut("baseline_file") {
+        resolve(file);
     }
 }
 
@@ -274,4 +358,18 @@ parallel_processing = false
         assert_eq!(config.file_patterns, vec!["*.py"]);
         assert!(!config.parallel_processing);
     }
+
+    #[test]
+    fn test_rules_table_parsing() {
+        let pyproject_content = r#"
+[tool.sqint.rules]
+SQ001 = "warning"
+unknown-column = "off"
+"#;
+
+        let config = Config::from_pyproject_toml(pyproject_content).unwrap();
+        assert_eq!(config.rules["SQ001"], RuleLevel::Warning);
+        assert_eq!(config.rules["unknown-column"], RuleLevel::Off);
+        assert!(Config::from_toml("[rules]\nSQ001 = \"loud\"").is_err());
+    }
 }
@@ -0,0 +1,193 @@
+use clap::ValueEnum;
+use sqlparser::dialect::{
+    AnsiDialect, BigQueryDialect, ClickHouseDialect, Dialect, DuckDbDialect, GenericDialect,
+    HiveDialect, MsSqlDialect, MySqlDialect, PostgreSqlDialect, RedshiftSqlDialect, SQLiteDialect,
+    SnowflakeDialect,
+};
+
+/// Value of the `dialect` option that tells the dialect of each file by the driver it imports
+pub const AUTO: &str = "auto";
+
+/// Whether the `dialect` option leaves the dialect of each file to the drivers it imports
+pub fn is_auto(names: &[String]) -> bool {
+    matches!(names, [name] if name.eq_ignore_ascii_case(AUTO))
+}
+
+/// SQL dialect checked against, as named in the `dialect` option and `--dialect`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SqlDialect {
+    #[value(alias = "default")]
+    Generic,
+    #[value(name = "postgres")]
+    PostgreSQL,
+    Oracle,
+    #[value(name = "sqlite")]
+    SQLite,
+    Ansi,
+    #[value(name = "bigquery")]
+    BigQuery,
+    #[value(name = "clickhouse")]
+    ClickHouse,
+    #[value(name = "duckdb")]
+    DuckDb,
+    Hive,
+    #[value(name = "mssql")]
+    MsSql,
+    #[value(name = "mysql")]
+    MySql,
+    #[value(name = "redshift")]
+    RedshiftSql,
+    Snowflake,
+}
+
+impl SqlDialect {
+    pub fn from_str(dialect_str: &str) -> Option<Self> {
+        <Self as ValueEnum>::from_str(dialect_str, true).ok()
+    }
+
+    /// Dialects named by the `dialect` option, the generic one for `auto` where no file tells.
+    /// `None` if one of them is unknown.
+    pub fn from_config(names: &[String]) -> Option<Vec<Self>> {
+        if is_auto(names) {
+            return Some(vec![Self::Generic]);
+        }
+        if names.is_empty() {
+            return None;
+        }
+        names.iter().map(|name| Self::from_str(name)).collect()
+    }
+
+    /// The sqlparser dialect used to parse SQL written in this dialect
+    pub fn parser_dialect(self) -> Box<dyn Dialect + Send + Sync> {
+        match self {
+            Self::Generic => Box::new(GenericDialect {}),
+            Self::PostgreSQL => Box::new(PostgreSqlDialect {}),
+            Self::Oracle => Box::new(OracleDialect {}),
+            Self::SQLite => Box::new(SQLiteDialect {}),
+            Self::Ansi => Box::new(AnsiDialect {}),
+            Self::BigQuery => Box::new(BigQueryDialect {}),
+            Self::ClickHouse => Box::new(ClickHouseDialect {}),
+            Self::DuckDb => Box::new(DuckDbDialect {}),
+            Self::Hive => Box::new(HiveDialect {}),
+            Self::MsSql => Box::new(MsSqlDialect {}),
+            Self::MySql => Box::new(MySqlDialect {}),
+            Self::RedshiftSql => Box::new(RedshiftSqlDialect {}),
+            Self::Snowflake => Box::new(SnowflakeDialect {}),
+        }
+    }
+
+    /// Canonical name of the dialect, as accepted in the configuration
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Generic => "generic",
+            Self::PostgreSQL => "postgres",
+            Self::Oracle => "oracle",
+            Self::SQLite => "sqlite",
+            Self::Ansi => "ansi",
+            Self::BigQuery => "bigquery",
+            Self::ClickHouse => "clickhouse",
+            Self::DuckDb => "duckdb",
+            Self::Hive => "hive",
+            Self::MsSql => "mssql",
+            Self::MySql => "mysql",
+            Self::RedshiftSql => "redshift",
+            Self::Snowflake => "snowflake",
+        }
+    }
+
+    pub fn all() -> &'static [Self] {
+        Self::value_variants()
+    }
+
+    /// Names accepted for the dialects, the alias of the generic one included
+    pub fn supported_dialects() -> Vec<&'static str> {
+        Self::value_variants()
+            .iter()
+            .map(|dialect| dialect.name())
+            .chain(["default"])
+            .collect()
+    }
+}
+
+/// Oracle SQL: `CONNECT BY` hierarchies, `(+)` outer joins, `MATCH_RECOGNIZE`, `=>` named
+/// arguments and identifiers with `$` and `#`, quoted only in `"`. `:bind` variables, `ROWNUM`,
+/// `DUAL` and `MERGE` parse as they do in any dialect.
+#[derive(Debug)]
+pub struct OracleDialect {}
+
+impl Dialect for OracleDialect {
+    fn is_delimited_identifier_start(&self, ch: char) -> bool {
+        ch == '"'
+    }
+
+    fn is_identifier_start(&self, ch: char) -> bool {
+        ch.is_alphabetic()
+    }
+
+    fn is_identifier_part(&self, ch: char) -> bool {
+        ch.is_alphanumeric() || matches!(ch, '_' | '$' | '#')
+    }
+
+    fn supports_connect_by(&self) -> bool {
+        true
+    }
+
+    fn supports_outer_join_operator(&self) -> bool {
+        true
+    }
+
+    fn supports_match_recognize(&self) -> bool {
+        true
+    }
+
+    fn supports_group_by_expr(&self) -> bool {
+        true
+    }
+
+    fn supports_named_fn_args_with_rarrow_operator(&self) -> bool {
+        true
+    }
+
+    fn supports_window_function_null_treatment_arg(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::parser::Parser;
+
+    #[test]
+    fn names_parse_back_to_their_dialect() {
+        for dialect in SqlDialect::value_variants() {
+            assert_eq!(SqlDialect::from_str(dialect.name()), Some(*dialect));
+        }
+        assert_eq!(SqlDialect::from_str("Default"), Some(SqlDialect::Generic));
+        assert_eq!(SqlDialect::from_str("ORACLE"), Some(SqlDialect::Oracle));
+        assert_eq!(SqlDialect::from_str("plsql"), None);
+    }
+
+    #[test]
+    fn oracle_specifics_parse() {
+        let dialect = SqlDialect::Oracle.parser_dialect();
+        for sql in [
+            "SELECT * FROM users WHERE ROWNUM <= 10",
+            "SELECT SYSDATE FROM DUAL",
+            "SELECT emp_id, manager_id FROM emp START WITH manager_id IS NULL \
+             CONNECT BY PRIOR emp_id = manager_id",
+            "SELECT e.name, d.name FROM emp e, dept d WHERE e.dept_id = d.id(+)",
+            "SELECT * FROM users WHERE id = :user_id",
+            "SELECT v$session.sid, t#1 FROM v$session, t#1",
+            "MERGE INTO t USING s ON (t.id = s.id) \
+             WHEN MATCHED THEN UPDATE SET t.a = s.a \
+             WHEN NOT MATCHED THEN INSERT (id, a) VALUES (s.id, s.a)",
+        ] {
+            assert!(
+                Parser::parse_sql(&*dialect, sql).is_ok(),
+                "{sql} doesn't parse"
+            );
+        }
+        assert!(Parser::parse_sql(&*dialect, "SELECT * FROM `users`").is_err());
+    }
+}
@@ -0,0 +1,215 @@
+use finder::{Context, SqlExtract};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::cli::{ExtractArgs, ExtractFormat};
+use crate::report::Location;
+
+/// SQL string written by `sqint extract`
+#[derive(Debug, Serialize)]
+struct Record {
+    #[serde(flatten)]
+    location: Location,
+    /// SQL with runtime values shown as the Python expressions they come from
+    sql: String,
+    /// Whether a variable or a function pattern found the SQL, with `--with-context`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    context: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pattern: Option<String>,
+    /// Times the SQL was found, with `--dedupe`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<usize>,
+}
+
+/// Writes the SQL strings of the files to stdout, returning how many were written.
+pub fn write(extracts: &[SqlExtract], args: &ExtractArgs) -> io::Result<usize> {
+    write_to(&mut io::stdout().lock(), extracts, args)
+}
+
+fn write_to(
+    out: &mut impl Write,
+    extracts: &[SqlExtract],
+    args: &ExtractArgs,
+) -> io::Result<usize> {
+    let mut records = records(extracts, args.with_context);
+    if args.dedupe {
+        records = dedupe(records);
+    }
+
+    match args.format {
+        ExtractFormat::Jsonl => {
+            for record in &records {
+                serde_json::to_writer(&mut *out, record)?;
+                writeln!(out)?;
+            }
+        }
+        ExtractFormat::Csv => {
+            let mut header = vec!["file", "cell", "line", "col", "name", "sql"];
+            if args.with_context {
+                header.extend(["context", "pattern"]);
+            }
+            if args.dedupe {
+                header.push("count");
+            }
+            writeln!(out, "{}", header.join(","))?;
+            for record in &records {
+                let mut row = vec![
+                    csv_field(&record.location.file),
+                    record
+                        .location
+                        .cell
+                        .map(|cell| cell.to_string())
+                        .unwrap_or_default(),
+                    record.location.line.to_string(),
+                    record.location.col.to_string(),
+                    csv_field(&record.location.name),
+                    csv_field(&record.sql),
+                ];
+                if args.with_context {
+                    row.push(record.context.unwrap_or_default().to_string());
+                    row.push(csv_field(record.pattern.as_deref().unwrap_or_default()));
+                }
+                if let Some(count) = record.count {
+                    row.push(count.to_string());
+                }
+                writeln!(out, "{}", row.join(","))?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(records.len())
+}
+
+fn records(extracts: &[SqlExtract], with_context: bool) -> Vec<Record> {
+    extracts
+        .iter()
+        .flat_map(|extract| {
+            extract
+                .strings
+                .iter()
+                .flat_map(|s| s.variants())
+                .map(|sql_string| {
+                    let (context, pattern) = match &sql_string.context {
+                        Some(Context::Variable(pattern)) if with_context => {
+                            (Some("variable"), Some(pattern.clone()))
+                        }
+                        Some(Context::Function(pattern)) if with_context => {
+                            (Some("function"), Some(pattern.clone()))
+                        }
+                        _ => (None, None),
+                    };
+                    Record {
+                        location: Location::of(extract, sql_string),
+                        sql: sql_string.resolved_sql().trim().to_string(),
+                        context,
+                        pattern,
+                        count: None,
+                    }
+                })
+        })
+        .collect()
+}
+
+/// Keeps the first of the records with the same SQL, ignoring whitespace, counting the others
+fn dedupe(records: Vec<Record>) -> Vec<Record> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut unique: Vec<Record> = Vec::new();
+    for mut record in records {
+        let key = record.sql.split_whitespace().collect::<Vec<_>>().join(" ");
+        if let Some(&index) = seen.get(&key) {
+            *unique[index].count.get_or_insert(1) += 1;
+        } else {
+            seen.insert(key, unique.len());
+            record.count = Some(1);
+            unique.push(record);
+        }
+    }
+    unique
+}
+
+/// Quotes a field if it has a separator, quote or line break, doubling its quotes
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::sync::Arc;
+
+    fn extract(args: &[&str], source: &str) -> String {
+        let cli = crate::Cli::parse_from(["sqint", "extract"].iter().chain(args));
+        let Some(crate::cli::Commands::Extract(args)) = &cli.command else {
+            unreachable!("Parsed an extract command");
+        };
+        let finder_config = finder::FinderConfig::new(
+            &["*query*".to_string()],
+            &["*.execute".to_string(), "execute_*".to_string()],
+        );
+        let extract = finder::SqlFinder::new(Arc::new(finder_config))
+            .analyze_source("app/db.py", source, false)
+            .unwrap();
+
+        let mut out = Vec::new();
+        write_to(&mut out, &[extract], args).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    const SOURCE: &str = r#"
+query = "SELECT id FROM users"
+cursor.execute(f"SELECT name, email FROM users WHERE id = {user_id}")
+other_query = """SELECT id
+    FROM users"""
+"#;
+
+    #[test]
+    fn json_lines() {
+        let out = extract(&["--with-context"], SOURCE);
+        let records: Vec<serde_json::Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["file"], "app/db.py");
+        assert_eq!(records[0]["line"], 2);
+        assert_eq!(records[0]["name"], "query");
+        assert_eq!(records[0]["context"], "variable");
+        assert_eq!(records[0]["pattern"], "*query*");
+        assert_eq!(records[1]["name"], "cursor.execute");
+        assert_eq!(
+            records[1]["sql"],
+            "SELECT name, email FROM users WHERE id = {user_id}"
+        );
+        assert_eq!(records[1]["context"], "function");
+        assert_eq!(records[1]["pattern"], "*.execute");
+        assert!(records[0].get("count").is_none());
+    }
+
+    #[test]
+    fn csv_with_dedupe() {
+        let out = extract(&["--format", "csv", "--dedupe"], SOURCE);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "file,cell,line,col,name,sql,count");
+        assert_eq!(lines[1], "app/db.py,,2,9,query,SELECT id FROM users,2");
+        assert_eq!(
+            lines[2],
+            "app/db.py,,3,1,cursor.execute,\"SELECT name, email FROM users WHERE id = {user_id}\",1"
+        );
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn csv_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+}
@@ -1,8 +1,12 @@
+use crate::cli::Cli;
 use crate::config::{Config, DEFAULT_CONFIG_NAME, PYPROJECT_CONFIG_NAME};
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use ignore::WalkBuilder;
 use logging::{always_log, error, warn};
-use std::{path::PathBuf, process::Command};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
 
 /// Returns only files that have changed compared to the baseline branch
 pub fn filter_incremental_files(files: &[String], cfg: &Config) -> Vec<String> {
@@ -91,24 +95,123 @@ fn get_changed_files(base_branch: &str, incl_staged: bool) -> Result<Vec<String>
         .collect())
 }
 
-pub fn load_config() -> Config {
-    let cwd = std::env::current_dir().expect("Unable to read current working directory");
-    let sqint_conf = cwd.join(DEFAULT_CONFIG_NAME);
-    let pyproject_conf = cwd.join(PYPROJECT_CONFIG_NAME);
-    let mut config = Config::default();
-
-    Config::from_file(&sqint_conf)
-        .or_else(|_| Config::from_file(&pyproject_conf))
-        .map_or_else(
-            |e| {
-                always_log!(
-                    "Using default configuration. Couldn't load config from {}: '{e}'.",
-                    sqint_conf.display(),
-                );
+/// Resolves the config governing each directory. That's the config files found in the directory
+/// and its parents up to the repository root, the outermost first and each overriding the ones
+/// above it, with the CLI arguments on top. A file given with `--config` governs every directory.
+pub struct ConfigTree<'a> {
+    cli: &'a Cli,
+    explicit: Option<Arc<Config>>,
+    // Config file of each directory looked at, if it has a usable one
+    found: HashMap<PathBuf, Option<PathBuf>>,
+    dirs: HashMap<PathBuf, usize>,
+    chains: HashMap<Vec<PathBuf>, usize>,
+    configs: Vec<Arc<Config>>,
+}
+
+impl<'a> ConfigTree<'a> {
+    /// Returns `None` if the file given with `--config` can't be loaded.
+    pub fn new(cli: &'a Cli) -> Option<Self> {
+        let explicit = match cli.config.as_deref() {
+            Some(path) => match Config::from_files(&[path]) {
+                Ok(config) => Some(Arc::new(cli.merge_with_config(config))),
+                Err(e) => {
+                    always_log!("Couldn't load config from {}: '{e}'.", path.display());
+                    return None;
+                }
             },
-            |file_config| config.merge_with(file_config),
-        );
-    config
+            None => None,
+        };
+        Some(Self {
+            cli,
+            explicit,
+            found: HashMap::new(),
+            dirs: HashMap::new(),
+            chains: HashMap::new(),
+            configs: Vec::new(),
+        })
+    }
+
+    /// Config of the working directory, which sets how files are collected and results reported.
+    pub fn root(&mut self) -> Arc<Config> {
+        let cwd = std::env::current_dir().expect("Unable to read current working directory");
+        self.resolve(&cwd).1
+    }
+
+    /// Notes when no config file governs the working directory, once logging is set up by the
+    /// root config so it isn't written among results on stdout.
+    pub fn log_defaults(&mut self) {
+        let cwd = std::env::current_dir().expect("Unable to read current working directory");
+        if self.explicit.is_none() && self.chain(&cwd).is_empty() {
+            always_log!(
+                "Using default configuration. No {DEFAULT_CONFIG_NAME} or {PYPROJECT_CONFIG_NAME} with a [tool.sqint] section found in {} or its parents.",
+                cwd.display()
+            );
+        }
+    }
+
+    /// Config governing `dir`, with an id that's shared by all directories with the same config.
+    pub fn resolve(&mut self, dir: &Path) -> (usize, Arc<Config>) {
+        if let Some(config) = &self.explicit {
+            return (0, config.clone());
+        }
+        if let Some(&id) = self.dirs.get(dir) {
+            return (id, self.configs[id].clone());
+        }
+
+        let chain = self.chain(dir);
+        let id = if let Some(&id) = self.chains.get(&chain) {
+            id
+        } else {
+            let config = Config::from_files(&chain).unwrap_or_else(|e| {
+                always_log!("Using default configuration for {}: '{e}'.", dir.display());
+                Config::default()
+            });
+            self.configs
+                .push(Arc::new(self.cli.merge_with_config(config)));
+            self.chains.insert(chain, self.configs.len() - 1);
+            self.configs.len() - 1
+        };
+        self.dirs.insert(dir.to_path_buf(), id);
+        (id, self.configs[id].clone())
+    }
+
+    /// Config files governing `dir`, the outermost first
+    fn chain(&mut self, dir: &Path) -> Vec<PathBuf> {
+        let mut chain = Vec::new();
+        for ancestor in dir.ancestors() {
+            let found = self
+                .found
+                .entry(ancestor.to_path_buf())
+                .or_insert_with(|| config_in(ancestor));
+            chain.extend(found.clone());
+            if ancestor.join(".git").exists() {
+                break;
+            }
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+/// Config file of a directory, a sqint.toml or else a pyproject.toml with a `[tool.sqint]` section
+fn config_in(dir: &Path) -> Option<PathBuf> {
+    let sqint_conf = dir.join(DEFAULT_CONFIG_NAME);
+    let pyproject_conf = dir.join(PYPROJECT_CONFIG_NAME);
+    let path = if sqint_conf.is_file() {
+        sqint_conf
+    } else if pyproject_conf.is_file() && !matches!(Config::read_table(&pyproject_conf), Ok(None)) {
+        pyproject_conf
+    } else {
+        return None;
+    };
+
+    match Config::from_file(&path) {
+        Ok(_) => Some(path),
+        Err(e) => {
+            always_log!("Ignoring config {}: '{e}'.", path.display());
+            None
+        }
+    }
 }
 
 #[must_use]
@@ -176,7 +279,7 @@ pub fn filter_file_pats(files: Vec<String>, cfg: &Config) -> (Vec<String>, Vec<S
     (include_files, sql_files)
 }
 
-fn slice_to_glob(patterns: &[String], log_ctx: &str) -> GlobSet {
+pub fn slice_to_glob(patterns: &[String], log_ctx: &str) -> GlobSet {
     patterns
         .iter()
         .filter_map(|p| {
@@ -194,3 +297,81 @@ fn slice_to_glob(patterns: &[String], log_ctx: &str) -> GlobSet {
             GlobSetBuilder::new().build().unwrap()
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::fs;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn nested_configs_override_their_subtree() {
+        let outer = std::env::temp_dir().join(format!("sqint-tree-{}", std::process::id()));
+        let root = outer.join("repo");
+        fs::create_dir_all(root.join(".git")).unwrap();
+        write(&outer.join("sqint.toml"), "max_threads = 7");
+        write(
+            &root.join("sqint.toml"),
+            r#"
+dialect = "postgres"
+variable_contexts = ["*custom*"]
+schema_files = ["db/schema.sql"]
+
+[rules]
+SQ001 = "warning"
+"#,
+        );
+        write(
+            &root.join("services/analytics/sqint.toml"),
+            "dialect = \"bigquery\"\n[rules]\nSQ006 = \"off\"",
+        );
+        write(
+            &root.join("services/api/pyproject.toml"),
+            "[tool.sqint]\nmax_threads = 3",
+        );
+        write(
+            &root.join("services/web/pyproject.toml"),
+            "[project]\nname = \"web\"",
+        );
+        write(&root.join("legacy/sqint.toml"), "dialect = 5");
+
+        let cli = Cli::try_parse_from(["sqint"]).unwrap();
+        let mut configs = ConfigTree::new(&cli).unwrap();
+
+        let (root_id, config) = configs.resolve(&root.join("services"));
+        assert_eq!(config.dialect, ["postgres"]);
+        assert_eq!(config.variable_contexts, ["*custom*"]);
+        assert_eq!(
+            config.max_threads, 0,
+            "Configs above the repository are ignored"
+        );
+        assert_eq!(
+            config.schema_files,
+            [root.join("db/schema.sql").to_string_lossy()]
+        );
+
+        let (_, config) = configs.resolve(&root.join("services/analytics/jobs"));
+        assert_eq!(config.dialect, ["bigquery"]);
+        assert_eq!(config.variable_contexts, ["*custom*"]);
+        assert_eq!(config.rules.len(), 2);
+
+        let (_, config) = configs.resolve(&root.join("services/api"));
+        assert_eq!(config.dialect, ["postgres"]);
+        assert_eq!(config.max_threads, 3);
+
+        assert_eq!(configs.resolve(&root.join("services/web")).0, root_id);
+        assert_eq!(configs.resolve(&root.join("legacy")).0, root_id);
+
+        let cli = Cli::try_parse_from(["sqint", "--dialect", "sqlite"]).unwrap();
+        let mut configs = ConfigTree::new(&cli).unwrap();
+        let (_, config) = configs.resolve(&root.join("services/analytics"));
+        assert_eq!(config.dialect, ["sqlite"]);
+
+        fs::remove_dir_all(&outer).unwrap();
+    }
+}
@@ -1,129 +1,534 @@
-use logging::{always_log, error, info};
-use std::sync::Arc;
+use finder::SymbolTable;
+use globset::{Glob, GlobMatcher};
+use logging::{always_log, error, info, warn};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::thread;
 
-use crate::analyzer::SqlDialect;
+use crate::analyzer::{SqlAnalyzer, analyze_in_dialects};
+use crate::baseline::Baseline;
+use crate::cli::{ExtractArgs, PortabilityArgs};
+use crate::dialect::{AUTO, SqlDialect, is_auto};
+use crate::files::ConfigTree;
+use crate::report::{Diagnostic, Reporter};
+use crate::rules::RuleSet;
+use crate::schema::Catalog;
 
-#[allow(clippy::too_many_lines)]
-pub fn handle_check(config: &Arc<crate::Config>, cli: &crate::Cli) {
-    let cfg = Arc::new(finder::FinderConfig::new(
-        &config.variable_contexts,
-        &config.function_contexts,
-    ));
-    let (found_files, explicit_files) = crate::files::collect_files(&cli.check_args.paths, config);
-    let explicit_files = crate::files::canonicalize_files(explicit_files);
-    let found_files = crate::files::canonicalize_files(found_files);
-    if found_files.is_empty() && explicit_files.is_empty() {
-        always_log!("No target files found in the specified paths.");
-        return;
+/// What a file is checked with, shared by all files governed by the same config
+pub(crate) struct Scope {
+    config: Arc<crate::Config>,
+    finder: Arc<finder::FinderConfig>,
+    rules: Arc<RuleSet>,
+    symbols: Arc<SymbolTable>,
+    // Dialects of the files matching `dialect_overrides` globs, most specific first
+    dialect_overrides: Vec<(GlobMatcher, SqlDialect)>,
+}
+
+impl Scope {
+    pub(crate) fn new(config: Arc<crate::Config>, symbols: Arc<SymbolTable>) -> Self {
+        let mut finder =
+            finder::FinderConfig::new(&config.variable_contexts, &config.function_contexts)
+                .with_presets(&config.presets)
+                .with_max_variants(config.max_variants);
+        // SQL files are split into statements in the first of the dialects
+        if let Some(dialects) = SqlDialect::from_config(&config.dialect) {
+            finder = finder.with_dialect(dialects[0].parser_dialect());
+        }
+        let finder = Arc::new(finder);
+        let rules = Arc::new(RuleSet::new(&config, load_catalog(&config).as_ref()));
+        let dialect_overrides = dialect_overrides(&config.dialect_overrides);
+        Self {
+            config,
+            finder,
+            rules,
+            symbols,
+            dialect_overrides,
+        }
     }
-    let target_files: Vec<String> = crate::files::filter_incremental_files(&found_files, config);
-    let (target_files, sql_files): (Vec<String>, Vec<String>) =
-        crate::files::filter_file_pats(target_files, config);
-    let target_files: Vec<String> = target_files.into_iter().chain(explicit_files).collect();
-    let sql_files: Vec<String> = sql_files.into_iter().collect();
 
-    let total_files = target_files.len() + sql_files.len();
-    if total_files == 0 {
-        always_log!("No files to process after filtering.");
-        return;
+    pub(crate) fn finder(&self) -> finder::SqlFinder {
+        finder::SqlFinder::new(self.finder.clone()).with_symbols(self.symbols.clone())
+    }
+
+    /// Checks the SQL found in a file, `None` if a dialect is unknown. Each string is checked
+    /// in the dialect its pragma or `function_contexts` entry names, or else in those of the file.
+    pub(crate) fn analyze(&self, sql_extract: &finder::SqlExtract) -> Option<Vec<Diagnostic>> {
+        let file_dialects = self.file_dialects(sql_extract)?;
+        let migration = self.rules.is_migration(&sql_extract.rel_path);
+        let mut analyzers: HashMap<SqlDialect, SqlAnalyzer> = HashMap::new();
+        let mut diagnostics = Vec::new();
+        for sql_string in &sql_extract.strings {
+            let dialects = self.string_dialects(sql_extract, sql_string, &file_dialects);
+            for dialect in &dialects {
+                analyzers
+                    .entry(*dialect)
+                    .or_insert_with(|| self.analyzer(*dialect));
+            }
+            let targets: Vec<&SqlAnalyzer> =
+                dialects.iter().map(|dialect| &analyzers[dialect]).collect();
+            diagnostics.extend(analyze_in_dialects(
+                &targets,
+                sql_string,
+                &sql_extract.rel_path,
+                migration,
+            ));
+        }
+
+        for diagnostic in &mut diagnostics {
+            let location = &mut diagnostic.location;
+            (location.cell, location.line) = sql_extract.locate(location.line);
+        }
+        Some(diagnostics)
     }
 
-    if config.parallel_processing {
-        let max_threads = if config.max_threads == 0 {
-            std::thread::available_parallelism()
-                .map(std::num::NonZero::get)
-                .unwrap_or(5)
-                - 1
+    /// Whether each SQL string of a file parses in each of the dialects it must run on, or in
+    /// every dialect with `auto`
+    pub(crate) fn portability(
+        &self,
+        sql_extract: &finder::SqlExtract,
+    ) -> Option<Vec<crate::portability::Record>> {
+        let targets = if is_auto(&self.config.dialect) {
+            SqlDialect::all().to_vec()
         } else {
-            info!("Using configured thread count: {}", config.max_threads);
-            config.max_threads
+            SqlDialect::from_config(&self.config.dialect)?
         };
+        let analyzers: Vec<SqlAnalyzer> = targets
+            .into_iter()
+            .map(|dialect| self.analyzer(dialect))
+            .collect();
+        Some(crate::portability::records(sql_extract, &analyzers))
+    }
 
-        // Process Python files
-        if !target_files.is_empty() {
-            let chunk_size = std::cmp::max(1, target_files.len() / max_threads);
-            target_files
-                .chunks(chunk_size)
-                .map(|chunk| {
-                    let chunk_vec = chunk.to_vec();
-                    let cfg = cfg.clone();
-                    let app_cfg = config.clone();
-                    thread::spawn(move || {
-                        for file_path in chunk_vec {
-                            process_file(&file_path, cfg.clone(), &app_cfg.clone(), false);
-                        }
-                    })
-                })
-                .collect::<Vec<_>>()
-                .into_iter()
-                .for_each(|handle| handle.join().unwrap());
+    fn analyzer(&self, dialect: SqlDialect) -> SqlAnalyzer {
+        SqlAnalyzer::new(
+            &dialect,
+            self.config.dialect_mappings.clone(),
+            &self.config.param_markers,
+            self.rules.clone(),
+        )
+    }
+
+    /// Dialect named for a single string, or else those of its file
+    fn string_dialects(
+        &self,
+        sql_extract: &finder::SqlExtract,
+        sql_string: &finder::SqlString,
+        file_dialects: &[SqlDialect],
+    ) -> Vec<SqlDialect> {
+        let Some(name) = sql_string.dialect.as_deref() else {
+            return file_dialects.to_vec();
+        };
+        if let Some(dialect) = SqlDialect::from_str(name) {
+            return vec![dialect];
         }
+        let names: Vec<&str> = file_dialects.iter().map(|d| d.name()).collect();
+        warn!(
+            "Unknown dialect '{name}' for {}::{}, checking it as {}",
+            sql_extract.rel_path,
+            sql_string.variable_name,
+            names.join(" and ")
+        );
+        file_dialects.to_vec()
+    }
 
-        // Process SQL files
-        if !sql_files.is_empty() {
-            let chunk_size = std::cmp::max(1, sql_files.len() / max_threads);
-            sql_files
-                .chunks(chunk_size)
-                .map(|chunk| {
-                    let chunk_vec = chunk.to_vec();
-                    let cfg = cfg.clone();
-                    let app_cfg = config.clone();
-                    thread::spawn(move || {
-                        for file_path in chunk_vec {
-                            process_file(&file_path, cfg.clone(), &app_cfg.clone(), true);
-                        }
-                    })
-                })
-                .collect::<Vec<_>>()
-                .into_iter()
-                .for_each(|handle| handle.join().unwrap());
+    /// Dialect of the first `dialect_overrides` glob matching the path of the file relative to
+    /// the working directory, or the configured ones. With `auto`, that of the database drivers
+    /// the file imports, when they agree. `None` if a configured dialect is unknown.
+    fn file_dialects(&self, sql_extract: &finder::SqlExtract) -> Option<Vec<SqlDialect>> {
+        let path = Path::new(&sql_extract.file_path);
+        let path: PathBuf = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| path.strip_prefix(cwd).ok())
+            .unwrap_or(path)
+            .components()
+            .filter(|component| !matches!(component, Component::CurDir))
+            .collect();
+        let rel_path = &sql_extract.rel_path;
+        if let Some((glob, dialect)) = self
+            .dialect_overrides
+            .iter()
+            .find(|(glob, _)| glob.is_match(&path))
+        {
+            info!(
+                "Checking {rel_path} as {}, matching dialect_overrides pattern '{}'",
+                dialect.name(),
+                glob.glob()
+            );
+            return Some(vec![*dialect]);
         }
-    } else {
-        for file_path in &target_files {
-            process_file(file_path, cfg.clone(), &config.clone(), false);
+        if !is_auto(&self.config.dialect) {
+            let dialects = SqlDialect::from_config(&self.config.dialect);
+            if dialects.is_none() {
+                error!(
+                    "Unknown dialect in {:?}. Supported: {:?}, or \"{AUTO}\" alone",
+                    self.config.dialect,
+                    SqlDialect::supported_dialects()
+                );
+            }
+            return dialects;
         }
 
-        for file_path in &sql_files {
-            process_file(file_path, cfg.clone(), &config.clone(), true);
+        let mut drivers = sql_extract.drivers.iter();
+        let Some(first) = drivers.next() else {
+            info!("Checking {rel_path} as generic, it imports no known database driver");
+            return Some(vec![SqlDialect::Generic]);
+        };
+        if drivers.any(|driver| driver.dialect != first.dialect) {
+            let drivers: Vec<String> = sql_extract
+                .drivers
+                .iter()
+                .map(|driver| format!("{} ({})", driver.module, driver.dialect))
+                .collect();
+            info!(
+                "Checking {rel_path} as generic, it imports drivers of different dialects: {}",
+                drivers.join(", ")
+            );
+            return Some(vec![SqlDialect::Generic]);
+        }
+        info!(
+            "Checking {rel_path} as {}, detected from its import of {}",
+            first.dialect, first.module
+        );
+        SqlDialect::from_str(&first.dialect).map(|dialect| vec![dialect])
+    }
+}
+
+/// Compiles the `dialect_overrides` globs, longer and so more specific ones first.
+fn dialect_overrides(overrides: &HashMap<String, String>) -> Vec<(GlobMatcher, SqlDialect)> {
+    let mut overrides: Vec<(&String, &String)> = overrides.iter().collect();
+    overrides.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    overrides
+        .into_iter()
+        .filter_map(|(pattern, dialect)| {
+            let Some(dialect) = SqlDialect::from_str(dialect) else {
+                error!(
+                    "Unknown dialect '{dialect}' for dialect_overrides pattern '{pattern}'. Supported: {:?}",
+                    SqlDialect::supported_dialects()
+                );
+                return None;
+            };
+            Glob::new(pattern)
+                .map_err(|e| {
+                    always_log!("Failed to parse dialect_overrides glob pattern '{pattern}': {e}");
+                })
+                .ok()
+                .map(|glob| (glob.compile_matcher(), dialect))
+        })
+        .collect()
+}
+
+/// Files to check with their scope, and whether they're raw SQL
+type Job = (String, Arc<Scope>, bool);
+
+/// A config with the found and the explicitly given files it governs
+type Governed = (Arc<crate::Config>, Vec<String>, Vec<String>);
+
+/// Checks the files and reports findings that aren't in the baseline, then the entries of the
+/// baseline that weren't found anymore.
+pub fn handle_check(
+    config: &Arc<crate::Config>,
+    cli: &crate::Cli,
+    configs: &mut ConfigTree,
+    reporter: &Reporter,
+) {
+    let path = Path::new(&config.baseline_file);
+    let baseline = match Baseline::load(path) {
+        Ok(baseline) => Arc::new(baseline),
+        Err(e) => {
+            error!("Couldn't load baseline from {}: {e}", path.display());
+            return;
         }
+    };
+    check_files(config, cli, configs, &baseline, reporter);
+    if baseline.is_empty() {
+        return;
     }
 
+    always_log!(
+        "Sqint: {} findings are accepted by the baseline in {}.",
+        baseline.suppressed(),
+        path.display()
+    );
+    let stale = baseline.stale();
+    for (entry, missing) in &stale {
+        always_log!(
+            "Baseline entry no longer found {missing}x: {} {}::{}",
+            entry.code,
+            entry.file,
+            entry.name
+        );
+    }
+    if !stale.is_empty() {
+        always_log!("Run `sqint baseline create` to remove the entries that are no longer found.");
+    }
+}
+
+/// Checks the files and writes all findings to the baseline file.
+pub fn handle_baseline_create(
+    config: &Arc<crate::Config>,
+    cli: &crate::Cli,
+    configs: &mut ConfigTree,
+) {
+    let path = Path::new(&config.baseline_file);
+    let baseline = Arc::new(Baseline::create(path));
+    check_files(
+        config,
+        cli,
+        configs,
+        &baseline,
+        &Reporter::new(config.output_format),
+    );
+    match baseline.write() {
+        Ok(count) => always_log!(
+            "Sqint: Wrote {count} findings to the baseline in {}.",
+            path.display()
+        ),
+        Err(e) => error!("Couldn't write baseline to {}: {e}", path.display()),
+    }
+}
+
+/// Lists the SQL found in the files without checking it.
+pub fn handle_extract(
+    config: &Arc<crate::Config>,
+    cli: &crate::Cli,
+    configs: &mut ConfigTree,
+    args: &ExtractArgs,
+) {
+    let Some((target_files, sql_files)) = plan_jobs(config, cli, configs) else {
+        return;
+    };
+    let found = Mutex::new(Vec::new());
+    for_each_job(
+        config,
+        &target_files,
+        &sql_files,
+        |(file_path, scope, is_raw_sql)| {
+            if let Some(sql_extract) = scope.finder().analyze_file(file_path, *is_raw_sql) {
+                found
+                    .lock()
+                    .expect("Extracting threads don't panic holding the lock")
+                    .push(sql_extract);
+            }
+        },
+    );
+
+    let mut extracts = found
+        .into_inner()
+        .expect("Extracting threads don't panic holding the lock");
+    extracts.sort_by(|a, b| a.rel_path.cmp(&b.rel_path));
+    match crate::extract::write(&extracts, args) {
+        Ok(count) => always_log!(
+            "Sqint: Extracted {count} SQL strings from {} files.",
+            target_files.len() + sql_files.len()
+        ),
+        Err(e) => error!("Failed to write extracted SQL: {e}"),
+    }
+}
+
+/// Reports which of the target dialects parse the SQL found in the files.
+pub fn handle_portability(
+    config: &Arc<crate::Config>,
+    cli: &crate::Cli,
+    configs: &mut ConfigTree,
+    args: &PortabilityArgs,
+) {
+    let Some((target_files, sql_files)) = plan_jobs(config, cli, configs) else {
+        return;
+    };
+    let found = Mutex::new(Vec::new());
+    for_each_job(
+        config,
+        &target_files,
+        &sql_files,
+        |(file_path, scope, is_raw_sql)| {
+            let Some(sql_extract) = scope.finder().analyze_file(file_path, *is_raw_sql) else {
+                return;
+            };
+            let Some(records) = scope.portability(&sql_extract) else {
+                error!(
+                    "Unknown dialect in {:?}. Supported: {:?}, or \"{AUTO}\" alone",
+                    scope.config.dialect,
+                    SqlDialect::supported_dialects()
+                );
+                return;
+            };
+            found
+                .lock()
+                .expect("Checking threads don't panic holding the lock")
+                .push((sql_extract.rel_path, records));
+        },
+    );
+
+    let mut files = found
+        .into_inner()
+        .expect("Checking threads don't panic holding the lock");
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let records: Vec<_> = files.into_iter().flat_map(|(_, records)| records).collect();
+    if let Err(e) = crate::portability::write(&records, args.format) {
+        error!("Failed to write the portability report: {e}");
+        return;
+    }
+    always_log!(
+        "Sqint: {} of {} SQL strings parse in every target dialect.",
+        records.iter().filter(|record| record.is_portable()).count(),
+        records.len()
+    );
+}
+
+fn check_files(
+    config: &Arc<crate::Config>,
+    cli: &crate::Cli,
+    configs: &mut ConfigTree,
+    baseline: &Arc<Baseline>,
+    reporter: &Reporter,
+) {
+    let Some((target_files, sql_files)) = plan_jobs(config, cli, configs) else {
+        return;
+    };
+    for_each_job(
+        config,
+        &target_files,
+        &sql_files,
+        |(file_path, scope, is_raw_sql)| {
+            process_file(file_path, scope, baseline, reporter, *is_raw_sql);
+        },
+    );
+
     always_log!(
         "Sqint: Processed {} files ({} Python, {} SQL).",
-        total_files,
+        target_files.len() + sql_files.len(),
         target_files.len(),
         sql_files.len()
     );
 }
 
+/// Collects the files to check with the scope of the config governing them, the Python files
+/// and the SQL files. `None` if there's nothing to check.
+fn plan_jobs(
+    config: &Arc<crate::Config>,
+    cli: &crate::Cli,
+    configs: &mut ConfigTree,
+) -> Option<(Vec<Job>, Vec<Job>)> {
+    let (found_files, explicit_files) =
+        crate::files::collect_files(&cli.check_args().paths, config);
+    let explicit_files = crate::files::canonicalize_files(explicit_files);
+    let found_files = crate::files::canonicalize_files(found_files);
+    if found_files.is_empty() && explicit_files.is_empty() {
+        always_log!("No target files found in the specified paths.");
+        return None;
+    }
+    let found_files: Vec<String> = crate::files::filter_incremental_files(&found_files, config);
+
+    // Files are filtered and checked with the config governing their directory
+    let mut scopes: HashMap<usize, Governed> = HashMap::new();
+    let files = found_files.into_iter().map(|file| (file, false));
+    for (file, explicit) in files.chain(explicit_files.into_iter().map(|file| (file, true))) {
+        let (id, file_config) = configs.resolve(parent_dir(&file));
+        let (_, found, explicits) = scopes
+            .entry(id)
+            .or_insert_with(|| (file_config, Vec::new(), Vec::new()));
+        if explicit { explicits } else { found }.push(file);
+    }
+
+    // Imported modules are read once for all files, whichever config governs them
+    let symbols = Arc::new(SymbolTable::new());
+    let mut target_files: Vec<Job> = Vec::new();
+    let mut sql_files: Vec<Job> = Vec::new();
+    for (scope_config, found, explicit) in scopes.into_values() {
+        let (targets, sqls) = crate::files::filter_file_pats(found, &scope_config);
+        if targets.is_empty() && sqls.is_empty() && explicit.is_empty() {
+            continue;
+        }
+        let scope = Arc::new(Scope::new(scope_config, symbols.clone()));
+        target_files.extend(
+            targets
+                .into_iter()
+                .chain(explicit)
+                .map(|file| (file, scope.clone(), false)),
+        );
+        sql_files.extend(sqls.into_iter().map(|file| (file, scope.clone(), true)));
+    }
+
+    if target_files.is_empty() && sql_files.is_empty() {
+        always_log!("No files to process after filtering.");
+        return None;
+    }
+    Some((target_files, sql_files))
+}
+
+/// Runs `job` for the Python files, then the SQL files, in parallel if configured
+fn for_each_job(
+    config: &crate::Config,
+    target_files: &[Job],
+    sql_files: &[Job],
+    job: impl Fn(&Job) + Sync,
+) {
+    if !config.parallel_processing {
+        target_files.iter().chain(sql_files).for_each(job);
+        return;
+    }
+
+    let max_threads = if config.max_threads == 0 {
+        std::thread::available_parallelism()
+            .map(std::num::NonZero::get)
+            .unwrap_or(5)
+            .saturating_sub(1)
+            .max(1)
+    } else {
+        info!("Using configured thread count: {}", config.max_threads);
+        config.max_threads
+    };
+
+    for jobs in [target_files, sql_files] {
+        if jobs.is_empty() {
+            continue;
+        }
+        let chunk_size = std::cmp::max(1, jobs.len() / max_threads);
+        thread::scope(|s| {
+            for chunk in jobs.chunks(chunk_size) {
+                s.spawn(|| chunk.iter().for_each(&job));
+            }
+        });
+    }
+}
+
+fn parent_dir(file: &str) -> &Path {
+    Path::new(file).parent().unwrap_or_else(|| Path::new("/"))
+}
+
+/// Builds the catalog of the configured schema files, shared by all files being checked.
+fn load_catalog(config: &crate::Config) -> Option<Arc<Catalog>> {
+    if config.schema_files.is_empty() {
+        return None;
+    }
+    // An unknown dialect is reported for every file, the catalog isn't needed then
+    let dialects = SqlDialect::from_config(&config.dialect)?;
+    let catalog = Catalog::load(&config.schema_files, &*dialects[0].parser_dialect());
+    info!(
+        "Loaded {} tables and views from {} schema files",
+        catalog.len(),
+        config.schema_files.len()
+    );
+    Some(Arc::new(catalog))
+}
+
 fn process_file(
     file_path: &str,
-    cfg: Arc<crate::FinderConfig>,
-    app_cfg: &Arc<crate::Config>,
+    scope: &Scope,
+    baseline: &Baseline,
+    reporter: &Reporter,
     is_raw_sql: bool,
 ) {
-    let mut sql_finder = finder::SqlFinder::new(cfg);
-
-    let Some(sql_extract) = sql_finder.analyze_file(file_path, is_raw_sql) else {
+    let Some(sql_extract) = scope.finder().analyze_file(file_path, is_raw_sql) else {
         return;
     };
-
-    let Some(dialect) = SqlDialect::from_str(&app_cfg.dialect) else {
-        error!(
-            "Unknown dialect. Supported: {:?}",
-            SqlDialect::supported_dialects()
-        );
+    let Some(diagnostics) = scope.analyze(&sql_extract) else {
         return;
     };
 
-    let analyzer = crate::analyzer::SqlAnalyzer::new(
-        &dialect,
-        app_cfg.dialect_mappings.clone(),
-        &app_cfg.param_markers,
-    );
-
-    analyzer.analyze_sql_extract(&sql_extract);
+    let diagnostics = baseline.filter(file_path, diagnostics);
+    reporter.report(sql_extract.strings.len(), &diagnostics);
 }
 
 pub fn handle_init() {
@@ -160,3 +565,157 @@ pub fn handle_init() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::DiagnosticKind;
+    use finder::preanalysis::{LineCol, Range};
+    use finder::{DriverImport, SqlExtract, SqlString};
+
+    fn extract(path: &str, dialects: &[Option<&str>]) -> SqlExtract {
+        let strings = dialects
+            .iter()
+            .map(|dialect| SqlString {
+                dialect: dialect.map(ToString::to_string),
+                ..SqlString::new(
+                    "query".to_string(),
+                    "SELECT `a` FROM `t`".to_string(),
+                    Range::verbatim(LineCol::new(1, 1, 0), ""),
+                )
+            })
+            .collect();
+        SqlExtract::new(path.to_string(), strings)
+    }
+
+    fn syntax_errors(scope: &Scope, path: &str, dialects: &[Option<&str>]) -> usize {
+        extract_errors(scope, &extract(path, dialects))
+    }
+
+    fn extract_errors(scope: &Scope, extract: &SqlExtract) -> usize {
+        scope
+            .analyze(extract)
+            .expect("Dialects are known")
+            .iter()
+            .filter(|diagnostic| diagnostic.kind == Some(DiagnosticKind::SyntaxError))
+            .count()
+    }
+
+    #[test]
+    fn sql_is_checked_in_the_dialect_named_for_it() {
+        let config = crate::Config {
+            dialect: vec!["postgres".to_string()],
+            dialect_overrides: HashMap::from([
+                ("etl/**".to_string(), "bigquery".to_string()),
+                ("etl/legacy/**".to_string(), "mssql".to_string()),
+            ]),
+            ..crate::Config::default()
+        };
+        let scope = Scope::new(Arc::new(config), Arc::new(SymbolTable::new()));
+
+        assert_eq!(syntax_errors(&scope, "app/db.py", &[None]), 1);
+        assert_eq!(syntax_errors(&scope, "etl/load.py", &[None]), 0);
+        assert_eq!(syntax_errors(&scope, "./etl/legacy/load.py", &[None]), 1);
+        assert_eq!(
+            syntax_errors(
+                &scope,
+                "app/db.py",
+                &[
+                    Some("bigquery"),
+                    Some("mysql"),
+                    Some("sqlite"),
+                    Some("postgres")
+                ]
+            ),
+            1
+        );
+        assert_eq!(syntax_errors(&scope, "etl/load.py", &[Some("unknown")]), 0);
+    }
+
+    #[test]
+    fn auto_dialect_follows_the_imported_drivers() {
+        let scope = Scope::new(
+            Arc::new(crate::Config::default()),
+            Arc::new(SymbolTable::new()),
+        );
+        let driver = |module: &str, dialect: &str| DriverImport {
+            module: module.to_string(),
+            dialect: dialect.to_string(),
+        };
+        let with_drivers = |drivers: Vec<DriverImport>| SqlExtract {
+            drivers,
+            ..extract("app/db.py", &[None])
+        };
+
+        // Backticks only fail where a driver names a dialect that rejects them
+        assert_eq!(extract_errors(&scope, &with_drivers(vec![])), 0);
+        assert_eq!(
+            extract_errors(&scope, &with_drivers(vec![driver("psycopg2", "postgres")])),
+            1
+        );
+        assert_eq!(
+            extract_errors(&scope, &with_drivers(vec![driver("pymysql", "mysql")])),
+            0
+        );
+        assert_eq!(
+            extract_errors(
+                &scope,
+                &with_drivers(vec![
+                    driver("psycopg2", "postgres"),
+                    driver("sqlite3", "sqlite")
+                ])
+            ),
+            0
+        );
+
+        let explicit = Scope::new(
+            Arc::new(crate::Config {
+                dialect: vec!["mysql".to_string()],
+                ..crate::Config::default()
+            }),
+            Arc::new(SymbolTable::new()),
+        );
+        assert_eq!(
+            extract_errors(
+                &explicit,
+                &with_drivers(vec![driver("psycopg2", "postgres")])
+            ),
+            0
+        );
+    }
+
+    #[test]
+    fn findings_name_the_target_dialects_rejecting_the_sql() {
+        let config = crate::Config {
+            dialect: vec![
+                "postgres".to_string(),
+                "mysql".to_string(),
+                "sqlite".to_string(),
+            ],
+            ..crate::Config::default()
+        };
+        let scope = Scope::new(Arc::new(config), Arc::new(SymbolTable::new()));
+
+        let diagnostics = scope.analyze(&extract("app/db.py", &[None])).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].dialect, "postgres");
+        assert!(
+            diagnostics[0]
+                .reason
+                .as_ref()
+                .is_some_and(|reason| reason.ends_with(", in postgres"))
+        );
+        // A dialect named for the string replaces the targets
+        assert_eq!(
+            syntax_errors(&scope, "app/db.py", &[Some("mysql"), Some("mssql")]),
+            1
+        );
+
+        let unknown = crate::Config {
+            dialect: vec!["postgres".to_string(), "auto".to_string()],
+            ..crate::Config::default()
+        };
+        let scope = Scope::new(Arc::new(unknown), Arc::new(SymbolTable::new()));
+        assert!(scope.analyze(&extract("app/db.py", &[None])).is_none());
+    }
+}
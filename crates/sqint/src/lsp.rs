@@ -0,0 +1,587 @@
+use finder::{SqlExtract, SqlString, SymbolTable};
+use logging::error;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::files::ConfigTree;
+use crate::handlers::Scope;
+use crate::report::{Diagnostic, Severity};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Serves the Language Server Protocol on stdin and stdout, returning the exit code.
+pub fn handle_lsp(configs: &mut ConfigTree) -> i32 {
+    Server::new(configs).serve(&mut io::stdin().lock(), &mut io::stdout().lock())
+}
+
+/// Language server checking the open documents as they're edited. Documents are checked with the
+/// config governing their directory, like `sqint check` would, but without the baseline.
+struct Server<'c, 'a> {
+    configs: &'c mut ConfigTree<'a>,
+    scopes: HashMap<usize, Arc<Scope>>,
+    symbols: Arc<SymbolTable>,
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+}
+
+/// Last version of a document that could be checked
+struct Document {
+    text: String,
+    extract: SqlExtract,
+}
+
+impl<'c, 'a> Server<'c, 'a> {
+    fn new(configs: &'c mut ConfigTree<'a>) -> Self {
+        Self {
+            configs,
+            scopes: HashMap::new(),
+            symbols: Arc::new(SymbolTable::new()),
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    fn serve(&mut self, input: &mut impl BufRead, output: &mut impl Write) -> i32 {
+        loop {
+            let body = match read_message(input) {
+                Ok(Some(body)) => body,
+                // The client went away without asking to exit
+                Ok(None) => return 1,
+                Err(e) => {
+                    error!("Failed to read LSP message: {e}");
+                    return 1;
+                }
+            };
+            let replies = match serde_json::from_str::<Value>(&body) {
+                Ok(message) if message["method"] == "exit" => return i32::from(!self.shutdown),
+                Ok(message) => self.handle(&message),
+                Err(e) => vec![error_response(Value::Null, PARSE_ERROR, &e.to_string())],
+            };
+            for reply in replies {
+                if let Err(e) = write_message(output, &reply) {
+                    error!("Failed to write LSP message: {e}");
+                    return 1;
+                }
+            }
+        }
+    }
+
+    /// Handles a request or notification, returning the messages to send back
+    fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let Some(id) = message.get("id").cloned() else {
+            return self.notify(method, params).into_iter().collect();
+        };
+        if self.shutdown {
+            return vec![error_response(
+                id,
+                INVALID_REQUEST,
+                "Server is shutting down",
+            )];
+        }
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    // Full documents are sent on every change
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                },
+                "serverInfo": {"name": "sqint", "version": env!("CARGO_PKG_VERSION")},
+            }),
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/hover" => self.hover(params),
+            _ => {
+                return vec![error_response(
+                    id,
+                    METHOD_NOT_FOUND,
+                    &format!("Unsupported method {method}"),
+                )];
+            }
+        };
+        vec![json!({"jsonrpc": "2.0", "id": id, "result": result})]
+    }
+
+    /// Keeps track of the open documents, returning their diagnostics when they changed
+    fn notify(&mut self, method: &str, params: &Value) -> Option<Value> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let text = match method {
+            "textDocument/didOpen" => params["textDocument"]["text"].as_str()?,
+            "textDocument/didChange" => params["contentChanges"]
+                .as_array()?
+                .last()?
+                .get("text")?
+                .as_str()?,
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return Some(publish(uri, Vec::new()));
+            }
+            _ => return None,
+        };
+        self.update(uri, text.to_string())
+    }
+
+    fn update(&mut self, uri: &str, text: String) -> Option<Value> {
+        // Python that doesn't parse while being typed keeps the last diagnostics
+        let (extract, diagnostics) = self.check(uri, &text)?;
+        let diagnostics = diagnostics
+            .iter()
+            .filter_map(|diagnostic| lsp_diagnostic(&text, &extract, diagnostic))
+            .collect();
+        self.documents
+            .insert(uri.to_string(), Document { text, extract });
+        Some(publish(uri, diagnostics))
+    }
+
+    /// Finds and checks the SQL of a document, `None` if it isn't checked or doesn't parse
+    fn check(&mut self, uri: &str, text: &str) -> Option<(SqlExtract, Vec<Diagnostic>)> {
+        let path = uri_to_path(uri)?;
+        // Notebooks are sent cell by cell, their positions can't be mapped to the JSON
+        if path.extension().is_some_and(|ext| ext == "ipynb") {
+            return None;
+        }
+        // Files importing the document read it again, as its constants may have changed
+        self.symbols.forget(&path);
+        let (id, config) = self
+            .configs
+            .resolve(path.parent().unwrap_or_else(|| Path::new("/")));
+        let symbols = &self.symbols;
+        let scope = self
+            .scopes
+            .entry(id)
+            .or_insert_with(|| Arc::new(Scope::new(config.clone(), symbols.clone())))
+            .clone();
+
+        let file = path.to_string_lossy().into_owned();
+        let (targets, sqls) = crate::files::filter_file_pats(vec![file.clone()], &config);
+        if targets.is_empty() && sqls.is_empty() {
+            return Some((SqlExtract::new(file, Vec::new()), Vec::new()));
+        }
+        let extract = scope
+            .finder()
+            .analyze_source(&file, text, !sqls.is_empty())?;
+        let diagnostics = scope.analyze(&extract)?;
+        Some((extract, diagnostics))
+    }
+
+    /// Shows the SQL string under the cursor with the values the Python code splices into it
+    fn hover(&self, params: &Value) -> Value {
+        let document = params["textDocument"]["uri"]
+            .as_str()
+            .and_then(|uri| self.documents.get(uri));
+        let (Some(document), Some(line), Some(character)) = (
+            document,
+            params["position"]["line"].as_u64(),
+            params["position"]["character"].as_u64(),
+        ) else {
+            return Value::Null;
+        };
+        let extract = &document.extract;
+
+        let line = usize::try_from(line).unwrap_or(usize::MAX);
+        let character = usize::try_from(character).unwrap_or(usize::MAX);
+        let position = (line + 1, char_col(&document.text, line, character));
+        let Some(sql_string) = extract
+            .strings
+            .iter()
+            .filter(|s| {
+                (s.range.start.line(), s.range.start.col()) <= position
+                    && position <= (s.range.end.line(), s.range.end.col())
+            })
+            .max_by_key(|s| (s.range.start.line(), s.range.start.col()))
+        else {
+            return Value::Null;
+        };
+
+        let (start, end) = (&sql_string.range.start, &sql_string.range.end);
+        json!({
+            "contents": {"kind": "markdown", "value": hover_text(sql_string)},
+            "range": {
+                "start": lsp_position(&document.text, start.line(), start.col()),
+                "end": lsp_position(&document.text, end.line(), end.col()),
+            },
+        })
+    }
+}
+
+/// SQL as it's checked, runtime values shown as the Python expressions they come from
+fn hover_text(sql_string: &SqlString) -> String {
+    let mut text = format!(
+        "**{}**\n```sql\n{}\n```",
+        sql_string.variable_name,
+        sql_string.resolved_sql().trim()
+    );
+    for interpolation in &sql_string.interpolations {
+        text.push_str(&format!(
+            "\n- `{}` is spliced in by {}",
+            interpolation.expr, interpolation.kind
+        ));
+    }
+    text
+}
+
+fn lsp_diagnostic(text: &str, extract: &SqlExtract, diagnostic: &Diagnostic) -> Option<Value> {
+    let kind = diagnostic.kind?;
+    let line_text = text
+        .lines()
+        .nth(diagnostic.location.line.checked_sub(1)?)
+        .unwrap_or_default();
+    let start = diagnostic.location.col.saturating_sub(1);
+
+    // Spliced values cover their expression, other findings the word they point at
+    let expr = extract
+        .strings
+        .iter()
+        .flat_map(|s| &s.interpolations)
+        .find(|i| {
+            i.position.line() == diagnostic.location.line
+                && i.position.col() == diagnostic.location.col
+        })
+        .map(|i| i.expr.lines().next().unwrap_or_default().chars().count());
+    let word = line_text
+        .chars()
+        .skip(start)
+        .take_while(|ch| ch.is_alphanumeric() || *ch == '_')
+        .count();
+    let len = expr.unwrap_or(word).max(1);
+
+    let severity = match diagnostic.severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+        Severity::Info => 3,
+    };
+    Some(json!({
+        "range": {
+            "start": lsp_position(text, diagnostic.location.line, diagnostic.location.col),
+            "end": lsp_position(text, diagnostic.location.line, diagnostic.location.col + len),
+        },
+        "severity": severity,
+        "code": kind.code(),
+        "source": "sqint",
+        "message": diagnostic.reason.as_deref().unwrap_or(kind.code()),
+    }))
+}
+
+fn publish(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": {"uri": uri, "diagnostics": diagnostics},
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+/// LSP position of a line and column counted from 1. LSP counts from 0, and columns in UTF-16
+/// code units rather than characters.
+fn lsp_position(text: &str, line: usize, col: usize) -> Value {
+    let line = line.saturating_sub(1);
+    let character: usize = text
+        .lines()
+        .nth(line)
+        .unwrap_or_default()
+        .chars()
+        .take(col.saturating_sub(1))
+        .map(char::len_utf16)
+        .sum();
+    json!({"line": line, "character": character})
+}
+
+/// Column counted from 1 of an LSP position on `line`
+fn char_col(text: &str, line: usize, character: usize) -> usize {
+    let mut units = 0;
+    let chars = text
+        .lines()
+        .nth(line)
+        .unwrap_or_default()
+        .chars()
+        .take_while(|ch| {
+            units += ch.len_utf16();
+            units <= character
+        })
+        .count();
+    chars + 1
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        if let Some(escaped) = escaped {
+            bytes.push(escaped);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // `file:///C:/src` is `C:/src` on Windows
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if cfg!(windows) && drive.is_ascii_alphabetic() => &path[1..],
+        _ => &path,
+    };
+    Some(PathBuf::from(path))
+}
+
+/// Reads the body of the next message, `None` at the end of the input
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Message without a Content-Length header",
+        ));
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn frame(message: &Value) -> Vec<u8> {
+        let mut framed = Vec::new();
+        write_message(&mut framed, message).unwrap();
+        framed
+    }
+
+    /// Runs the server on a scripted session, returning its exit code and the messages it sent
+    fn session(messages: &[Value]) -> (i32, Vec<Value>) {
+        let cli = crate::Cli::parse_from(["sqint", "lsp"]);
+        let mut configs = ConfigTree::new(&cli).unwrap();
+        let input: Vec<u8> = messages.iter().flat_map(frame).collect();
+        let mut output = Vec::new();
+        let code = Server::new(&mut configs).serve(&mut input.as_slice(), &mut output);
+
+        let mut replies = Vec::new();
+        let mut output = output.as_slice();
+        while let Some(body) = read_message(&mut output).unwrap() {
+            replies.push(serde_json::from_str(&body).unwrap());
+        }
+        (code, replies)
+    }
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+    }
+
+    fn notification(method: &str, params: Value) -> Value {
+        json!({"jsonrpc": "2.0", "method": method, "params": params})
+    }
+
+    fn uri(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("sqint-lsp-{}", std::process::id()));
+        format!("file://{}", dir.join(name).display())
+    }
+
+    fn open(uri: &str, text: &str) -> Value {
+        notification(
+            "textDocument/didOpen",
+            json!({"textDocument": {"uri": uri, "languageId": "python", "version": 1, "text": text}}),
+        )
+    }
+
+    fn exit() -> [Value; 2] {
+        [
+            request(99, "shutdown", Value::Null),
+            notification("exit", Value::Null),
+        ]
+    }
+
+    fn diagnostics(replies: &[Value]) -> Vec<&Value> {
+        replies
+            .iter()
+            .filter(|r| r["method"] == "textDocument/publishDiagnostics")
+            .collect()
+    }
+
+    #[test]
+    fn initialize_and_shutdown() {
+        let mut messages = vec![
+            request(1, "initialize", json!({"capabilities": {}})),
+            notification("initialized", json!({})),
+            request(2, "textDocument/definition", json!({})),
+        ];
+        messages.extend(exit());
+        let (code, replies) = session(&messages);
+
+        assert_eq!(code, 0);
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies[0]["id"], 1);
+        assert_eq!(replies[0]["result"]["capabilities"]["hoverProvider"], true);
+        assert_eq!(replies[1]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(replies[2]["id"], 99);
+        assert_eq!(replies[2]["result"], Value::Null);
+
+        let (code, _) = session(&[notification("exit", Value::Null)]);
+        assert_eq!(code, 1);
+    }
+
+    #[test]
+    fn diagnostics_of_the_buffer() {
+        let uri = uri("queries.py");
+        // The buffer is checked, not the file on disk that doesn't exist
+        let mut messages = vec![open(
+            &uri,
+            "import db\nx = '😀'; query = \"SELEC * FROM users\"\nvalid_query = 'SELECT 1'\n",
+        )];
+        messages.push(notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": {"uri": uri, "version": 2},
+                "contentChanges": [{"text": "query = f\"SELECT * FROM users WHERE id = {user_id}\"\n"}],
+            }),
+        ));
+        // Python that doesn't parse keeps the last diagnostics
+        messages.push(notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": {"uri": uri, "version": 3},
+                "contentChanges": [{"text": "query = f\"SELECT"}],
+            }),
+        ));
+        messages.push(notification(
+            "textDocument/didClose",
+            json!({"textDocument": {"uri": uri}}),
+        ));
+        messages.extend(exit());
+        let (_, replies) = session(&messages);
+        let published = diagnostics(&replies);
+        assert_eq!(published.len(), 3);
+
+        let opened = published[0]["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(opened.len(), 1);
+        assert_eq!(opened[0]["code"], "SQ001");
+        assert_eq!(opened[0]["severity"], 1);
+        assert_eq!(
+            opened[0]["range"]["start"],
+            json!({"line": 1, "character": 19})
+        );
+        assert_eq!(
+            opened[0]["range"]["end"],
+            json!({"line": 1, "character": 24})
+        );
+
+        let changed = published[1]["params"]["diagnostics"].as_array().unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0]["code"], "SQ012");
+        assert_eq!(
+            changed[0]["range"]["start"],
+            json!({"line": 0, "character": 42})
+        );
+        assert_eq!(
+            changed[0]["range"]["end"],
+            json!({"line": 0, "character": 49})
+        );
+
+        assert_eq!(published[2]["params"]["diagnostics"], json!([]));
+    }
+
+    #[test]
+    fn hover_shows_resolved_sql() {
+        let uri = uri("hover.py");
+        let text = "import db\nquery = f\"SELECT * FROM users WHERE id = {user_id}\"\n";
+        let hover = |line: u64, character: u64| {
+            request(
+                2,
+                "textDocument/hover",
+                json!({"textDocument": {"uri": uri}, "position": {"line": line, "character": character}}),
+            )
+        };
+        let mut messages = vec![open(&uri, text), hover(1, 20), hover(1, 2)];
+        messages.extend(exit());
+        let (_, replies) = session(&messages);
+
+        let hovered = &replies[1]["result"];
+        let value = hovered["contents"]["value"].as_str().unwrap();
+        assert!(
+            value.contains("SELECT * FROM users WHERE id = {user_id}"),
+            "{value}"
+        );
+        assert!(
+            value.contains("`user_id` is spliced in by an f-string"),
+            "{value}"
+        );
+        assert_eq!(hovered["range"]["start"]["line"], 1);
+        assert_eq!(hovered["range"]["end"], json!({"line": 1, "character": 51}));
+        assert_eq!(replies[2]["result"], Value::Null);
+    }
+
+    #[test]
+    fn hover_over_sql_ending_in_a_constant() {
+        let uri = uri("constants.py");
+        let text = "TABLE = \"users\"\nquery = f\"SELECT * FROM {TABLE}\"\n\
+                    BASE = \"SELECT 1\"\nother_query = BASE\n";
+        let hover = |line: u64, character: u64| {
+            request(
+                2,
+                "textDocument/hover",
+                json!({"textDocument": {"uri": uri}, "position": {"line": line, "character": character}}),
+            )
+        };
+        let mut messages = vec![open(&uri, text), hover(1, 27), hover(3, 16)];
+        messages.extend(exit());
+        let (_, replies) = session(&messages);
+
+        assert_eq!(
+            replies[1]["result"]["range"],
+            json!({"start": {"line": 1, "character": 8}, "end": {"line": 1, "character": 32}})
+        );
+        assert_eq!(
+            replies[2]["result"]["range"],
+            json!({"start": {"line": 3, "character": 14}, "end": {"line": 3, "character": 18}})
+        );
+    }
+
+    #[test]
+    fn file_uris() {
+        assert_eq!(
+            uri_to_path("file:///home/me/my%20project/db.py"),
+            Some(PathBuf::from("/home/me/my project/db.py"))
+        );
+        assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+    }
+}
@@ -1,20 +1,47 @@
 mod analyzer;
+mod baseline;
 mod cli;
 mod config;
+mod dialect;
+mod extract;
 mod files;
 mod handlers;
+mod lsp;
+mod placeholders;
+mod portability;
+mod report;
+mod rules;
+mod sarif;
+mod schema;
 use clap::Parser;
-use cli::{Cli, Commands};
-use config::{Config, DEFAULT_CONFIG, DEFAULT_CONFIG_NAME};
-use finder::FinderConfig;
-use logging::{Logger, always_log, debug};
+use cli::{BaselineCommand, Cli, Commands};
+use config::{Config, DEFAULT_CONFIG, DEFAULT_CONFIG_NAME, OutputFormat};
+use logging::{Logger, debug};
+use report::Reporter;
 
 //TODO: Impl all README features
 //TODO: Big Refactor + Tests + Asserts
 fn main() {
     let cli = Cli::parse();
-    let config = cli.merge_with_config(files::load_config());
+    // The language server, extracted SQL and machine readable results use stdout, so nothing
+    // else may be written there, not even while the config is loaded
+    if matches!(
+        cli.command,
+        Some(Commands::Lsp | Commands::Extract(_) | Commands::Portability(_))
+    ) || cli
+        .check_args()
+        .output_format
+        .is_some_and(|format| format != OutputFormat::Text)
+    {
+        Logger::use_stderr_only();
+    }
+    let Some(mut configs) = files::ConfigTree::new(&cli) else {
+        std::process::exit(2);
+    };
+    let config = configs.root();
     setup_logging(&cli, &config);
+    configs.log_defaults();
+    let output_format = config.output_format;
 
     debug!("CLI arguments parsed: {:?}", cli);
     debug!("Configuration loaded successfully");
@@ -22,38 +49,48 @@ fn main() {
     match cli.command {
         None => {
             debug!("No explicit command provided, defaulting to check");
-            handlers::handle_check(&config.into(), &cli);
+            let reporter = Reporter::new(output_format);
+            handlers::handle_check(&config, &cli, &mut configs, &reporter);
+            reporter.report_summary();
         }
         Some(ref comm) => {
             debug!("Processing command: {:?}", comm);
             match comm {
                 Commands::Check(_) => {
-                    handlers::handle_check(&config.into(), &cli);
+                    let reporter = Reporter::new(output_format);
+                    handlers::handle_check(&config, &cli, &mut configs, &reporter);
+                    reporter.report_summary();
                 }
                 Commands::Init(_) => {
                     handlers::handle_init();
                 }
+                Commands::Baseline(BaselineCommand::Create(_)) => {
+                    handlers::handle_baseline_create(&config, &cli, &mut configs);
+                }
+                Commands::Extract(args) => {
+                    handlers::handle_extract(&config, &cli, &mut configs, args);
+                }
+                Commands::Portability(args) => {
+                    handlers::handle_portability(&config, &cli, &mut configs, args);
+                }
+                Commands::Lsp => {
+                    std::process::exit(lsp::handle_lsp(&mut configs));
+                }
             }
         }
     }
 
-    let exit_code = Logger::exit_code();
-    let sql_errors = Logger::get_sql_error_count();
-    let sql_total = Logger::get_sql_var_count();
-
-    if sql_total > 0 {
-        always_log!(
-            "Sqint: {} sql strings checked, {} errors found",
-            sql_total,
-            sql_errors
-        );
-    }
-
-    std::process::exit(exit_code);
+    std::process::exit(Logger::exit_code());
 }
 
 fn setup_logging(cli: &Cli, cfg: &Config) {
     let ll = cli.loglevel.unwrap_or(cfg.loglevel);
     debug!("Logging initialized at level: {:?}", ll);
     Logger::init(ll);
+    if !cli.use_colored_output() {
+        Logger::use_plain_output();
+    }
+    if cfg.output_format != OutputFormat::Text {
+        Logger::use_stderr_only();
+    }
 }
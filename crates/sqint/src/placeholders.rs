@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use finder::ParamStyle;
+
+use crate::analyzer::PLACEHOLDER;
+
+/// Parameter marker found in SQL text
+#[derive(Debug, PartialEq, Eq)]
+pub struct Marker {
+    /// Byte range in the SQL
+    pub range: Range<usize>,
+    pub style: ParamStyle,
+    /// Name of a named marker, digits of a `$n` one
+    pub name: Option<String>,
+}
+
+/// Parameter markers of SQL text, outside of literals and comments
+pub fn param_markers(sql: &str) -> Vec<Marker> {
+    code_spans(sql)
+        .into_iter()
+        .flat_map(|span| scan_markers(&sql[span.clone()], span.start))
+        .collect()
+}
+
+// Markers of code without literals and comments that starts at byte `base` of the SQL
+fn scan_markers(code: &str, base: usize) -> Vec<Marker> {
+    let mut markers = Vec::new();
+    let chars: Vec<char> = code.chars().collect();
+    let offsets: Vec<usize> = code
+        .char_indices()
+        .map(|(offset, _)| base + offset)
+        .chain(std::iter::once(base + code.len()))
+        .collect();
+    let mut push = |start: usize, len: usize, style, name: Option<String>| {
+        markers.push(Marker {
+            range: offsets[start]..offsets[start + len],
+            style,
+            name,
+        });
+    };
+    let mut i = 0;
+
+    while i < chars.len() {
+        let next = chars.get(i + 1).copied();
+        match chars[i] {
+            '?' => push(i, 1, ParamStyle::Qmark, None),
+            '%' if next == Some('%') => i += 1,
+            '%' if next == Some('s') => {
+                push(i, 2, ParamStyle::Format, None);
+                i += 1;
+            }
+            '%' if next == Some('(') => {
+                if let Some(len) = chars[i + 2..].iter().position(|c| *c == ')') {
+                    let name = chars[i + 2..i + 2 + len].iter().collect();
+                    // Up to the conversion after the name
+                    let conversion = usize::from(
+                        chars
+                            .get(i + len + 3)
+                            .is_some_and(char::is_ascii_alphabetic),
+                    );
+                    push(i, len + 3 + conversion, ParamStyle::Pyformat, Some(name));
+                    i += len + 2 + conversion;
+                }
+            }
+            '$' => {
+                let digits: String = chars[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect();
+                if !digits.is_empty() {
+                    let len = digits.len();
+                    push(i, len + 1, ParamStyle::Dollar, Some(digits));
+                    i += len;
+                }
+            }
+            // Not a `::` cast or `:=` assignment
+            ':' if i.checked_sub(1).is_none_or(|prev| chars[prev] != ':')
+                && next.is_some_and(|c| c.is_alphabetic() || c == '_') =>
+            {
+                let name: String = chars[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_alphanumeric() || **c == '_')
+                    .collect();
+                let len = name.chars().count();
+                push(i, len + 1, ParamStyle::Named, Some(name));
+                i += len;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    markers
+}
+
+/// Byte ranges of SQL text outside of quoted literals and comments
+fn code_spans(sql: &str) -> Vec<Range<usize>> {
+    let chars: Vec<char> = sql.chars().collect();
+    let offsets: Vec<usize> = sql
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(sql.len()))
+        .collect();
+    let mut spans = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let next = chars.get(i + 1).copied();
+        let end = match chars[i] {
+            quote @ ('\'' | '"' | '`') => skip_past(&chars, i + 1, |c, _| c == quote),
+            '-' if next == Some('-') => skip_past(&chars, i, |c, _| c == '\n'),
+            '/' if next == Some('*') => {
+                (skip_past(&chars, i + 2, |c, n| c == '*' && n == Some('/')) + 1).min(chars.len())
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        spans.push(offsets[start]..offsets[i]);
+        start = end;
+        i = end;
+    }
+    spans.push(offsets[start]..sql.len());
+    spans.retain(|span| !span.is_empty());
+    spans
+}
+
+// Index right after the first char from `start` matching `end`, given the char and the next one
+fn skip_past(chars: &[char], start: usize, end: impl Fn(char, Option<char>) -> bool) -> usize {
+    (start..chars.len())
+        .find(|&i| end(chars[i], chars.get(i + 1).copied()))
+        .map_or(chars.len(), |i| i + 1)
+}
+
+/// Parameter markers and dialect mappings replaced in SQL before it's parsed
+pub struct Substitutions {
+    /// Styles of the `param_markers` that are patterns, like `:name` for all named markers
+    styles: Vec<ParamStyle>,
+    /// Literal tokens with their replacement, longest first
+    tokens: Vec<(String, String)>,
+}
+
+impl Substitutions {
+    pub fn new(mut mappings: HashMap<String, String>, param_markers: &[String]) -> Self {
+        let mut styles = Vec::new();
+        for marker in param_markers {
+            match pattern_style(marker) {
+                Some(style) => styles.push(style),
+                None => {
+                    mappings.insert(marker.clone(), PLACEHOLDER.to_string());
+                }
+            }
+        }
+        let mut tokens: Vec<(String, String)> = mappings
+            .into_iter()
+            .filter(|(from, _)| !from.is_empty())
+            .collect();
+        // Deterministic, the longest of overlapping tokens is replaced
+        tokens.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        Self { styles, tokens }
+    }
+
+    /// Replacements of `sql` in order, outside of literals and comments. Markers of `styles`
+    /// and of the configured patterns are filled first, tokens only match whole words.
+    pub fn find(&self, sql: &str, styles: &[ParamStyle]) -> Vec<(Range<usize>, &str)> {
+        let mut markers = param_markers(sql)
+            .into_iter()
+            .filter(|marker| styles.contains(&marker.style) || self.styles.contains(&marker.style))
+            .map(|marker| marker.range)
+            .peekable();
+        let mut replacements = Vec::new();
+
+        for span in code_spans(sql) {
+            let mut offset = span.start;
+            while offset < span.end {
+                while markers.next_if(|marker| marker.start < offset).is_some() {}
+                if let Some(marker) = markers.next_if(|marker| marker.start == offset) {
+                    offset = marker.end;
+                    replacements.push((marker, PLACEHOLDER));
+                } else if let Some((from, to)) = self
+                    .tokens
+                    .iter()
+                    .find(|(from, _)| token_at(&sql[..span.end], offset, from))
+                {
+                    replacements.push((offset..offset + from.len(), to.as_str()));
+                    offset += from.len();
+                } else {
+                    offset += sql[offset..].chars().next().map_or(1, char::len_utf8);
+                }
+            }
+        }
+        replacements
+    }
+}
+
+/// Style of a parameter marker given as the pattern for all markers of a style, like `$1`
+fn pattern_style(marker: &str) -> Option<ParamStyle> {
+    match param_markers(marker).as_slice() {
+        [only] if only.range == (0..marker.len()) => Some(only.style),
+        _ => None,
+    }
+}
+
+// Whether `token` is at `offset` of `code`, not as part of a longer word
+fn token_at(code: &str, offset: usize, token: &str) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let starts_word = token.starts_with(is_word);
+    let ends_word = token.ends_with(is_word);
+    code[offset..].starts_with(token)
+        && !(starts_word && code[..offset].ends_with(is_word))
+        && !(ends_word && code[offset + token.len()..].starts_with(is_word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn substitutions(mappings: &[(&str, &str)], markers: &[&str]) -> Substitutions {
+        Substitutions::new(
+            mappings
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect(),
+            &markers.iter().map(|m| (*m).to_string()).collect::<Vec<_>>(),
+        )
+    }
+
+    fn replaced(substitutions: &Substitutions, sql: &str) -> Vec<(String, String)> {
+        substitutions
+            .find(sql, &[])
+            .into_iter()
+            .map(|(range, to)| (sql[range].to_string(), to.to_string()))
+            .collect()
+    }
+
+    fn pair(from: &str, to: &str) -> (String, String) {
+        (from.to_string(), to.to_string())
+    }
+
+    #[test]
+    fn code_outside_literals_and_comments() {
+        let sql = "SELECT 'a?' FROM t -- b?\nWHERE c = ? /* d? */ AND e = \"f?\"";
+        let spans: Vec<&str> = code_spans(sql).into_iter().map(|s| &sql[s]).collect();
+        assert_eq!(
+            spans,
+            vec!["SELECT ", " FROM t ", "WHERE c = ? ", " AND e = "]
+        );
+        assert_eq!(
+            replaced(&substitutions(&[], &["?"]), sql),
+            vec![pair("?", PLACEHOLDER)]
+        );
+    }
+
+    #[test]
+    fn tokens_match_whole_words() {
+        let subs = substitutions(&[("ISNULL", "IS NULL")], &[]);
+        assert_eq!(
+            replaced(&subs, "SELECT MYISNULLCOL, ISNULL_X FROM t WHERE a ISNULL"),
+            vec![pair("ISNULL", "IS NULL")]
+        );
+        assert_eq!(
+            replaced(&subs, "SELECT * FROM t WHERE a ISNULL"),
+            vec![pair("ISNULL", "IS NULL")]
+        );
+    }
+
+    #[test]
+    fn longest_overlapping_token_wins() {
+        let subs = substitutions(&[("NOT NULL", "X"), ("NULL", "Y"), ("@@", "Z")], &["@@p"]);
+        assert_eq!(
+            replaced(&subs, "a NOT NULL b NULL c @@p d @@"),
+            vec![
+                pair("NOT NULL", "X"),
+                pair("NULL", "Y"),
+                pair("@@p", PLACEHOLDER),
+                pair("@@", "Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn param_markers_as_patterns() {
+        let subs = substitutions(&[], &["$1", ":name", "%(name)s"]);
+        assert_eq!(
+            replaced(
+                &subs,
+                "SELECT a::int FROM t WHERE a = $1 AND b = $12 AND c = :c AND d = %(d)s AND e = '$1'"
+            ),
+            vec![
+                pair("$1", PLACEHOLDER),
+                pair("$12", PLACEHOLDER),
+                pair(":c", PLACEHOLDER),
+                pair("%(d)s", PLACEHOLDER),
+            ]
+        );
+        assert_eq!(pattern_style("?"), Some(ParamStyle::Qmark));
+        assert_eq!(pattern_style("%s"), Some(ParamStyle::Format));
+        assert_eq!(pattern_style("@p"), None);
+    }
+}
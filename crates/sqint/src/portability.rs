@@ -0,0 +1,164 @@
+use finder::SqlExtract;
+use serde::Serialize;
+use std::io::{self, Write};
+
+use crate::analyzer::SqlAnalyzer;
+use crate::cli::PortabilityFormat;
+use crate::report::Location;
+
+/// SQL string written by `sqint portability`, with the target dialects that parse it
+#[derive(Debug, Serialize)]
+pub struct Record {
+    #[serde(flatten)]
+    location: Location,
+    accepted: Vec<&'static str>,
+    rejected: Vec<Rejection>,
+}
+
+#[derive(Debug, Serialize)]
+struct Rejection {
+    dialect: &'static str,
+    reason: String,
+}
+
+impl Record {
+    pub const fn is_portable(&self) -> bool {
+        self.rejected.is_empty()
+    }
+}
+
+/// Parses the SQL strings of a file in the dialect of each analyzer
+pub fn records(extract: &SqlExtract, analyzers: &[SqlAnalyzer]) -> Vec<Record> {
+    extract
+        .strings
+        .iter()
+        .map(|sql_string| {
+            let mut record = Record {
+                location: Location::of(extract, sql_string),
+                accepted: Vec::new(),
+                rejected: Vec::new(),
+            };
+            for analyzer in analyzers {
+                let dialect = analyzer.dialect().name();
+                match analyzer.syntax_error(sql_string) {
+                    Some(reason) => record.rejected.push(Rejection { dialect, reason }),
+                    None => record.accepted.push(dialect),
+                }
+            }
+            record
+        })
+        .collect()
+}
+
+/// Writes the records to stdout
+pub fn write(records: &[Record], format: PortabilityFormat) -> io::Result<()> {
+    write_to(&mut io::stdout().lock(), records, format)
+}
+
+fn write_to(out: &mut impl Write, records: &[Record], format: PortabilityFormat) -> io::Result<()> {
+    for record in records {
+        match format {
+            PortabilityFormat::Jsonl => serde_json::to_writer(&mut *out, record)?,
+            PortabilityFormat::Text => {
+                let mut parts = Vec::new();
+                if !record.accepted.is_empty() {
+                    parts.push(format!("accepted by {}", record.accepted.join(", ")));
+                }
+                parts.extend(
+                    record
+                        .rejected
+                        .iter()
+                        .map(|r| format!("rejected by {} ({})", r.dialect, r.reason)),
+                );
+                write!(
+                    out,
+                    "{}::{}: {}",
+                    record.location,
+                    record.location.name,
+                    parts.join("; ")
+                )?;
+            }
+        }
+        writeln!(out)?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::dialect::SqlDialect;
+    use crate::rules::RuleSet;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn portability(format: PortabilityFormat, source: &str) -> String {
+        portability_with(&Config::default(), format, source)
+    }
+
+    fn portability_with(config: &Config, format: PortabilityFormat, source: &str) -> String {
+        let finder_config = finder::FinderConfig::new(&["*query*".to_string()], &[]);
+        let extract = finder::SqlFinder::new(Arc::new(finder_config))
+            .analyze_source("app/db.py", source, false)
+            .unwrap();
+        let rules = Arc::new(RuleSet::new(config, None));
+        let analyzers: Vec<SqlAnalyzer> = [SqlDialect::PostgreSQL, SqlDialect::MySql]
+            .iter()
+            .map(|dialect| SqlAnalyzer::new(dialect, HashMap::new(), &[], rules.clone()))
+            .collect();
+
+        let mut out = Vec::new();
+        write_to(&mut out, &records(&extract, &analyzers), format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    const SOURCE: &str = r#"
+query = "SELECT id FROM users LIMIT 10"
+other_query = "SELECT `id` FROM users"
+"#;
+
+    #[test]
+    fn text_lines() {
+        let out = portability(PortabilityFormat::Text, SOURCE);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[0],
+            "app/db.py:2:9::query: accepted by postgres, mysql"
+        );
+        assert!(
+            lines[1].starts_with(
+                "app/db.py:3:15::other_query: accepted by mysql; rejected by postgres ("
+            ),
+            "{}",
+            lines[1]
+        );
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn json_lines() {
+        let out = portability(PortabilityFormat::Jsonl, SOURCE);
+        let records: Vec<serde_json::Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            records[0]["accepted"],
+            serde_json::json!(["postgres", "mysql"])
+        );
+        assert_eq!(records[0]["rejected"], serde_json::json!([]));
+        assert_eq!(records[1]["accepted"], serde_json::json!(["mysql"]));
+        assert_eq!(records[1]["rejected"][0]["dialect"], "postgres");
+    }
+
+    #[test]
+    fn rule_levels_dont_change_what_parses() {
+        let mut config = Config::default();
+        config
+            .rules
+            .insert("SQ001".to_string(), crate::config::RuleLevel::Off);
+        let out = portability_with(&config, PortabilityFormat::Text, SOURCE);
+        assert!(out.contains("rejected by postgres ("), "{out}");
+    }
+}
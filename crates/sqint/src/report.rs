@@ -0,0 +1,457 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+use finder::{SqlExtract, SqlString};
+use logging::{Logger, always_log, error, info, warn};
+use serde::Serialize;
+
+use crate::config::OutputFormat;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// Every kind of finding sqint can report, one per rule
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticKind {
+    SyntaxError,
+    UnknownTable,
+    UnknownColumn,
+    AmbiguousColumn,
+    InsertArity,
+    UpdateWithoutWhere,
+    DeleteWithoutWhere,
+    Truncate,
+    DropTable,
+    DropDatabase,
+    DropColumn,
+    SqlInjection,
+    ParamMismatch,
+}
+
+impl DiagnosticKind {
+    pub const ALL: [Self; 13] = [
+        Self::SyntaxError,
+        Self::UnknownTable,
+        Self::UnknownColumn,
+        Self::AmbiguousColumn,
+        Self::InsertArity,
+        Self::UpdateWithoutWhere,
+        Self::DeleteWithoutWhere,
+        Self::Truncate,
+        Self::DropTable,
+        Self::DropDatabase,
+        Self::DropColumn,
+        Self::SqlInjection,
+        Self::ParamMismatch,
+    ];
+
+    /// Stable code of the rule reporting this kind, never reused once released
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::SyntaxError => "SQ001",
+            Self::UnknownTable => "SQ002",
+            Self::UnknownColumn => "SQ003",
+            Self::AmbiguousColumn => "SQ004",
+            Self::InsertArity => "SQ005",
+            Self::UpdateWithoutWhere => "SQ006",
+            Self::DeleteWithoutWhere => "SQ007",
+            Self::Truncate => "SQ008",
+            Self::DropTable => "SQ009",
+            Self::DropDatabase => "SQ010",
+            Self::DropColumn => "SQ011",
+            Self::SqlInjection => "SQ012",
+            Self::ParamMismatch => "SQ013",
+        }
+    }
+
+    pub const fn id(self) -> &'static str {
+        match self {
+            Self::SyntaxError => "syntax-error",
+            Self::UnknownTable => "unknown-table",
+            Self::UnknownColumn => "unknown-column",
+            Self::AmbiguousColumn => "ambiguous-column",
+            Self::InsertArity => "insert-arity",
+            Self::UpdateWithoutWhere => "update-without-where",
+            Self::DeleteWithoutWhere => "delete-without-where",
+            Self::Truncate => "truncate",
+            Self::DropTable => "drop-table",
+            Self::DropDatabase => "drop-database",
+            Self::DropColumn => "drop-column",
+            Self::SqlInjection => "sql-injection",
+            Self::ParamMismatch => "param-mismatch",
+        }
+    }
+
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::SyntaxError => "SQL string can't be parsed in a configured dialect",
+            Self::UnknownTable => "Table or view is not declared in the schema files",
+            Self::UnknownColumn => "Column does not exist in the table it is looked up in",
+            Self::AmbiguousColumn => "Unqualified column exists in more than one joined table",
+            Self::InsertArity => "INSERT gives a different number of values than it has columns",
+            Self::UpdateWithoutWhere => "UPDATE without a WHERE clause changes every row",
+            Self::DeleteWithoutWhere => "DELETE without a WHERE clause removes every row",
+            Self::Truncate => "TRUNCATE removes every row of a table",
+            Self::DropTable => "DROP TABLE outside of migrations",
+            Self::DropDatabase => "DROP DATABASE outside of migrations",
+            Self::DropColumn => "ALTER TABLE ... DROP COLUMN outside of migrations",
+            Self::SqlInjection => {
+                "Runtime value is spliced into the SQL instead of bound as a parameter"
+            }
+            Self::ParamMismatch => {
+                "Parameters passed with the SQL don't match its parameter markers"
+            }
+        }
+    }
+
+    /// Severity findings are reported at unless configured otherwise
+    pub const fn default_severity(self) -> Severity {
+        match self {
+            Self::SyntaxError
+            | Self::UnknownTable
+            | Self::UnknownColumn
+            | Self::AmbiguousColumn
+            | Self::InsertArity
+            | Self::UpdateWithoutWhere
+            | Self::DeleteWithoutWhere
+            | Self::Truncate
+            | Self::DropTable
+            | Self::DropDatabase
+            | Self::DropColumn
+            | Self::SqlInjection
+            | Self::ParamMismatch => Severity::Error,
+        }
+    }
+
+    /// Looks a kind up by its code or id, as used in the `[rules]` table.
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.code().eq_ignore_ascii_case(key) || kind.id() == key)
+    }
+}
+
+/// Where a SQL string or a finding in it is, and the name the SQL string was found in
+#[derive(Debug, Clone, Serialize)]
+pub struct Location {
+    pub file: String,
+    /// Code cell of a notebook, counted from 1, that `line` is in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cell: Option<usize>,
+    pub line: usize,
+    pub col: usize,
+    /// Variable or function name the SQL string was found in
+    pub name: String,
+}
+
+impl Location {
+    /// Start of a SQL string of the file
+    pub fn of(extract: &SqlExtract, sql_string: &SqlString) -> Self {
+        let start = &sql_string.range.start;
+        let (cell, line) = extract.locate(start.line());
+        Self {
+            file: extract.rel_path.clone(),
+            cell,
+            line,
+            col: start.col(),
+            name: sql_string.variable_name.clone(),
+        }
+    }
+}
+
+/// `file:line:col`, or `file:cell N:line:col` in notebooks
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.cell {
+            Some(cell) => write!(f, "{}:cell {cell}:{}:{}", self.file, self.line, self.col),
+            None => write!(f, "{}:{}:{}", self.file, self.line, self.col),
+        }
+    }
+}
+
+/// Finding in a single SQL string, or its confirmation as valid when nothing was found
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    #[serde(flatten)]
+    pub location: Location,
+    /// SQL after placeholders and dialect mappings were filled in
+    pub sql: String,
+    /// Source text of the Python expression or raw SQL the SQL was found in
+    #[serde(skip)]
+    pub source: String,
+    pub dialect: &'static str,
+    pub severity: Severity,
+    /// What was found, `None` for valid SQL
+    pub kind: Option<DiagnosticKind>,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum JsonRecord<'a> {
+    Sql {
+        code: Option<&'static str>,
+        #[serde(flatten)]
+        diagnostic: &'a Diagnostic,
+    },
+    Summary {
+        sql_total: u64,
+        sql_errors: u64,
+        sql_warnings: u64,
+    },
+}
+
+impl<'a> JsonRecord<'a> {
+    fn sql(diagnostic: &'a Diagnostic) -> Self {
+        Self::Sql {
+            code: diagnostic.kind.map(DiagnosticKind::code),
+            diagnostic,
+        }
+    }
+}
+
+/// Reports the findings of the checked files in the output format
+pub struct Reporter {
+    format: OutputFormat,
+    // SARIF is a single document, so results are collected until the summary is reported
+    sarif_results: Mutex<Vec<Diagnostic>>,
+}
+
+impl Reporter {
+    pub const fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            sarif_results: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Reports the diagnostics of `checked` SQL strings, a string can have several findings.
+    pub fn report(&self, checked: usize, diagnostics: &[Diagnostic]) {
+        count(checked, diagnostics);
+        match self.format {
+            OutputFormat::Text => diagnostics.iter().for_each(report_text),
+            OutputFormat::Json => write_json_lines(diagnostics.iter().map(JsonRecord::sql)),
+            OutputFormat::Sarif => {
+                self.sarif_results
+                    .lock()
+                    .expect("Reporting threads don't panic while holding the lock")
+                    .extend(diagnostics.iter().filter(|d| d.kind.is_some()).cloned());
+            }
+        }
+    }
+
+    /// Reports the totals over all checked files, once everything was reported.
+    pub fn report_summary(self) {
+        let sql_errors = Logger::get_sql_error_count();
+        let sql_warnings = Logger::get_sql_warning_count();
+        let sql_total = Logger::get_sql_var_count();
+
+        match self.format {
+            OutputFormat::Text => {
+                if sql_total > 0 && sql_warnings > 0 {
+                    always_log!(
+                        "Sqint: {} sql strings checked, {} errors and {} warnings found",
+                        sql_total,
+                        sql_errors,
+                        sql_warnings
+                    );
+                } else if sql_total > 0 {
+                    always_log!(
+                        "Sqint: {} sql strings checked, {} errors found",
+                        sql_total,
+                        sql_errors
+                    );
+                }
+            }
+            OutputFormat::Json => write_json_lines(std::iter::once(JsonRecord::Summary {
+                sql_total,
+                sql_errors,
+                sql_warnings,
+            })),
+            OutputFormat::Sarif => {
+                let results = self
+                    .sarif_results
+                    .into_inner()
+                    .expect("Reporting threads don't panic while holding the lock");
+                match serde_json::to_string_pretty(&crate::sarif::sarif_log(&results)) {
+                    Ok(log) => {
+                        let _ = writeln!(io::stdout().lock(), "{log}");
+                    }
+                    Err(e) => error!("Failed to serialize SARIF log: {e}"),
+                }
+            }
+        }
+    }
+}
+
+fn report_text(d: &Diagnostic) {
+    let (Some(kind), Some(reason)) = (d.kind, &d.reason) else {
+        info!(
+            "./{}::{}: Valid sql string: `{}`",
+            d.location, d.location.name, d.sql
+        );
+        return;
+    };
+
+    let code = kind.code();
+    match d.severity {
+        Severity::Error => error!(
+            "./{}::{}: `{}` => {code} {reason}",
+            d.location, d.location.name, d.sql
+        ),
+        Severity::Warning => warn!(
+            "./{}::{}: `{}` => {code} {reason}",
+            d.location, d.location.name, d.sql
+        ),
+        Severity::Info => info!(
+            "./{}::{}: `{}` => {code} {reason}",
+            d.location, d.location.name, d.sql
+        ),
+    }
+}
+
+fn count(checked: usize, diagnostics: &[Diagnostic]) {
+    (0..checked).for_each(|_| Logger::increment_sql_var_count());
+    for d in diagnostics {
+        match d.severity {
+            Severity::Error => {
+                Logger::increment_sql_error_count();
+                Logger::set_error_occurred();
+            }
+            Severity::Warning => Logger::increment_sql_warning_count(),
+            Severity::Info => {}
+        }
+    }
+}
+
+fn write_json_lines<'a>(records: impl Iterator<Item = JsonRecord<'a>>) {
+    // Holding the lock keeps the records of one file together when checking in parallel
+    let mut out = io::stdout().lock();
+    for record in records {
+        match serde_json::to_string(&record) {
+            Ok(line) => {
+                let _ = writeln!(out, "{line}");
+            }
+            Err(e) => error!("Failed to serialize report record: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_record_shape() {
+        let diagnostic = Diagnostic {
+            location: Location {
+                file: "app/db.py".to_string(),
+                cell: None,
+                line: 12,
+                col: 9,
+                name: "query".to_string(),
+            },
+            sql: "SELECT * FORM users".to_string(),
+            source: "\"SELECT * FORM users\"".to_string(),
+            dialect: "postgres",
+            severity: Severity::Error,
+            kind: Some(DiagnosticKind::SyntaxError),
+            reason: Some("Expected: end of statement, found: users".to_string()),
+        };
+
+        let value = serde_json::to_value(JsonRecord::sql(&diagnostic)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "type": "sql",
+                "file": "app/db.py",
+                "line": 12,
+                "col": 9,
+                "name": "query",
+                "sql": "SELECT * FORM users",
+                "dialect": "postgres",
+                "severity": "error",
+                "kind": "syntax-error",
+                "code": "SQ001",
+                "reason": "Expected: end of statement, found: users",
+            })
+        );
+    }
+
+    #[test]
+    fn notebook_cell_location() {
+        let mut diagnostic = Diagnostic {
+            location: Location {
+                file: "notebooks/report.ipynb".to_string(),
+                cell: Some(3),
+                line: 2,
+                col: 5,
+                name: "%%sql".to_string(),
+            },
+            sql: "SELECT 1".to_string(),
+            source: "SELECT 1".to_string(),
+            dialect: "generic",
+            severity: Severity::Info,
+            kind: None,
+            reason: None,
+        };
+        assert_eq!(
+            diagnostic.location.to_string(),
+            "notebooks/report.ipynb:cell 3:2:5"
+        );
+        assert_eq!(serde_json::to_value(&diagnostic).unwrap()["cell"], 3);
+
+        diagnostic.location.cell = None;
+        assert_eq!(
+            diagnostic.location.to_string(),
+            "notebooks/report.ipynb:2:5"
+        );
+    }
+
+    #[test]
+    fn json_summary_shape() {
+        let value = serde_json::to_value(JsonRecord::Summary {
+            sql_total: 10,
+            sql_errors: 2,
+            sql_warnings: 1,
+        })
+        .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "type": "summary",
+                "sql_total": 10,
+                "sql_errors": 2,
+                "sql_warnings": 1,
+            })
+        );
+    }
+
+    #[test]
+    fn rule_keys() {
+        assert_eq!(
+            DiagnosticKind::from_key("SQ003"),
+            Some(DiagnosticKind::UnknownColumn)
+        );
+        assert_eq!(
+            DiagnosticKind::from_key("sq001"),
+            Some(DiagnosticKind::SyntaxError)
+        );
+        assert_eq!(
+            DiagnosticKind::from_key("insert-arity"),
+            Some(DiagnosticKind::InsertArity)
+        );
+        assert_eq!(DiagnosticKind::from_key("SQ999"), None);
+
+        let codes: std::collections::HashSet<_> =
+            DiagnosticKind::ALL.iter().map(|k| k.code()).collect();
+        assert_eq!(codes.len(), DiagnosticKind::ALL.len());
+    }
+}
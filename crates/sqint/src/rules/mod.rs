@@ -0,0 +1,235 @@
+mod params;
+mod safety;
+mod schema;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use finder::SqlString;
+use globset::GlobSet;
+use logging::warn;
+use sqlparser::ast::Statement;
+use sqlparser::tokenizer::Span;
+
+use crate::config::{Config, RuleLevel};
+use crate::report::{DiagnosticKind, Severity};
+use crate::schema::Catalog;
+
+/// Finding of a rule in a single statement
+#[derive(Debug)]
+pub struct Violation {
+    pub kind: DiagnosticKind,
+    pub reason: String,
+    // 1-based line and column within the checked SQL
+    pub location: Option<(u64, u64)>,
+}
+
+impl Violation {
+    /// Finding pointing at the start of `span`, or at the whole SQL string if the span is empty.
+    pub fn new(kind: DiagnosticKind, reason: String, span: Span) -> Self {
+        Self {
+            kind,
+            reason,
+            location: (span.start.line > 0).then_some((span.start.line, span.start.column)),
+        }
+    }
+}
+
+/// Check run on every SQL string that parsed, on each of its statements or on the string as a whole.
+pub trait Rule: Send + Sync {
+    /// Kinds of finding the rule reports, each carrying its code and default severity. The
+    /// findings of kinds that are turned off are dropped.
+    fn kinds(&self) -> &[DiagnosticKind];
+
+    /// Whether the rule also checks SQL in migration files, where schema changes are expected
+    fn in_migrations(&self) -> bool {
+        true
+    }
+
+    fn check(&self, _statement: &Statement) -> Vec<Violation> {
+        Vec::new()
+    }
+
+    /// Findings in the SQL string as a whole, checked once whatever number of statements it has
+    fn check_sql_string(&self, _sql_string: &SqlString) -> Vec<Violation> {
+        Vec::new()
+    }
+}
+
+/// The enabled rules and the severity each kind of finding is reported at.
+/// Syntax errors (SQ001) and values spliced into the SQL (SQ012) are found while extracting and
+/// parsing the SQL rather than by a rule, but are configured the same way.
+pub struct RuleSet {
+    rules: Vec<Box<dyn Rule>>,
+    severities: HashMap<DiagnosticKind, Severity>,
+    migrations: GlobSet,
+}
+
+impl RuleSet {
+    pub fn new(config: &Config, catalog: Option<&Arc<Catalog>>) -> Self {
+        let mut severities: HashMap<DiagnosticKind, Severity> = DiagnosticKind::ALL
+            .iter()
+            .map(|kind| (*kind, kind.default_severity()))
+            .collect();
+        for (key, level) in &config.rules {
+            let Some(kind) = DiagnosticKind::from_key(key) else {
+                warn!("Unknown rule '{key}' in [rules], expected a code like SQ001 or a rule name");
+                continue;
+            };
+            match level {
+                RuleLevel::Off => severities.remove(&kind),
+                RuleLevel::Warning => severities.insert(kind, Severity::Warning),
+                RuleLevel::Error => severities.insert(kind, Severity::Error),
+            };
+        }
+
+        let mut rules: Vec<Box<dyn Rule>> =
+            vec![Box::new(safety::SafetyRule), Box::new(params::ParamsRule)];
+        if let Some(catalog) = catalog {
+            rules.push(Box::new(schema::SchemaRule::new(catalog.clone())));
+        }
+        rules.retain(|rule| {
+            rule.kinds()
+                .iter()
+                .any(|kind| severities.contains_key(kind))
+        });
+
+        Self {
+            rules,
+            severities,
+            migrations: crate::files::slice_to_glob(
+                &config.migration_patterns,
+                "migration_patterns",
+            ),
+        }
+    }
+
+    /// Severity findings of `kind` are reported at, `None` if its rule is off
+    pub fn severity(&self, kind: DiagnosticKind) -> Option<Severity> {
+        self.severities.get(&kind).copied()
+    }
+
+    pub fn is_migration(&self, path: &str) -> bool {
+        self.migrations.is_match(path)
+    }
+
+    /// Findings in the statements a SQL string parsed into
+    pub fn check(
+        &self,
+        statements: &[Statement],
+        sql_string: &SqlString,
+        migration: bool,
+    ) -> Vec<Violation> {
+        let rules: Vec<&dyn Rule> = self
+            .rules
+            .iter()
+            .filter(|rule| !migration || rule.in_migrations())
+            .map(AsRef::as_ref)
+            .collect();
+        statements
+            .iter()
+            .flat_map(|statement| rules.iter().flat_map(move |rule| rule.check(statement)))
+            .chain(
+                rules
+                    .iter()
+                    .flat_map(|rule| rule.check_sql_string(sql_string)),
+            )
+            .filter(|violation| self.severities.contains_key(&violation.kind))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sql_string(sql: &str) -> SqlString {
+        SqlString::new(
+            "sql".to_string(),
+            sql.to_string(),
+            finder::preanalysis::Range::verbatim(finder::LineCol::new(1, 1, 0), ""),
+        )
+    }
+
+    fn parsed(sql: &str) -> Vec<Statement> {
+        sqlparser::parser::Parser::parse_sql(&sqlparser::dialect::GenericDialect {}, sql).unwrap()
+    }
+
+    fn kinds_found(rules: &RuleSet, sql: &str) -> Vec<DiagnosticKind> {
+        rules
+            .check(&parsed(sql), &sql_string(sql), false)
+            .into_iter()
+            .map(|violation| violation.kind)
+            .collect()
+    }
+
+    #[test]
+    fn configured_levels() {
+        let config = Config {
+            rules: HashMap::from([
+                ("SQ001".to_string(), RuleLevel::Warning),
+                ("unknown-column".to_string(), RuleLevel::Off),
+                ("drop-column".to_string(), RuleLevel::Off),
+                ("SQ999".to_string(), RuleLevel::Error),
+            ]),
+            ..Config::default()
+        };
+        let mut catalog = Catalog::default();
+        catalog.add_ddl(
+            "CREATE TABLE users (id INT)",
+            &sqlparser::dialect::GenericDialect {},
+            "schema.sql",
+        );
+        let rules = RuleSet::new(&config, Some(&Arc::new(catalog)));
+
+        assert_eq!(
+            rules.severity(DiagnosticKind::SyntaxError),
+            Some(Severity::Warning)
+        );
+        assert_eq!(rules.severity(DiagnosticKind::UnknownColumn), None);
+        assert_eq!(
+            rules.severity(DiagnosticKind::UnknownTable),
+            Some(Severity::Error)
+        );
+        // The safety, parameter and schema rules, each with some of its kinds still on
+        assert_eq!(rules.rules.len(), 3);
+        assert!(kinds_found(&rules, "SELECT email FROM users").is_empty());
+        assert_eq!(
+            kinds_found(&rules, "SELECT id FROM orders"),
+            vec![DiagnosticKind::UnknownTable]
+        );
+        assert!(kinds_found(&rules, "ALTER TABLE users DROP COLUMN id").is_empty());
+        assert_eq!(
+            kinds_found(&rules, "DROP TABLE users"),
+            vec![DiagnosticKind::DropTable]
+        );
+    }
+
+    #[test]
+    fn safety_rules_skip_migrations() {
+        let rules = RuleSet::new(&Config::default(), None);
+        assert!(rules.is_migration("project/app/migrations/0002_drop_email.py"));
+        assert!(!rules.is_migration("project/app/models.py"));
+
+        let sql = "ALTER TABLE users DROP COLUMN email";
+        let statements = parsed(sql);
+        let sql_string = sql_string(sql);
+        assert_eq!(rules.check(&statements, &sql_string, false).len(), 1);
+        assert!(rules.check(&statements, &sql_string, true).is_empty());
+    }
+
+    #[test]
+    fn sql_string_checked_once() {
+        let rules = RuleSet::new(&Config::default(), None);
+        let sql = "UPDATE t SET a = ? WHERE id = 1; SELECT a FROM t WHERE id = ?";
+        let mut sql_string = sql_string(sql);
+        sql_string.params = Some(finder::CallParams::Positional(1));
+        sql_string.param_styles = vec![finder::ParamStyle::Qmark];
+        let kinds: Vec<DiagnosticKind> = rules
+            .check(&parsed(sql), &sql_string, false)
+            .into_iter()
+            .map(|violation| violation.kind)
+            .collect();
+        assert_eq!(kinds, vec![DiagnosticKind::ParamMismatch]);
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeSet;
+
+use finder::{CallParams, ParamStyle, SqlString};
+use sqlparser::tokenizer::Span;
+
+use super::{Rule, Violation};
+use crate::placeholders::param_markers;
+use crate::report::DiagnosticKind;
+
+/// Compares the parameter markers of the SQL with the literal parameters passed along with it,
+/// e.g. `cursor.execute("... WHERE id = ?", (a, b))`. Only the markers of the styles the
+/// function takes are counted.
+pub struct ParamsRule;
+
+impl Rule for ParamsRule {
+    fn kinds(&self) -> &[DiagnosticKind] {
+        &[DiagnosticKind::ParamMismatch]
+    }
+
+    fn check_sql_string(&self, sql_string: &SqlString) -> Vec<Violation> {
+        let Some(params) = &sql_string.params else {
+            return Vec::new();
+        };
+        mismatches(
+            &Markers::scan(&sql_string.sql_content, &sql_string.param_styles),
+            params,
+        )
+        .into_iter()
+        .map(|reason| Violation::new(DiagnosticKind::ParamMismatch, reason, Span::empty()))
+        .collect()
+    }
+}
+
+/// Parameter markers of SQL text, counted by how they take their values
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Markers {
+    /// `?` and `%s`, each taking the next value
+    positional: usize,
+    /// Highest `$n`, a number can be used several times
+    numbered: usize,
+    /// `:name` and `%(name)s`
+    named: BTreeSet<String>,
+}
+
+impl Markers {
+    fn scan(sql: &str, styles: &[ParamStyle]) -> Self {
+        param_markers(sql)
+            .into_iter()
+            .filter(|marker| styles.contains(&marker.style))
+            .fold(Self::default(), |mut markers, marker| {
+                match (marker.style, marker.name) {
+                    (ParamStyle::Dollar, Some(digits)) => {
+                        if let Ok(n) = digits.parse::<usize>() {
+                            markers.numbered = markers.numbered.max(n);
+                        }
+                    }
+                    (_, Some(name)) => {
+                        markers.named.insert(name);
+                    }
+                    (_, None) => markers.positional += 1,
+                }
+                markers
+            })
+    }
+
+    fn positional_count(&self) -> usize {
+        self.positional + self.numbered
+    }
+}
+
+fn mismatches(markers: &Markers, params: &CallParams) -> Vec<String> {
+    let positional = markers.positional_count();
+    // Mixed marker styles are rejected by every driver, there is nothing sensible to compare
+    if positional > 0 && !markers.named.is_empty() {
+        return Vec::new();
+    }
+
+    match params {
+        CallParams::Positional(count) if !markers.named.is_empty() => vec![format!(
+            "SQL uses named parameters but the call passes {}",
+            counted(*count, "positional value")
+        )],
+        CallParams::Positional(count) if *count != positional => vec![format!(
+            "SQL has {} but the call passes {}",
+            counted(positional, "parameter marker"),
+            counted(*count, "value")
+        )],
+        CallParams::Positional(_) => Vec::new(),
+        CallParams::Named(_) if positional > 0 => vec![format!(
+            "SQL has {} but the call passes a dict",
+            counted(positional, "positional parameter marker")
+        )],
+        CallParams::Named(keys) => {
+            let keys: BTreeSet<&String> = keys.iter().collect();
+            let missing = markers
+                .named
+                .iter()
+                .filter(|name| !keys.contains(name))
+                .map(|name| format!("Named parameter `{name}` has no value"));
+            let unused = keys
+                .iter()
+                .filter(|key| !markers.named.contains(**key))
+                .map(|key| format!("Value `{key}` is passed but not used in the SQL"));
+            missing.chain(unused).collect()
+        }
+    }
+}
+
+fn counted(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STYLES: &[ParamStyle] = &[
+        ParamStyle::Qmark,
+        ParamStyle::Named,
+        ParamStyle::Format,
+        ParamStyle::Pyformat,
+        ParamStyle::Dollar,
+    ];
+
+    fn named(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|n| (*n).to_string()).collect()
+    }
+
+    #[test]
+    fn scan_markers() {
+        assert_eq!(
+            Markers::scan(
+                "SELECT * FROM t WHERE a = ? AND b = '?' AND c = %s -- ?\n AND d LIKE 'x%%'",
+                STYLES
+            ),
+            Markers {
+                positional: 2,
+                ..Markers::default()
+            }
+        );
+        assert_eq!(
+            Markers::scan("UPDATE t SET a = $1, b = $2 WHERE id = $1 /* $3 */", STYLES),
+            Markers {
+                numbered: 2,
+                ..Markers::default()
+            }
+        );
+        assert_eq!(
+            Markers::scan(
+                "SELECT a::int FROM t WHERE id = :id AND ts > '12:30' AND k = %(key)s AND j = :id",
+                STYLES
+            ),
+            Markers {
+                named: named(&["id", "key"]),
+                ..Markers::default()
+            }
+        );
+    }
+
+    #[test]
+    fn only_markers_of_the_styles() {
+        // `?` is a JSON operator in PostgreSQL, where asyncpg takes `$n`
+        let sql = "SELECT * FROM t WHERE data ? 'key' AND id = $1";
+        assert_eq!(
+            Markers::scan(sql, &[ParamStyle::Dollar]),
+            Markers {
+                numbered: 1,
+                ..Markers::default()
+            }
+        );
+        assert!(
+            mismatches(
+                &Markers::scan(sql, &[ParamStyle::Dollar]),
+                &CallParams::Positional(1)
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn positional_mismatch() {
+        let markers = Markers::scan("INSERT INTO t (a, b, c) VALUES (?, ?, ?)", STYLES);
+        assert_eq!(
+            mismatches(&markers, &CallParams::Positional(2)),
+            vec!["SQL has 3 parameter markers but the call passes 2 values"]
+        );
+        assert!(mismatches(&markers, &CallParams::Positional(3)).is_empty());
+        assert_eq!(
+            mismatches(&markers, &CallParams::Named(vec!["a".to_string()])),
+            vec!["SQL has 3 positional parameter markers but the call passes a dict"]
+        );
+    }
+
+    #[test]
+    fn named_mismatch() {
+        let markers = Markers::scan("SELECT * FROM t WHERE a = :a AND b = :b", STYLES);
+        assert_eq!(
+            mismatches(
+                &markers,
+                &CallParams::Named(vec!["a".to_string(), "c".to_string()])
+            ),
+            vec![
+                "Named parameter `b` has no value",
+                "Value `c` is passed but not used in the SQL",
+            ]
+        );
+        assert_eq!(
+            mismatches(&markers, &CallParams::Positional(2)),
+            vec!["SQL uses named parameters but the call passes 2 positional values"]
+        );
+    }
+}
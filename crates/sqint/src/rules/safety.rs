@@ -0,0 +1,185 @@
+use sqlparser::ast::{
+    AlterTableOperation, FromTable, ObjectType, SetExpr, Spanned, Statement, TableFactor,
+    TableWithJoins,
+};
+
+use super::{Rule, Violation};
+use crate::report::DiagnosticKind;
+
+/// Flags statements that destroy data or schema. Migrations are expected to do that, so the
+/// rule only runs on application code.
+pub struct SafetyRule;
+
+impl Rule for SafetyRule {
+    fn kinds(&self) -> &[DiagnosticKind] {
+        &[
+            DiagnosticKind::UpdateWithoutWhere,
+            DiagnosticKind::DeleteWithoutWhere,
+            DiagnosticKind::Truncate,
+            DiagnosticKind::DropTable,
+            DiagnosticKind::DropDatabase,
+            DiagnosticKind::DropColumn,
+        ]
+    }
+
+    fn in_migrations(&self) -> bool {
+        false
+    }
+
+    fn check(&self, statement: &Statement) -> Vec<Violation> {
+        dangers(statement)
+    }
+}
+
+fn dangers(statement: &Statement) -> Vec<Violation> {
+    match statement {
+        // UPDATE and DELETE preceded by a WITH clause
+        Statement::Query(query) => match &*query.body {
+            SetExpr::Update(statement) | SetExpr::Delete(statement) => dangers(statement),
+            _ => Vec::new(),
+        },
+        Statement::Update {
+            table,
+            selection: None,
+            ..
+        } => vec![Violation::new(
+            DiagnosticKind::UpdateWithoutWhere,
+            format!(
+                "UPDATE of `{}` has no WHERE clause and changes every row",
+                table_name(table)
+            ),
+            table.relation.span(),
+        )],
+        Statement::Delete(delete) if delete.selection.is_none() => {
+            let (FromTable::WithFromKeyword(tables) | FromTable::WithoutKeyword(tables)) =
+                &delete.from;
+            tables
+                .first()
+                .map(|table| {
+                    Violation::new(
+                        DiagnosticKind::DeleteWithoutWhere,
+                        format!(
+                            "DELETE from `{}` has no WHERE clause and removes every row",
+                            table_name(table)
+                        ),
+                        table.relation.span(),
+                    )
+                })
+                .into_iter()
+                .collect()
+        }
+        Statement::Truncate { table_names, .. } => table_names
+            .iter()
+            .map(|target| {
+                Violation::new(
+                    DiagnosticKind::Truncate,
+                    format!("TRUNCATE removes every row of `{}`", target.name),
+                    target.name.span(),
+                )
+            })
+            .collect(),
+        Statement::Drop {
+            object_type: object_type @ (ObjectType::Table | ObjectType::Database),
+            names,
+            ..
+        } => {
+            let kind = if *object_type == ObjectType::Table {
+                DiagnosticKind::DropTable
+            } else {
+                DiagnosticKind::DropDatabase
+            };
+            names
+                .iter()
+                .map(|name| {
+                    Violation::new(kind, format!("DROP {object_type} `{name}`"), name.span())
+                })
+                .collect()
+        }
+        Statement::AlterTable {
+            name, operations, ..
+        } => operations
+            .iter()
+            .filter_map(|operation| match operation {
+                AlterTableOperation::DropColumn { column_name, .. } => Some(Violation::new(
+                    DiagnosticKind::DropColumn,
+                    format!("ALTER TABLE `{name}` drops column `{column_name}`"),
+                    column_name.span,
+                )),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn table_name(table: &TableWithJoins) -> String {
+    match &table.relation {
+        TableFactor::Table { name, .. } => name.to_string(),
+        relation => relation.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::dialect::GenericDialect;
+    use sqlparser::parser::Parser;
+
+    type Danger = (DiagnosticKind, String, Option<(u64, u64)>);
+
+    fn dangers_in(sql: &str) -> Vec<Danger> {
+        Parser::parse_sql(&GenericDialect {}, sql)
+            .unwrap()
+            .iter()
+            .flat_map(dangers)
+            .map(|v| (v.kind, v.reason, v.location))
+            .collect()
+    }
+
+    #[test]
+    fn statements_without_where() {
+        assert_eq!(
+            dangers_in("UPDATE users SET active = 0"),
+            vec![(
+                DiagnosticKind::UpdateWithoutWhere,
+                "UPDATE of `users` has no WHERE clause and changes every row".to_string(),
+                Some((1, 8))
+            )]
+        );
+        assert_eq!(
+            dangers_in("WITH old AS (SELECT 1) DELETE FROM sessions"),
+            vec![(
+                DiagnosticKind::DeleteWithoutWhere,
+                "DELETE from `sessions` has no WHERE clause and removes every row".to_string(),
+                Some((1, 36))
+            )]
+        );
+        assert!(dangers_in("UPDATE users SET active = 0 WHERE id = 1").is_empty());
+        assert!(dangers_in("DELETE FROM sessions WHERE expires < now()").is_empty());
+    }
+
+    #[test]
+    fn destructive_ddl() {
+        let kinds: Vec<DiagnosticKind> = dangers_in(
+            "TRUNCATE TABLE logs, audit; DROP TABLE IF EXISTS users; DROP DATABASE app; \
+             ALTER TABLE users ADD COLUMN age INT, DROP COLUMN email; DROP VIEW active_users",
+        )
+        .into_iter()
+        .map(|(kind, ..)| kind)
+        .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DiagnosticKind::Truncate,
+                DiagnosticKind::Truncate,
+                DiagnosticKind::DropTable,
+                DiagnosticKind::DropDatabase,
+                DiagnosticKind::DropColumn,
+            ]
+        );
+        assert_eq!(
+            dangers_in("ALTER TABLE users DROP COLUMN email")[0].1,
+            "ALTER TABLE `users` drops column `email`"
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use sqlparser::ast::Statement;
+
+use super::{Rule, Violation};
+use crate::report::DiagnosticKind;
+use crate::schema::Catalog;
+
+/// Checks statements against the catalog of the schema files
+pub struct SchemaRule {
+    catalog: Arc<Catalog>,
+}
+
+impl SchemaRule {
+    pub const fn new(catalog: Arc<Catalog>) -> Self {
+        Self { catalog }
+    }
+}
+
+impl Rule for SchemaRule {
+    fn kinds(&self) -> &[DiagnosticKind] {
+        &[
+            DiagnosticKind::UnknownTable,
+            DiagnosticKind::UnknownColumn,
+            DiagnosticKind::AmbiguousColumn,
+            DiagnosticKind::InsertArity,
+        ]
+    }
+
+    fn check(&self, statement: &Statement) -> Vec<Violation> {
+        self.catalog.check(statement)
+    }
+}
@@ -0,0 +1,172 @@
+use std::path::{Component, Path};
+
+use serde_json::{Value, json};
+
+use crate::report::{Diagnostic, DiagnosticKind, Severity};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Builds a SARIF 2.1.0 log with one result per finding.
+pub fn sarif_log(diagnostics: &[Diagnostic]) -> Value {
+    let rules: Vec<Value> = DiagnosticKind::ALL
+        .iter()
+        .map(|kind| {
+            json!({
+                "id": kind.code(),
+                "name": kind.id(),
+                "shortDescription": { "text": kind.description() },
+                "defaultConfiguration": { "level": level(kind.default_severity()) },
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = diagnostics
+        .iter()
+        .filter_map(|d| d.kind.map(|kind| sarif_result(d, kind)))
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "sqint",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            // Columns count chars, as in the other output formats
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    })
+}
+
+fn sarif_result(d: &Diagnostic, kind: DiagnosticKind) -> Value {
+    let rule_index = DiagnosticKind::ALL
+        .iter()
+        .position(|k| *k == kind)
+        .expect("Every kind is listed in ALL");
+
+    // The region of a notebook finding is within its cell
+    let mut properties = json!({ "name": d.location.name, "dialect": d.dialect });
+    if let Some(cell) = d.location.cell {
+        properties["cell"] = json!(cell);
+    }
+
+    json!({
+        "ruleId": kind.code(),
+        "ruleIndex": rule_index,
+        "level": level(d.severity),
+        "message": { "text": d.reason.as_deref().unwrap_or(kind.description()) },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": {
+                    "uri": artifact_uri(&d.location.file),
+                    "uriBaseId": "%SRCROOT%",
+                },
+                "region": {
+                    "startLine": d.location.line,
+                    "startColumn": d.location.col,
+                    "snippet": { "text": d.source },
+                },
+            },
+        }],
+        "properties": properties,
+    })
+}
+
+const fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "note",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
+
+// Reported paths start with the name of the working directory, SARIF wants them relative to it.
+fn artifact_uri(file: &str) -> String {
+    let mut components = Path::new(file).components();
+    if !matches!(components.next(), Some(Component::Normal(_))) {
+        return file.replace('\\', "/");
+    }
+    components
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::Location;
+
+    fn diagnostic(kind: Option<DiagnosticKind>) -> Diagnostic {
+        Diagnostic {
+            location: Location {
+                file: "project/app/db.py".to_string(),
+                cell: None,
+                line: 7,
+                col: 14,
+                name: "query".to_string(),
+            },
+            sql: "SELECT * FORM PLACEHOLDER".to_string(),
+            source: "f\"SELECT * FORM {table}\"".to_string(),
+            dialect: "generic",
+            severity: if kind.is_some() {
+                Severity::Error
+            } else {
+                Severity::Info
+            },
+            kind,
+            reason: kind.map(|_| "Expected: end of statement, found: PLACEHOLDER".to_string()),
+        }
+    }
+
+    #[test]
+    fn result_for_each_finding() {
+        let log = sarif_log(&[
+            diagnostic(Some(DiagnosticKind::SyntaxError)),
+            diagnostic(None),
+        ]);
+
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(log["runs"][0]["columnKind"], "unicodeCodePoints");
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0],
+            json!({
+                "ruleId": "SQ001",
+                "ruleIndex": 0,
+                "level": "error",
+                "message": { "text": "Expected: end of statement, found: PLACEHOLDER" },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "app/db.py", "uriBaseId": "%SRCROOT%" },
+                        "region": {
+                            "startLine": 7,
+                            "startColumn": 14,
+                            "snippet": { "text": "f\"SELECT * FORM {table}\"" },
+                        },
+                    },
+                }],
+                "properties": { "name": "query", "dialect": "generic" },
+            })
+        );
+    }
+
+    #[test]
+    fn rules_describe_every_kind() {
+        let log = sarif_log(&[]);
+        let rules = log["runs"][0]["tool"]["driver"]["rules"]
+            .as_array()
+            .unwrap();
+        assert_eq!(rules.len(), DiagnosticKind::ALL.len());
+        assert_eq!(rules[0]["id"], "SQ001");
+        assert_eq!(rules[0]["name"], "syntax-error");
+        assert!(log["runs"][0]["results"].as_array().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,1007 @@
+use std::collections::HashMap;
+use std::fs;
+
+use logging::{error, warn};
+use sqlparser::ast::{
+    AlterTableOperation, AssignmentTarget, CreateTable, Delete, Expr, FromTable, FunctionArg,
+    FunctionArgExpr, FunctionArguments, GroupByExpr, Ident, Insert, JoinConstraint, JoinOperator,
+    ObjectName, ObjectNamePart, ObjectType, OrderBy, OrderByKind, Query, Select, SelectItem,
+    SelectItemQualifiedWildcardKind, SetExpr, Spanned, Statement, TableAlias, TableFactor,
+    TableObject, TableWithJoins, UpdateTableFromKind,
+};
+use sqlparser::dialect::Dialect;
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Span, Token, Tokenizer};
+
+use crate::analyzer::PLACEHOLDER;
+use crate::report::DiagnosticKind;
+use crate::rules::Violation;
+
+// Relations every database provides without them being declared in the schema files
+const SYSTEM_SCHEMAS: [&str; 5] = [
+    "information_schema",
+    "pg_catalog",
+    "sys",
+    "mysql",
+    "performance_schema",
+];
+const SYSTEM_TABLES: [&str; 1] = ["dual"];
+const SYSTEM_TABLE_PREFIXES: [&str; 2] = ["pg_", "sqlite_"];
+
+// Date parts some dialects take as bare words in function calls, e.g. `DATEADD(day, 1, col)`
+const DATE_PARTS: [&str; 19] = [
+    "year",
+    "quarter",
+    "month",
+    "week",
+    "day",
+    "hour",
+    "minute",
+    "second",
+    "millisecond",
+    "microsecond",
+    "nanosecond",
+    "epoch",
+    "dow",
+    "doy",
+    "isoweek",
+    "isoyear",
+    "dayofweek",
+    "dayofyear",
+    "weekday",
+];
+
+/// Column names of a relation, `None` when they can't be known statically
+type Columns = Option<Vec<String>>;
+
+/// Tables and views declared in the configured schema files
+#[derive(Debug, Default)]
+pub struct Catalog {
+    // Keyed by the lowercased unqualified name, identifiers are compared case insensitively
+    relations: HashMap<String, Columns>,
+}
+
+impl Catalog {
+    /// Builds the catalog from DDL files, statements that can't be parsed are skipped.
+    pub fn load(paths: &[String], dialect: &dyn Dialect) -> Self {
+        let mut catalog = Self::default();
+        for path in paths {
+            match fs::read_to_string(path) {
+                Ok(ddl) => catalog.add_ddl(&ddl, dialect, path),
+                Err(e) => error!("Failed to read schema file '{path}': {e}"),
+            }
+        }
+        catalog
+    }
+
+    pub fn len(&self) -> usize {
+        self.relations.len()
+    }
+
+    /// Checks a parsed statement against the catalog.
+    pub fn check(&self, statement: &Statement) -> Vec<Violation> {
+        let mut checker = Checker::new(self);
+        checker.statement(statement);
+        checker.issues
+    }
+
+    pub(crate) fn add_ddl(&mut self, ddl: &str, dialect: &dyn Dialect, path: &str) {
+        let tokens = match Tokenizer::new(dialect, ddl).tokenize_with_location() {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                error!("Failed to read schema file '{path}': {e}");
+                return;
+            }
+        };
+
+        // Statements are parsed one by one, so a single unsupported one doesn't drop the file
+        for statement in tokens.split(|t| t.token == Token::SemiColon) {
+            if statement
+                .iter()
+                .all(|t| matches!(t.token, Token::Whitespace(_)))
+            {
+                continue;
+            }
+            match Parser::new(dialect)
+                .with_tokens_with_locations(statement.to_vec())
+                .parse_statements()
+            {
+                Ok(statements) => statements.iter().for_each(|s| self.apply(s)),
+                Err(e) => warn!("Skipping statement in schema file '{path}': {e}"),
+            }
+        }
+    }
+
+    fn apply(&mut self, statement: &Statement) {
+        match statement {
+            Statement::CreateTable(CreateTable {
+                name,
+                columns,
+                query,
+                like,
+                clone,
+                ..
+            }) => {
+                let columns = if !columns.is_empty() {
+                    Some(columns.iter().map(|c| normalize(&c.name)).collect())
+                } else if let Some(query) = query {
+                    Checker::new(self).query(query, None)
+                } else {
+                    like.as_ref()
+                        .or(clone.as_ref())
+                        .and_then(|source| self.relations.get(&relation_key(source)).cloned())
+                        .flatten()
+                };
+                self.relations.insert(relation_key(name), columns);
+            }
+            Statement::CreateView {
+                name,
+                columns,
+                query,
+                ..
+            } => {
+                let columns = if columns.is_empty() {
+                    Checker::new(self).query(query, None)
+                } else {
+                    Some(columns.iter().map(|c| normalize(&c.name)).collect())
+                };
+                self.relations.insert(relation_key(name), columns);
+            }
+            Statement::AlterTable {
+                name, operations, ..
+            } => self.alter(relation_key(name), operations),
+            Statement::Drop {
+                object_type: ObjectType::Table | ObjectType::View | ObjectType::MaterializedView,
+                names,
+                ..
+            } => {
+                for name in names {
+                    self.relations.remove(&relation_key(name));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn alter(&mut self, mut key: String, operations: &[AlterTableOperation]) {
+        for operation in operations {
+            if let AlterTableOperation::RenameTable { table_name } = operation {
+                if let Some(columns) = self.relations.remove(&key) {
+                    key = relation_key(table_name);
+                    self.relations.insert(key.clone(), columns);
+                }
+                continue;
+            }
+
+            let Some(Some(columns)) = self.relations.get_mut(&key) else {
+                continue;
+            };
+            match operation {
+                AlterTableOperation::AddColumn { column_def, .. } => {
+                    columns.push(normalize(&column_def.name));
+                }
+                AlterTableOperation::DropColumn { column_name, .. } => {
+                    columns.retain(|c| *c != normalize(column_name));
+                }
+                AlterTableOperation::RenameColumn {
+                    old_column_name: old,
+                    new_column_name: new,
+                }
+                | AlterTableOperation::ChangeColumn {
+                    old_name: old,
+                    new_name: new,
+                    ..
+                } => {
+                    let old = normalize(old);
+                    if let Some(column) = columns.iter_mut().find(|c| **c == old) {
+                        *column = normalize(new);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Names visible at one level of a (possibly nested) query.
+#[derive(Default)]
+struct Scope<'p> {
+    parent: Option<&'p Scope<'p>>,
+    // Relations of the FROM clause by alias or name
+    relations: Vec<(String, Columns)>,
+    ctes: HashMap<String, Columns>,
+    // Output names of the SELECT list, ORDER BY and some dialects' WHERE may refer to them
+    aliases: Vec<String>,
+    // Columns merged by `JOIN .. USING`, which are not ambiguous
+    shared: Vec<String>,
+    natural_join: bool,
+}
+
+impl<'p> Scope<'p> {
+    fn child(parent: Option<&'p Scope<'p>>) -> Self {
+        Self {
+            parent,
+            ..Self::default()
+        }
+    }
+
+    fn chain(&self) -> impl Iterator<Item = &Scope<'p>> {
+        std::iter::successors(Some(self), |scope| scope.parent)
+    }
+
+    fn cte(&self, name: &str) -> Option<&Columns> {
+        self.chain().find_map(|scope| scope.ctes.get(name))
+    }
+}
+
+struct Checker<'c> {
+    catalog: &'c Catalog,
+    issues: Vec<Violation>,
+}
+
+impl<'c> Checker<'c> {
+    const fn new(catalog: &'c Catalog) -> Self {
+        Self {
+            catalog,
+            issues: Vec::new(),
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Query(query) => {
+                self.query(query, None);
+            }
+            Statement::Insert(insert) => self.insert(insert),
+            Statement::Update {
+                table,
+                assignments,
+                from,
+                selection,
+                ..
+            } => {
+                let mut scope = Scope {
+                    relations: self.table_with_joins(table, &Scope::default()),
+                    ..Scope::default()
+                };
+                if let Some(
+                    UpdateTableFromKind::BeforeSet(tables) | UpdateTableFromKind::AfterSet(tables),
+                ) = from
+                {
+                    for table in tables {
+                        let relations = self.table_with_joins(table, &Scope::default());
+                        scope.relations.extend(relations);
+                    }
+                }
+
+                for assignment in assignments {
+                    let targets = match &assignment.target {
+                        AssignmentTarget::ColumnName(name) => std::slice::from_ref(name),
+                        AssignmentTarget::Tuple(names) => names.as_slice(),
+                    };
+                    for target in targets {
+                        self.assignment_target(target, &scope);
+                    }
+                    self.expr(&assignment.value, &scope);
+                }
+                if let Some(selection) = selection {
+                    self.expr(selection, &scope);
+                }
+            }
+            Statement::Delete(delete) => self.delete(delete),
+            _ => {}
+        }
+    }
+
+    /// Checks a query and returns the names of the columns it produces.
+    fn query(&mut self, query: &Query, outer: Option<&Scope>) -> Columns {
+        let mut scope = Scope::child(outer);
+        if let Some(with) = &query.with {
+            for cte in &with.cte_tables {
+                let name = normalize(&cte.alias.name);
+                if with.recursive {
+                    scope.ctes.insert(name.clone(), None);
+                }
+                let columns = self.query(&cte.query, Some(&scope));
+                scope
+                    .ctes
+                    .insert(name, alias_columns(&cte.alias).or(columns));
+            }
+        }
+        self.set_expr(&query.body, &scope, query.order_by.as_ref())
+    }
+
+    fn set_expr(&mut self, body: &SetExpr, scope: &Scope, order_by: Option<&OrderBy>) -> Columns {
+        match body {
+            SetExpr::Select(select) => self.select(select, scope, order_by),
+            SetExpr::Query(query) => self.query(query, Some(scope)),
+            SetExpr::SetOperation { left, right, .. } => {
+                let columns = self.set_expr(left, scope, None);
+                self.set_expr(right, scope, None);
+                columns
+            }
+            SetExpr::Insert(statement)
+            | SetExpr::Update(statement)
+            | SetExpr::Delete(statement) => {
+                self.statement(statement);
+                None
+            }
+            SetExpr::Values(_) | SetExpr::Table(_) => None,
+        }
+    }
+
+    fn select(&mut self, select: &Select, outer: &Scope, order_by: Option<&OrderBy>) -> Columns {
+        let mut scope = Scope::child(Some(outer));
+        for table in &select.from {
+            let relations = self.table_with_joins(table, outer);
+            scope.relations.extend(relations);
+        }
+
+        let constraints: Vec<&JoinConstraint> = select
+            .from
+            .iter()
+            .flat_map(|table| &table.joins)
+            .filter_map(|join| join_constraint(&join.join_operator))
+            .collect();
+        for constraint in &constraints {
+            match constraint {
+                JoinConstraint::Using(names) => scope.shared.extend(names.iter().map(relation_key)),
+                JoinConstraint::Natural => scope.natural_join = true,
+                JoinConstraint::On(_) | JoinConstraint::None => {}
+            }
+        }
+
+        for item in &select.projection {
+            if let SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } = item {
+                self.expr(expr, &scope);
+            }
+        }
+        scope.aliases = select
+            .projection
+            .iter()
+            .filter_map(|item| match item {
+                SelectItem::ExprWithAlias { alias, .. } => Some(normalize(alias)),
+                _ => None,
+            })
+            .collect();
+
+        let group_by = match &select.group_by {
+            GroupByExpr::Expressions(exprs, _) => exprs.as_slice(),
+            GroupByExpr::All(_) => &[],
+        };
+        let order_by = match order_by.map(|o| &o.kind) {
+            Some(OrderByKind::Expressions(exprs)) => exprs.as_slice(),
+            _ => &[],
+        };
+        let clauses = constraints
+            .iter()
+            .filter_map(|constraint| match constraint {
+                JoinConstraint::On(expr) => Some(expr),
+                _ => None,
+            })
+            .chain(&select.selection)
+            .chain(group_by)
+            .chain(&select.having)
+            .chain(&select.qualify)
+            .chain(order_by.iter().map(|o| &o.expr));
+        for expr in clauses {
+            self.expr(expr, &scope);
+        }
+
+        projection_columns(&select.projection, &scope)
+    }
+
+    fn table_with_joins(
+        &mut self,
+        table: &TableWithJoins,
+        outer: &Scope,
+    ) -> Vec<(String, Columns)> {
+        let mut relations = self.table_factor(&table.relation, outer);
+        for join in &table.joins {
+            let joined = self.table_factor(&join.relation, outer);
+            relations.extend(joined);
+        }
+        relations
+    }
+
+    fn table_factor(&mut self, factor: &TableFactor, outer: &Scope) -> Vec<(String, Columns)> {
+        match factor {
+            TableFactor::Table {
+                name,
+                alias,
+                args: None,
+                ..
+            } => {
+                let columns = self.relation(name, outer);
+                vec![(
+                    alias
+                        .as_ref()
+                        .map_or_else(|| relation_key(name), |a| normalize(&a.name)),
+                    alias.as_ref().and_then(alias_columns).or(columns),
+                )]
+            }
+            TableFactor::Derived {
+                subquery, alias, ..
+            } => {
+                let columns = self.query(subquery, Some(outer));
+                vec![(
+                    alias
+                        .as_ref()
+                        .map_or_else(String::new, |a| normalize(&a.name)),
+                    alias.as_ref().and_then(alias_columns).or(columns),
+                )]
+            }
+            TableFactor::NestedJoin {
+                table_with_joins, ..
+            } => self.table_with_joins(table_with_joins, outer),
+            // Table functions, UNNEST and the like produce columns nothing is known about
+            _ => vec![(String::new(), None)],
+        }
+    }
+
+    /// Columns of the named table, view or CTE, reporting it when it doesn't exist.
+    fn relation(&mut self, name: &ObjectName, scope: &Scope) -> Columns {
+        let key = relation_key(name);
+        if name.0.len() == 1
+            && let Some(columns) = scope.cte(&key)
+        {
+            return columns.clone();
+        }
+        if let Some(columns) = self.catalog.relations.get(&key) {
+            return columns.clone();
+        }
+
+        let ident = last_ident(name);
+        if !ident.is_none_or(is_placeholder) && !is_system_relation(name) {
+            self.issue(
+                DiagnosticKind::UnknownTable,
+                format!("Unknown table `{name}`"),
+                ident.map_or_else(Span::empty, |i| i.span),
+            );
+        }
+        None
+    }
+
+    fn insert(&mut self, insert: &Insert) {
+        let TableObject::TableName(name) = &insert.table else {
+            return;
+        };
+        let columns = self.relation(name, &Scope::default());
+
+        if let Some(table_columns) = &columns {
+            let targets = insert
+                .columns
+                .iter()
+                .chain(insert.assignments.iter().filter_map(|a| match &a.target {
+                    AssignmentTarget::ColumnName(column) => last_ident(column),
+                    AssignmentTarget::Tuple(_) => None,
+                }));
+            for column in targets {
+                if !is_placeholder(column) && !table_columns.contains(&normalize(column)) {
+                    self.issue(
+                        DiagnosticKind::UnknownColumn,
+                        format!("Unknown column `{}` in `{name}`", column.value),
+                        column.span,
+                    );
+                }
+            }
+        }
+
+        let Some(source) = &insert.source else {
+            return;
+        };
+        let expected = if insert.columns.is_empty() {
+            columns.as_ref().map(Vec::len)
+        } else {
+            Some(insert.columns.len())
+        };
+        let target = if insert.columns.is_empty() {
+            "has"
+        } else {
+            "lists"
+        };
+        // Columns left out of an INSERT without a column list take their defaults
+        let mismatched = |given: usize, expected: usize| {
+            given > expected || (given < expected && !insert.columns.is_empty())
+        };
+
+        if let SetExpr::Values(values) = source.body.as_ref() {
+            let Some(expected) = expected else {
+                return;
+            };
+            for row in values
+                .rows
+                .iter()
+                .filter(|row| mismatched(row.len(), expected))
+            {
+                let span = row.first().map_or_else(Span::empty, Spanned::span);
+                self.issue(
+                    DiagnosticKind::InsertArity,
+                    format!(
+                        "INSERT into `{name}` {target} {expected} columns but {} values are given",
+                        row.len()
+                    ),
+                    span,
+                );
+            }
+        } else if let (Some(expected), Some(produced)) = (expected, self.query(source, None))
+            && mismatched(produced.len(), expected)
+        {
+            self.issue(
+                DiagnosticKind::InsertArity,
+                format!(
+                    "INSERT into `{name}` {target} {expected} columns but the query selects {}",
+                    produced.len()
+                ),
+                source.body.span(),
+            );
+        }
+    }
+
+    fn delete(&mut self, delete: &Delete) {
+        let (FromTable::WithFromKeyword(tables) | FromTable::WithoutKeyword(tables)) = &delete.from;
+        let mut scope = Scope::default();
+        for table in tables.iter().chain(delete.using.iter().flatten()) {
+            let relations = self.table_with_joins(table, &Scope::default());
+            scope.relations.extend(relations);
+        }
+        if let Some(selection) = &delete.selection {
+            self.expr(selection, &scope);
+        }
+    }
+
+    fn assignment_target(&mut self, target: &ObjectName, scope: &Scope) {
+        let idents: Vec<&Ident> = target
+            .0
+            .iter()
+            .filter_map(ObjectNamePart::as_ident)
+            .collect();
+        match idents.as_slice() {
+            // Unqualified targets always belong to the updated table, which comes first
+            [column] => {
+                if let Some((table, Some(columns))) = scope.relations.first()
+                    && !is_placeholder(column)
+                    && !columns.contains(&normalize(column))
+                {
+                    self.issue(
+                        DiagnosticKind::UnknownColumn,
+                        format!("Unknown column `{}` in `{table}`", column.value),
+                        column.span,
+                    );
+                }
+            }
+            [.., qualifier, column] => self.column(Some(qualifier), column, scope),
+            [] => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, scope: &Scope) {
+        match expr {
+            Expr::Identifier(ident) => self.column(None, ident, scope),
+            Expr::CompoundIdentifier(idents) => {
+                if let [.., qualifier, column] = idents.as_slice() {
+                    self.column(Some(qualifier), column, scope);
+                }
+            }
+            Expr::IsFalse(e)
+            | Expr::IsNotFalse(e)
+            | Expr::IsTrue(e)
+            | Expr::IsNotTrue(e)
+            | Expr::IsNull(e)
+            | Expr::IsNotNull(e)
+            | Expr::IsUnknown(e)
+            | Expr::IsNotUnknown(e)
+            | Expr::Nested(e)
+            | Expr::UnaryOp { expr: e, .. }
+            | Expr::Cast { expr: e, .. }
+            | Expr::Collate { expr: e, .. }
+            | Expr::Extract { expr: e, .. }
+            | Expr::Ceil { expr: e, .. }
+            | Expr::Floor { expr: e, .. } => self.expr(e, scope),
+            Expr::IsDistinctFrom(left, right)
+            | Expr::IsNotDistinctFrom(left, right)
+            | Expr::BinaryOp { left, right, .. }
+            | Expr::AnyOp { left, right, .. }
+            | Expr::AllOp { left, right, .. }
+            | Expr::Like {
+                expr: left,
+                pattern: right,
+                ..
+            }
+            | Expr::ILike {
+                expr: left,
+                pattern: right,
+                ..
+            }
+            | Expr::SimilarTo {
+                expr: left,
+                pattern: right,
+                ..
+            }
+            | Expr::AtTimeZone {
+                timestamp: left,
+                time_zone: right,
+            } => {
+                self.expr(left, scope);
+                self.expr(right, scope);
+            }
+            Expr::Between {
+                expr, low, high, ..
+            } => {
+                self.expr(expr, scope);
+                self.expr(low, scope);
+                self.expr(high, scope);
+            }
+            Expr::InList { expr, list, .. } => {
+                self.expr(expr, scope);
+                list.iter().for_each(|e| self.expr(e, scope));
+            }
+            Expr::InSubquery { expr, subquery, .. } => {
+                self.expr(expr, scope);
+                self.set_expr(subquery, scope, None);
+            }
+            Expr::Tuple(exprs) => exprs.iter().for_each(|e| self.expr(e, scope)),
+            Expr::Case {
+                operand,
+                conditions,
+                else_result,
+            } => {
+                let whens = conditions.iter().flat_map(|c| [&c.condition, &c.result]);
+                for e in operand.iter().map(AsRef::as_ref).chain(whens) {
+                    self.expr(e, scope);
+                }
+                if let Some(e) = else_result {
+                    self.expr(e, scope);
+                }
+            }
+            Expr::Exists { subquery, .. } | Expr::Subquery(subquery) => {
+                self.query(subquery, Some(scope));
+            }
+            Expr::Function(function) => self.function_args(&function.args, scope),
+            _ => {}
+        }
+    }
+
+    fn function_args(&mut self, args: &FunctionArguments, scope: &Scope) {
+        match args {
+            FunctionArguments::List(list) => {
+                for arg in &list.args {
+                    let (FunctionArg::Unnamed(FunctionArgExpr::Expr(e))
+                    | FunctionArg::Named {
+                        arg: FunctionArgExpr::Expr(e),
+                        ..
+                    }
+                    | FunctionArg::ExprNamed {
+                        arg: FunctionArgExpr::Expr(e),
+                        ..
+                    }) = arg
+                    else {
+                        continue;
+                    };
+                    if let Expr::Identifier(ident) = e
+                        && DATE_PARTS.contains(&normalize(ident).as_str())
+                    {
+                        continue;
+                    }
+                    self.expr(e, scope);
+                }
+            }
+            FunctionArguments::Subquery(query) => {
+                self.query(query, Some(scope));
+            }
+            FunctionArguments::None => {}
+        }
+    }
+
+    fn column(&mut self, qualifier: Option<&Ident>, column: &Ident, scope: &Scope) {
+        if is_placeholder(column) {
+            return;
+        }
+        let name = normalize(column);
+
+        if let Some(qualifier) = qualifier {
+            let alias = normalize(qualifier);
+            let relation = scope
+                .chain()
+                .find_map(|s| s.relations.iter().find(|(a, _)| *a == alias));
+            // Unresolved qualifiers may be schemas or struct fields, they are left alone
+            if let Some((_, Some(columns))) = relation
+                && !columns.contains(&name)
+            {
+                self.issue(
+                    DiagnosticKind::UnknownColumn,
+                    format!("Unknown column `{}.{}`", qualifier.value, column.value),
+                    column.span,
+                );
+            }
+            return;
+        }
+
+        for s in scope.chain() {
+            if s.aliases.contains(&name) {
+                return;
+            }
+            let owners: Vec<&str> = s
+                .relations
+                .iter()
+                .filter(|(_, columns)| columns.as_ref().is_some_and(|c| c.contains(&name)))
+                .map(|(alias, _)| alias.as_str())
+                .collect();
+            if owners.len() > 1 && !s.natural_join && !s.shared.contains(&name) {
+                self.issue(
+                    DiagnosticKind::AmbiguousColumn,
+                    format!(
+                        "Ambiguous column `{}`, it exists in `{}`",
+                        column.value,
+                        owners.join("`, `")
+                    ),
+                    column.span,
+                );
+                return;
+            }
+            if !owners.is_empty() || s.relations.iter().any(|(_, c)| c.is_none()) {
+                return;
+            }
+        }
+
+        if scope.chain().any(|s| !s.relations.is_empty()) {
+            self.issue(
+                DiagnosticKind::UnknownColumn,
+                format!("Unknown column `{}`", column.value),
+                column.span,
+            );
+        }
+    }
+
+    fn issue(&mut self, kind: DiagnosticKind, reason: String, span: Span) {
+        self.issues.push(Violation::new(kind, reason, span));
+    }
+}
+
+/// Names of the columns a SELECT list produces, `None` if any of them can't be named.
+fn projection_columns(projection: &[SelectItem], scope: &Scope) -> Columns {
+    let mut columns = Vec::new();
+    for item in projection {
+        match item {
+            SelectItem::ExprWithAlias { alias, .. } => columns.push(normalize(alias)),
+            SelectItem::UnnamedExpr(Expr::Identifier(ident)) => columns.push(normalize(ident)),
+            SelectItem::UnnamedExpr(Expr::CompoundIdentifier(idents)) => {
+                columns.push(normalize(idents.last()?));
+            }
+            SelectItem::Wildcard(_) => {
+                for (_, relation) in &scope.relations {
+                    columns.extend(relation.clone()?);
+                }
+            }
+            SelectItem::QualifiedWildcard(SelectItemQualifiedWildcardKind::ObjectName(name), _) => {
+                let alias = relation_key(name);
+                let (_, relation) = scope.relations.iter().find(|(a, _)| *a == alias)?;
+                columns.extend(relation.clone()?);
+            }
+            _ => return None,
+        }
+    }
+    Some(columns)
+}
+
+const fn join_constraint(operator: &JoinOperator) -> Option<&JoinConstraint> {
+    match operator {
+        JoinOperator::Join(c)
+        | JoinOperator::Inner(c)
+        | JoinOperator::Left(c)
+        | JoinOperator::LeftOuter(c)
+        | JoinOperator::Right(c)
+        | JoinOperator::RightOuter(c)
+        | JoinOperator::FullOuter(c)
+        | JoinOperator::Semi(c)
+        | JoinOperator::LeftSemi(c)
+        | JoinOperator::RightSemi(c)
+        | JoinOperator::Anti(c)
+        | JoinOperator::LeftAnti(c)
+        | JoinOperator::RightAnti(c)
+        | JoinOperator::StraightJoin(c)
+        | JoinOperator::AsOf { constraint: c, .. } => Some(c),
+        JoinOperator::CrossJoin | JoinOperator::CrossApply | JoinOperator::OuterApply => None,
+    }
+}
+
+fn alias_columns(alias: &TableAlias) -> Columns {
+    (!alias.columns.is_empty()).then(|| alias.columns.iter().map(|c| normalize(&c.name)).collect())
+}
+
+fn normalize(ident: &Ident) -> String {
+    ident.value.to_lowercase()
+}
+
+fn last_ident(name: &ObjectName) -> Option<&Ident> {
+    name.0.last().and_then(ObjectNamePart::as_ident)
+}
+
+fn relation_key(name: &ObjectName) -> String {
+    last_ident(name).map(normalize).unwrap_or_default()
+}
+
+// Dynamic parts of the SQL, e.g. an interpolated table name, can't be checked
+fn is_placeholder(ident: &Ident) -> bool {
+    ident.value == PLACEHOLDER
+}
+
+fn is_system_relation(name: &ObjectName) -> bool {
+    let parts: Vec<String> = name
+        .0
+        .iter()
+        .filter_map(ObjectNamePart::as_ident)
+        .map(normalize)
+        .collect();
+    let Some((table, schemas)) = parts.split_last() else {
+        return false;
+    };
+    schemas.iter().any(|s| SYSTEM_SCHEMAS.contains(&s.as_str()))
+        || SYSTEM_TABLES.contains(&table.as_str())
+        || SYSTEM_TABLE_PREFIXES.iter().any(|p| table.starts_with(p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::dialect::GenericDialect;
+
+    const SCHEMA: &str = "
+        CREATE TABLE users (id INT PRIMARY KEY, name TEXT, email TEXT);
+        CREATE TABLE orders (id INT, user_id INT, total DECIMAL(10, 2));
+        CREATE VIEW big_orders AS SELECT o.id, o.total AS amount FROM orders o WHERE total > 100;
+        CREATE FUNCTION unsupported() RETURNS INT AS 'SELECT 1' LANGUAGE SQL IMMUTABLE NONSENSE;
+        ALTER TABLE users RENAME COLUMN email TO mail;
+        ALTER TABLE users ADD COLUMN created_at TIMESTAMP;
+        CREATE TABLE legacy (id INT);
+        DROP TABLE legacy;
+    ";
+
+    fn catalog() -> Catalog {
+        let mut catalog = Catalog::default();
+        catalog.add_ddl(SCHEMA, &GenericDialect {}, "schema.sql");
+        catalog
+    }
+
+    type Issue = (DiagnosticKind, String, Option<(u64, u64)>);
+
+    fn issues(sql: &str) -> Vec<Issue> {
+        let statements = Parser::parse_sql(&GenericDialect {}, sql).unwrap();
+        let catalog = catalog();
+        statements
+            .iter()
+            .flat_map(|s| catalog.check(s))
+            .map(|i| (i.kind, i.reason, i.location))
+            .collect()
+    }
+
+    #[test]
+    fn catalog_follows_ddl() {
+        let catalog = catalog();
+        assert_eq!(catalog.len(), 3);
+        assert_eq!(
+            catalog.relations["users"],
+            Some(vec![
+                "id".to_string(),
+                "name".to_string(),
+                "mail".to_string(),
+                "created_at".to_string()
+            ])
+        );
+        assert_eq!(
+            catalog.relations["big_orders"],
+            Some(vec!["id".to_string(), "amount".to_string()])
+        );
+    }
+
+    #[test]
+    fn known_columns_pass() {
+        assert!(
+            issues(
+                "SELECT u.id, name, mail FROM users u WHERE created_at > PLACEHOLDER ORDER BY id"
+            )
+            .is_empty()
+        );
+        assert!(
+            issues("SELECT amount, COUNT(*) AS n FROM big_orders GROUP BY amount HAVING n > 1")
+                .is_empty()
+        );
+        assert!(issues("UPDATE users SET name = PLACEHOLDER WHERE id = PLACEHOLDER").is_empty());
+        assert!(issues("DELETE FROM orders WHERE user_id IN (SELECT id FROM users)").is_empty());
+    }
+
+    #[test]
+    fn unknown_table() {
+        assert_eq!(
+            issues("SELECT id FROM user_accounts"),
+            vec![(
+                DiagnosticKind::UnknownTable,
+                "Unknown table `user_accounts`".to_string(),
+                Some((1, 16))
+            )]
+        );
+        assert!(issues("SELECT * FROM information_schema.tables").is_empty());
+        assert!(issues("SELECT * FROM PLACEHOLDER WHERE anything = 1").is_empty());
+        assert!(issues("SELECT * FROM legacy").len() == 1);
+    }
+
+    #[test]
+    fn unknown_column() {
+        assert_eq!(
+            issues("SELECT id, email\nFROM users"),
+            vec![(
+                DiagnosticKind::UnknownColumn,
+                "Unknown column `email`".to_string(),
+                Some((1, 12))
+            )]
+        );
+        assert_eq!(
+            issues("SELECT o.amount FROM orders o")[0].1,
+            "Unknown column `o.amount`"
+        );
+        assert_eq!(
+            issues("UPDATE users SET email = PLACEHOLDER")[0].1,
+            "Unknown column `email` in `users`"
+        );
+    }
+
+    #[test]
+    fn ambiguous_column() {
+        assert_eq!(
+            issues("SELECT name FROM users u JOIN orders o ON o.user_id = u.id WHERE id = 1"),
+            vec![(
+                DiagnosticKind::AmbiguousColumn,
+                "Ambiguous column `id`, it exists in `u`, `o`".to_string(),
+                Some((1, 66))
+            )]
+        );
+        assert!(issues("SELECT id FROM users JOIN orders USING (id)").is_empty());
+    }
+
+    #[test]
+    fn insert_arity() {
+        assert_eq!(
+            issues("INSERT INTO users (id, name) VALUES (1, 'a'), (2, 'b', 'c')"),
+            vec![(
+                DiagnosticKind::InsertArity,
+                "INSERT into `users` lists 2 columns but 3 values are given".to_string(),
+                Some((1, 48))
+            )]
+        );
+        assert_eq!(
+            issues("INSERT INTO orders VALUES (1, 2, 3, 4)")[0].1,
+            "INSERT into `orders` has 3 columns but 4 values are given"
+        );
+        assert!(issues("INSERT INTO orders VALUES (1, 2)").is_empty());
+        assert!(issues("INSERT INTO orders SELECT id FROM users").is_empty());
+        assert_eq!(
+            issues("INSERT INTO orders (id, user_id, total) VALUES (1, 2)")[0].1,
+            "INSERT into `orders` lists 3 columns but 2 values are given"
+        );
+        assert_eq!(
+            issues("INSERT INTO orders (id, user_id) SELECT id, name, mail FROM users")[0].1,
+            "INSERT into `orders` lists 2 columns but the query selects 3"
+        );
+        assert_eq!(
+            issues("INSERT INTO users (id, email) VALUES (1, 'a')")[0].1,
+            "Unknown column `email` in `users`"
+        );
+    }
+
+    #[test]
+    fn nested_scopes() {
+        assert!(
+            issues(
+                "WITH totals AS (SELECT user_id, SUM(total) AS spent FROM orders GROUP BY user_id)
+                 SELECT u.name, t.spent FROM users u JOIN totals t ON t.user_id = u.id
+                 WHERE EXISTS (SELECT 1 FROM orders WHERE orders.user_id = u.id AND total > 5)"
+            )
+            .is_empty()
+        );
+        assert_eq!(
+            issues("SELECT s.missing FROM (SELECT id FROM users) s")[0].1,
+            "Unknown column `s.missing`"
+        );
+        assert!(issues("SELECT anything FROM generate_series(1, 10)").is_empty());
+    }
+}
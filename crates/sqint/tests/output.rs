@@ -0,0 +1,59 @@
+//! Runs the binary to check that machine readable results are all that's written to stdout.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Project without a config file, with one valid and one invalid SQL string
+fn project(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sqint-output-{name}-{}", std::process::id()));
+    // The repository root stops the search for config files above the project
+    fs::create_dir_all(dir.join(".git")).unwrap();
+    fs::write(
+        dir.join("db.py"),
+        "query = \"SELECT id FROM users\"\nother_query = \"SELECT id FORM users\"\n",
+    )
+    .unwrap();
+    dir
+}
+
+fn stdout(dir: &PathBuf, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_sqint"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn json_lines_on_stdout() {
+    let dir = project("json");
+    let out = stdout(&dir, &["--output-format", "json"]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    let records: Vec<serde_json::Value> = out
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("{e}: {line}")))
+        .collect();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[2]["type"], "summary");
+    assert_eq!(records[2]["sql_errors"], 1);
+}
+
+#[test]
+fn sarif_log_on_stdout() {
+    let dir = project("sarif");
+    let out = stdout(&dir, &["check", "--output-format", "sarif"]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    let log: serde_json::Value =
+        serde_json::from_str(&out).unwrap_or_else(|e| panic!("{e}: {out}"));
+    assert_eq!(log["version"], "2.1.0");
+    let results = log["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(
+        results[0]["locations"][0]["physicalLocation"]["region"]["snippet"]["text"],
+        "\"SELECT id FORM users\""
+    );
+}